@@ -0,0 +1,372 @@
+use std::{env, ffi::OsString, process::exit};
+
+// Exit status for command line usage errors, matching GNU ls
+const USAGE_EXIT_CODE: i32 = 2;
+
+// Built-in flags
+const HELP_ARG_NAME: &str = "help";
+const VERSION_ARG_NAME: &str = "version";
+
+// Reports a bad command line and exits, for errors found after parsing as well
+pub fn usage_error(app_name: &str, err: &str) -> ! {
+    eprintln!("{}: {}", app_name, err);
+    eprintln!("Try '{} --help' for more information.", app_name);
+    exit(USAGE_EXIT_CODE);
+}
+
+pub struct Arg {
+    name: &'static str,
+    short: Option<&'static str>,
+    long: Option<&'static str>,
+    takes_value: bool,
+    // The value can be left off, and is then only taken after '='
+    is_value_optional: bool,
+}
+
+impl Arg {
+    pub fn with_name(name: &'static str) -> Arg {
+        Arg {
+            name,
+            short: None,
+            long: None,
+            takes_value: false,
+            is_value_optional: false,
+        }
+    }
+
+    pub fn short(mut self, short: &'static str) -> Arg {
+        self.short = Some(short);
+        self
+    }
+
+    pub fn long(mut self, long: &'static str) -> Arg {
+        self.long = Some(long);
+        self
+    }
+
+    pub fn takes_value(mut self, takes_value: bool) -> Arg {
+        self.takes_value = takes_value;
+        self
+    }
+
+    // A flag that can be given on its own or with a value, like `--color`
+    // and `--color=never`
+    pub fn optional_value(mut self, is_value_optional: bool) -> Arg {
+        self.takes_value = is_value_optional;
+        self.is_value_optional = is_value_optional;
+        self
+    }
+
+    // An argument with neither a short nor a long flag is an operand
+    fn is_positional(&self) -> bool {
+        self.short.is_none() && self.long.is_none()
+    }
+
+    // The long flag defaults to the argument name
+    fn long_name(&self) -> &'static str {
+        self.long.unwrap_or(self.name)
+    }
+}
+
+// A single flag seen on the command line, in the order it was given
+struct Occurrence {
+    name: &'static str,
+    value: Option<String>,
+}
+
+pub struct Matches {
+    occurrences: Vec<Occurrence>,
+    // Kept as given, since paths needn't be UTF-8
+    operands: Vec<OsString>,
+}
+
+impl Matches {
+    pub fn is_present(&self, name: &str) -> bool {
+        self.occurrences.iter().any(|o| o.name == name)
+    }
+
+    pub fn value_of(&self, name: &str) -> Option<&str> {
+        self.occurrences
+            .iter()
+            .rev()
+            .find(|o| o.name == name)
+            .and_then(|o| o.value.as_deref())
+    }
+
+    // Every value given for a flag
+    pub fn values_of(&self, name: &str) -> Vec<&str> {
+        self.occurrences
+            .iter()
+            .filter(|o| o.name == name)
+            .filter_map(|o| o.value.as_deref())
+            .collect()
+    }
+
+    // Returns whichever of the given flags appeared last on the command line
+    pub fn last_of(&self, names: &[&str]) -> Option<&'static str> {
+        self.occurrences
+            .iter()
+            .rev()
+            .find(|o| names.contains(&o.name))
+            .map(|o| o.name)
+    }
+
+    // The operands for the positional argument, in the order given
+    pub fn operands(&self) -> &[OsString] {
+        &self.operands
+    }
+}
+
+// Flag values are read as text, so one that isn't UTF-8 is a usage error
+fn value_string(value: OsString, arg: &Arg) -> Result<String, String> {
+    value.into_string().map_err(|value| {
+        format!(
+            "invalid argument '{}' for '--{}'",
+            value.to_string_lossy(),
+            arg.long_name()
+        )
+    })
+}
+
+pub struct App {
+    name: &'static str,
+    about: &'static str,
+    author: &'static str,
+    version: &'static str,
+    args: Vec<Arg>,
+}
+
+impl App {
+    pub fn new(name: &'static str) -> App {
+        App {
+            name,
+            about: "",
+            author: "",
+            version: "",
+            args: vec![
+                Arg::with_name(HELP_ARG_NAME).short("h"),
+                // Long only, -v is free for version sorting like in ls
+                Arg::with_name(VERSION_ARG_NAME).long(VERSION_ARG_NAME),
+            ],
+        }
+    }
+
+    pub fn about(mut self, about: &'static str) -> App {
+        self.about = about;
+        self
+    }
+
+    pub fn author(mut self, author: &'static str) -> App {
+        self.author = author;
+        self
+    }
+
+    pub fn version(mut self, version: &'static str) -> App {
+        self.version = version;
+        self
+    }
+
+    pub fn arg(mut self, arg: Arg) -> App {
+        self.args.push(arg);
+        self
+    }
+
+    pub fn get_matches(self) -> Matches {
+        let matches = match self.parse(env::args_os().skip(1)) {
+            Ok(matches) => matches,
+            Err(err) => usage_error(self.name, &err),
+        };
+
+        if matches.is_present(HELP_ARG_NAME) {
+            println!("{}", self.help());
+            exit(0);
+        }
+
+        if matches.is_present(VERSION_ARG_NAME) {
+            println!("{} {}", self.name, self.version);
+            exit(0);
+        }
+
+        matches
+    }
+
+    fn find_short(&self, short: &str) -> Option<&Arg> {
+        self.args.iter().find(|a| a.short == Some(short))
+    }
+
+    fn find_long(&self, long: &str) -> Option<&Arg> {
+        self.args
+            .iter()
+            .find(|a| !a.is_positional() && a.long_name() == long)
+    }
+
+    fn parse(&self, mut argv: impl Iterator<Item = OsString>) -> Result<Matches, String> {
+        let mut matches = Matches {
+            occurrences: vec![],
+            operands: vec![],
+        };
+
+        while let Some(os_token) = argv.next() {
+            let Some(token) = os_token.to_str() else {
+                // Flags are all ASCII, so only an operand can fail to be UTF-8
+                if os_token.len() > 1 && os_token.as_encoded_bytes().starts_with(b"-") {
+                    return Err(format!("invalid option '{}'", os_token.to_string_lossy()));
+                }
+                matches.operands.push(os_token);
+                continue;
+            };
+
+            if token == "--" {
+                matches.operands.extend(argv.by_ref());
+            } else if let Some(long) = token.strip_prefix("--") {
+                let (flag, inline_value) = match long.split_once('=') {
+                    Some((flag, value)) => (flag, Some(value.to_string())),
+                    None => (long, None),
+                };
+                let arg = self
+                    .find_long(flag)
+                    .ok_or(format!("unrecognized option '--{}'", flag))?;
+                let value = match (arg.takes_value, inline_value) {
+                    (true, Some(value)) => Some(value),
+                    (true, None) if arg.is_value_optional => None,
+                    (true, None) => Some(value_string(
+                        argv.next()
+                            .ok_or(format!("option '--{}' requires an argument", flag))?,
+                        arg,
+                    )?),
+                    (false, Some(_)) => {
+                        return Err(format!("option '--{}' doesn't allow an argument", flag))
+                    }
+                    (false, None) => None,
+                };
+                matches.occurrences.push(Occurrence {
+                    name: arg.name,
+                    value,
+                });
+            } else if token.len() > 1 && token.starts_with('-') {
+                let shorts = &token[1..];
+                for (index, ch) in shorts.char_indices() {
+                    let short = &shorts[index..index + ch.len_utf8()];
+                    let arg = self
+                        .find_short(short)
+                        .ok_or(format!("invalid option -- '{}'", short))?;
+                    if arg.takes_value {
+                        // The rest of the token is the value, e.g. `-w80`
+                        let rest = &shorts[index + ch.len_utf8()..];
+                        let value = match rest.is_empty() {
+                            true => value_string(
+                                argv.next()
+                                    .ok_or(format!("option requires an argument -- '{}'", short))?,
+                                arg,
+                            )?,
+                            false => rest.to_string(),
+                        };
+                        matches.occurrences.push(Occurrence {
+                            name: arg.name,
+                            value: Some(value),
+                        });
+                        break;
+                    }
+                    matches.occurrences.push(Occurrence {
+                        name: arg.name,
+                        value: None,
+                    });
+                }
+            } else {
+                matches.operands.push(os_token);
+            }
+        }
+
+        Ok(matches)
+    }
+
+    fn help(&self) -> String {
+        let mut help = format!(
+            "{} {}\n{}\n{}\n\nUSAGE:\n  {} [OPTIONS]",
+            self.name, self.version, self.author, self.about, self.name
+        );
+        if let Some(positional) = self.args.iter().find(|a| a.is_positional()) {
+            help.push_str(&format!(" {}", positional.name.to_uppercase()));
+        }
+
+        help.push_str("\n\nOPTIONS:");
+        for arg in self.args.iter().filter(|a| !a.is_positional()) {
+            let value = match (arg.takes_value, arg.is_value_optional) {
+                (true, true) => format!("[={}]", arg.name.to_uppercase()),
+                (true, false) => format!("={}", arg.name.to_uppercase()),
+                (false, _) => String::new(),
+            };
+            match arg.short {
+                Some(short) => {
+                    help.push_str(&format!("\n  -{}, --{}{}", short, arg.long_name(), value))
+                }
+                None => help.push_str(&format!("\n  --{}{}", arg.long_name(), value)),
+            }
+        }
+
+        help
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::ffi::OsStringExt;
+
+    fn app() -> App {
+        App::new("rs")
+            .arg(Arg::with_name("one-line").short("1"))
+            .arg(Arg::with_name("long").short("l"))
+            .arg(Arg::with_name("comma").short("m"))
+            .arg(Arg::with_name("width").short("w").takes_value(true))
+            .arg(Arg::with_name("path"))
+    }
+
+    fn parse(argv: &[&[u8]]) -> Result<Matches, String> {
+        app().parse(argv.iter().map(|arg| OsString::from_vec(arg.to_vec())))
+    }
+
+    #[test]
+    fn last_format_flag_wins() {
+        let formats = ["one-line", "long", "comma"];
+        for (argv, last) in [
+            (&[&b"-lm"[..]][..], "comma"),
+            (&[b"-ml"], "long"),
+            (&[b"-1m"], "comma"),
+            (&[b"-l", b"dir", b"-1"], "one-line"),
+        ] {
+            let matches = parse(argv).unwrap();
+            assert_eq!(matches.last_of(&formats), Some(last));
+        }
+    }
+
+    #[test]
+    fn flags_can_follow_operands() {
+        let matches = parse(&[b"dir1", b"-l", b"dir2", b"--", b"-m"]).unwrap();
+        assert!(matches.is_present("long"));
+        assert!(!matches.is_present("comma"));
+        assert_eq!(matches.operands(), ["dir1", "dir2", "-m"]);
+    }
+
+    #[test]
+    fn operands_may_be_non_utf8() {
+        let matches = parse(&[b"\xff", b"--", b"\xffx"]).unwrap();
+        assert_eq!(
+            matches.operands(),
+            [
+                OsString::from_vec(vec![0xff]),
+                OsString::from_vec(vec![0xff, b'x'])
+            ]
+        );
+    }
+
+    #[test]
+    fn flags_and_values_must_be_utf8() {
+        assert!(parse(&[b"-\xff"]).is_err());
+        assert_eq!(
+            parse(&[b"-w", b"\xff"]).err().as_deref(),
+            Some("invalid argument '\u{fffd}' for '--width'")
+        );
+        assert_eq!(parse(&[b"-w80"]).unwrap().value_of("width"), Some("80"));
+    }
+}
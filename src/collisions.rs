@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+
+use crate::normalize;
+
+// The name decomposed and lowercased, equal for names a case and
+// normalization insensitive filesystem would take to be the same
+pub fn fold(name: &str) -> String {
+    normalize::nfd(name).to_lowercase()
+}
+
+// Indexes of the names that fold to the same key, in groups of two or more,
+// each group in the order its names were given
+pub fn find_groups<'a>(keys: impl Iterator<Item = &'a str>) -> Vec<Vec<usize>> {
+    let mut by_key: HashMap<String, Vec<usize>> = HashMap::new();
+    for (index, key) in keys.enumerate() {
+        by_key.entry(fold(key)).or_default().push(index);
+    }
+
+    let mut groups: Vec<Vec<usize>> = by_key
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .collect();
+    groups.sort();
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn case_pairs_fold_together() {
+        for (a, b) in [
+            ("README.md", "readme.md"),
+            ("Makefile", "MAKEFILE"),
+            ("ÉTÉ", "été"),
+            ("Ä", "ä"),
+        ] {
+            assert_eq!(fold(a), fold(b), "{} {}", a, b);
+        }
+        assert_ne!(fold("readme.md"), fold("readme.txt"));
+        assert_ne!(fold("e"), fold("é"));
+    }
+
+    #[test]
+    fn precomposed_and_decomposed_accents_fold_together() {
+        assert_eq!(fold("caf\u{e9}"), fold("cafe\u{301}"));
+        assert_eq!(fold("CAF\u{c9}"), fold("cafe\u{301}"));
+        assert_eq!(fold("\u{c5}ngstr\u{f6}m"), fold("A\u{30a}ngstro\u{308}m"));
+    }
+
+    #[test]
+    fn groups_hold_the_indexes_of_matching_names() {
+        let names = [
+            "b",
+            "README.md",
+            "caf\u{e9}",
+            "x",
+            "readme.md",
+            "cafe\u{301}",
+            "Readme.MD",
+        ];
+        assert_eq!(
+            find_groups(names.iter().copied()),
+            [vec![1, 4, 6], vec![2, 5]]
+        );
+        assert!(find_groups(["a", "b", "c"].into_iter()).is_empty());
+    }
+}
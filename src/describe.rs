@@ -0,0 +1,145 @@
+use crate::size::ByteSize;
+use crate::time::{DateFormat, SimpleDate};
+
+pub enum DescribedKind {
+    File,
+    Directory,
+    Symlink(String),
+    Special,
+    Unknown,
+}
+
+// The fields of an entry that --describe puts into words
+pub struct Description {
+    pub name: String,
+    pub kind: DescribedKind,
+    pub size: Option<ByteSize>,
+    pub time: Option<(&'static str, SimpleDate)>,
+    pub owner: Option<String>,
+}
+
+// e.g. "main.rs, file, 4.2 kilobytes, modified March 3rd 2024, owned by harry"
+pub fn sentence(description: &Description) -> String {
+    let mut parts = vec![description.name.clone()];
+
+    parts.push(match &description.kind {
+        DescribedKind::File => String::from("file"),
+        DescribedKind::Directory => String::from("directory"),
+        DescribedKind::Symlink(target) => format!("symbolic link to {}", target),
+        DescribedKind::Special => String::from("special file"),
+        DescribedKind::Unknown => String::from("unreadable"),
+    });
+
+    if let Some(size) = description.size {
+        parts.push(size.words());
+    }
+
+    if let Some((verb, date)) = &description.time {
+        parts.push(format!(
+            "{} {} {} {}",
+            verb,
+            date.month_display(DateFormat::FullMonth),
+            date.day_ordinal(),
+            date.year()
+        ));
+    }
+
+    if let Some(owner) = &description.owner {
+        parts.push(format!("owned by {}", owner));
+    }
+
+    parts.join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Days from 1970-01-01 to 2024-03-01
+    const MARCH_1ST_2024: i64 = 19783;
+
+    fn description(kind: DescribedKind) -> Description {
+        Description {
+            name: String::from("main.rs"),
+            kind,
+            size: None,
+            time: None,
+            owner: None,
+        }
+    }
+
+    #[test]
+    fn every_field_in_order() {
+        let description = Description {
+            size: Some(ByteSize(4300)),
+            time: Some(("modified", SimpleDate::from_days(MARCH_1ST_2024 + 2))),
+            owner: Some(String::from("harry")),
+            ..description(DescribedKind::File)
+        };
+        assert_eq!(
+            sentence(&description),
+            "main.rs, file, 4.2 kilobytes, modified March 3rd 2024, owned by harry"
+        );
+    }
+
+    #[test]
+    fn kinds() {
+        for (kind, expected) in [
+            (DescribedKind::Directory, "main.rs, directory"),
+            (
+                DescribedKind::Symlink(String::from("../lib.rs")),
+                "main.rs, symbolic link to ../lib.rs",
+            ),
+            (DescribedKind::Special, "main.rs, special file"),
+            (DescribedKind::Unknown, "main.rs, unreadable"),
+        ] {
+            assert_eq!(sentence(&description(kind)), expected);
+        }
+    }
+
+    #[test]
+    fn sizes_are_spelled_out() {
+        for (bytes, expected) in [
+            (0, "0 bytes"),
+            (1, "1 byte"),
+            (1023, "1023 bytes"),
+            (1024, "1.0 kilobytes"),
+            (5 * 1024 * 1024, "5.0 megabytes"),
+        ] {
+            let description = Description {
+                size: Some(ByteSize(bytes)),
+                ..description(DescribedKind::File)
+            };
+            assert_eq!(
+                sentence(&description),
+                format!("main.rs, file, {}", expected)
+            );
+        }
+    }
+
+    #[test]
+    fn ordinal_days() {
+        for (day, ordinal) in [
+            (1, "1st"),
+            (2, "2nd"),
+            (3, "3rd"),
+            (4, "4th"),
+            (11, "11th"),
+            (12, "12th"),
+            (13, "13th"),
+            (21, "21st"),
+            (22, "22nd"),
+            (23, "23rd"),
+            (31, "31st"),
+        ] {
+            let description = Description {
+                time: Some(("accessed", SimpleDate::from_days(MARCH_1ST_2024 + day - 1))),
+                ..description(DescribedKind::File)
+            };
+            assert_eq!(
+                sentence(&description),
+                format!("main.rs, file, accessed March {} 2024", ordinal)
+            );
+        }
+    }
+}
@@ -0,0 +1,35 @@
+// Splits a dev_t into its major and minor numbers. The encoding is platform
+// specific, so other platforms get None and show the raw value instead.
+#[cfg(target_os = "linux")]
+pub fn major_minor(dev: u64) -> Option<(u64, u64)> {
+    // glibc's gnu_dev_major/gnu_dev_minor
+    let major = ((dev >> 8) & 0xfff) | ((dev >> 32) & !0xfff);
+    let minor = (dev & 0xff) | ((dev >> 12) & !0xff);
+    Some((major, minor))
+}
+
+#[cfg(target_os = "macos")]
+pub fn major_minor(dev: u64) -> Option<(u64, u64)> {
+    Some(((dev >> 24) & 0xff, dev & 0xffffff))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn major_minor(_dev: u64) -> Option<(u64, u64)> {
+    None
+}
+
+// e.g. "259:2", or the raw number where it can't be decoded
+pub fn device_display(dev: u64) -> String {
+    match major_minor(dev) {
+        Some((major, minor)) => format!("{}:{}", major, minor),
+        None => dev.to_string(),
+    }
+}
+
+// e.g. "1, 3", what ls shows in place of a device node's size
+pub fn size_display(rdev: u64) -> String {
+    match major_minor(rdev) {
+        Some((major, minor)) => format!("{}, {}", major, minor),
+        None => rdev.to_string(),
+    }
+}
@@ -0,0 +1,91 @@
+use std::fmt::Write;
+
+const GRAPH_NAME: &str = "rs";
+
+// A Graphviz digraph of a directory tree, nodes numbered as they're added
+#[derive(Default)]
+pub struct Digraph {
+    nodes: Vec<(String, bool)>,
+    edges: Vec<(usize, usize)>,
+}
+
+// Quotes the text as a DOT string. Backslashes are doubled as well, since
+// labels would otherwise read them as escapes like \n.
+fn quote(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for ch in text.chars() {
+        match ch {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            ch => quoted.push(ch),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+impl Digraph {
+    // Adds a node, drawn as a box for a directory and an ellipse otherwise,
+    // returning its index for edges
+    pub fn add_node(&mut self, label: String, is_dir: bool) -> usize {
+        self.nodes.push((label, is_dir));
+        self.nodes.len() - 1
+    }
+
+    pub fn add_edge(&mut self, from: usize, to: usize) {
+        self.edges.push((from, to));
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = format!("digraph {} {{\n", GRAPH_NAME);
+        for (index, (label, is_dir)) in self.nodes.iter().enumerate() {
+            let shape = match is_dir {
+                true => "box",
+                false => "ellipse",
+            };
+            let _ = writeln!(
+                out,
+                "  n{} [label={}, shape={}];",
+                index,
+                quote(label),
+                shape
+            );
+        }
+        for (from, to) in &self.edges {
+            let _ = writeln!(out, "  n{} -> n{};", from, to);
+        }
+        out.push('}');
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_are_quoted() {
+        assert_eq!(quote("plain"), "\"plain\"");
+        assert_eq!(quote("say \"hi\""), "\"say \\\"hi\\\"\"");
+        assert_eq!(quote("a\\n"), "\"a\\\\n\"");
+        assert_eq!(quote("two\nlines\r"), "\"two\\nlines\\r\"");
+        assert_eq!(quote("{x}; y -> z"), "\"{x}; y -> z\"");
+    }
+
+    #[test]
+    fn nodes_then_edges() {
+        let mut graph = Digraph::default();
+        let root = graph.add_node(".".to_string(), true);
+        let file = graph.add_node("a \"b\"".to_string(), false);
+        graph.add_edge(root, file);
+        assert_eq!(
+            graph.render(),
+            "digraph rs {\n  n0 [label=\".\", shape=box];\n  n1 [label=\"a \\\"b\\\"\", \
+             shape=ellipse];\n  n0 -> n1;\n}"
+        );
+        assert_eq!(Digraph::default().render(), "digraph rs {\n}");
+    }
+}
@@ -0,0 +1,76 @@
+use std::{
+    collections::HashMap,
+    fs::{File, Metadata},
+    hash::{DefaultHasher, Hasher},
+    io::{self, Read},
+    path::Path,
+};
+
+#[cfg(target_os = "linux")]
+use std::os::linux::fs::MetadataExt;
+#[cfg(target_os = "macos")]
+use std::os::macos::fs::MetadataExt;
+#[cfg(all(unix, not(any(target_os = "linux", target_os = "macos"))))]
+use std::os::unix::fs::MetadataExt;
+
+const HASH_BUFFER_SIZE: usize = 64 * 1024;
+
+// Hashes a file's content a buffer at a time, so big files aren't read into memory
+fn content_hash(path: &Path) -> io::Result<u64> {
+    let mut file = File::open(path)?;
+    let mut hasher = DefaultHasher::new();
+    let mut buffer = vec![0; HASH_BUFFER_SIZE];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            return Ok(hasher.finish());
+        }
+        hasher.write(&buffer[..read]);
+    }
+}
+
+// Groups the files that have identical content, each group being indices into
+// `files` in their original order. Only files of the same size can match, so
+// only those are hashed, and hard links to one inode match without reading.
+pub fn find_groups(files: &[(&Path, &Metadata)]) -> Vec<Vec<usize>> {
+    let mut by_size: HashMap<u64, Vec<usize>> = HashMap::new();
+    for (index, (_, meta)) in files.iter().enumerate() {
+        by_size.entry(meta.len()).or_default().push(index);
+    }
+
+    let mut groups = vec![];
+    for same_size in by_size.into_values().filter(|indices| indices.len() > 1) {
+        let mut by_inode: HashMap<(u64, u64), Vec<usize>> = HashMap::new();
+        for index in same_size {
+            let meta = files[index].1;
+            by_inode
+                .entry((meta.st_dev(), meta.st_ino()))
+                .or_default()
+                .push(index);
+        }
+
+        // Nothing else to compare against, so these can only be links to each other
+        if by_inode.len() == 1 {
+            groups.extend(by_inode.into_values());
+            continue;
+        }
+
+        // One file per inode is enough to hash, its links share the result
+        let mut by_content: HashMap<u64, Vec<usize>> = HashMap::new();
+        for links in by_inode.into_values() {
+            match content_hash(files[links[0]].0) {
+                Ok(hash) => by_content.entry(hash).or_default().extend(links),
+                // Unreadable files can't be compared, but links are still the same file
+                Err(_) if links.len() > 1 => groups.push(links),
+                Err(_) => continue,
+            }
+        }
+        groups.extend(by_content.into_values().filter(|indices| indices.len() > 1));
+    }
+
+    for group in groups.iter_mut() {
+        group.sort();
+    }
+    groups.sort();
+    groups
+}
@@ -0,0 +1,192 @@
+// The members an entry can have in structured output, named as --fields
+// takes them
+#[derive(Clone, Copy, PartialEq)]
+pub enum Field {
+    Name,
+    Path,
+    Type,
+    Size,
+    Blocks,
+    Inode,
+    Links,
+    Uid,
+    Gid,
+    User,
+    Group,
+    Mode,
+    Permissions,
+    Mtime,
+    Atime,
+    Ctime,
+    Target,
+    GitTime,
+    GitAuthor,
+}
+
+const FIELD_NAMES: [(&str, Field); 19] = [
+    ("name", Field::Name),
+    ("path", Field::Path),
+    ("type", Field::Type),
+    ("size", Field::Size),
+    ("blocks", Field::Blocks),
+    ("inode", Field::Inode),
+    ("nlink", Field::Links),
+    ("uid", Field::Uid),
+    ("gid", Field::Gid),
+    ("user", Field::User),
+    ("group", Field::Group),
+    ("mode", Field::Mode),
+    ("permissions", Field::Permissions),
+    ("mtime", Field::Mtime),
+    ("atime", Field::Atime),
+    ("ctime", Field::Ctime),
+    ("target", Field::Target),
+    ("git-time", Field::GitTime),
+    ("git-author", Field::GitAuthor),
+];
+
+// What --json-tree emits without --fields
+pub const DEFAULT_FIELDS: [Field; 4] = [Field::Name, Field::Path, Field::Type, Field::Size];
+
+// What --json emits without --fields, every field an entry has
+pub const JSON_FIELDS: [Field; 17] = [
+    Field::Name,
+    Field::Path,
+    Field::Type,
+    Field::Size,
+    Field::Blocks,
+    Field::Inode,
+    Field::Links,
+    Field::Uid,
+    Field::Gid,
+    Field::User,
+    Field::Group,
+    Field::Mode,
+    Field::Permissions,
+    Field::Mtime,
+    Field::Atime,
+    Field::Ctime,
+    Field::Target,
+];
+
+// What --csv and --tsv emit without --fields
+pub const RECORD_FIELDS: [Field; 8] = [
+    Field::Name,
+    Field::Type,
+    Field::Size,
+    Field::Mode,
+    Field::Links,
+    Field::User,
+    Field::Group,
+    Field::Mtime,
+];
+
+impl Field {
+    pub fn name(self) -> &'static str {
+        FIELD_NAMES
+            .iter()
+            .find(|(_, field)| *field == self)
+            .map_or("", |(name, _)| name)
+    }
+
+    // Fields read from the repository's log rather than the entry itself
+    pub fn is_git(self) -> bool {
+        matches!(self, Field::GitTime | Field::GitAuthor)
+    }
+}
+
+// A comma separated list of field names, kept in the order given
+pub fn parse_list(list: &str) -> Result<Vec<Field>, String> {
+    let mut fields = vec![];
+    for name in list.split(',').map(str::trim) {
+        let Some((_, field)) = FIELD_NAMES.iter().find(|(known, _)| *known == name) else {
+            let valid: Vec<&str> = FIELD_NAMES.iter().map(|(name, _)| *name).collect();
+            return Err(format!(
+                "unknown field '{}', valid fields are: {}",
+                name,
+                valid.join(", ")
+            ));
+        };
+        if field.is_git() && !cfg!(feature = "git") {
+            return Err(format!(
+                "field '{}' is not available, rs was compiled without the 'git' feature",
+                name
+            ));
+        }
+        if !fields.contains(field) {
+            fields.push(*field);
+        }
+    }
+    Ok(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(list: &str) -> Vec<&'static str> {
+        parse_list(list)
+            .unwrap()
+            .into_iter()
+            .map(Field::name)
+            .collect()
+    }
+
+    #[test]
+    fn order_is_kept() {
+        assert_eq!(names("size,name"), ["size", "name"]);
+        assert_eq!(
+            names("mtime,user,nlink,path"),
+            ["mtime", "user", "nlink", "path"]
+        );
+    }
+
+    #[test]
+    fn repeats_are_dropped_and_spaces_trimmed() {
+        assert_eq!(names("name, size ,name"), ["name", "size"]);
+    }
+
+    #[test]
+    fn every_name_parses_to_its_field() {
+        for (name, field) in FIELD_NAMES {
+            if field.is_git() && !cfg!(feature = "git") {
+                continue;
+            }
+            assert!(parse_list(name).unwrap() == [field], "{}", name);
+            assert_eq!(field.name(), name);
+        }
+    }
+
+    #[test]
+    fn unknown_names_list_the_valid_ones() {
+        let err = parse_list("name,bogus").err().unwrap();
+        assert!(
+            err.starts_with("unknown field 'bogus', valid fields are: name, path, type, size,"),
+            "{}",
+            err
+        );
+        assert!(err.ends_with(", git-time, git-author"), "{}", err);
+        assert!(parse_list("").is_err());
+        assert!(parse_list("name,").is_err());
+        assert!(parse_list("Name").is_err());
+        assert!(parse_list("links").is_err());
+    }
+
+    #[test]
+    fn git_fields_need_the_feature() {
+        match cfg!(feature = "git") {
+            true => assert_eq!(names("git-time,git-author"), ["git-time", "git-author"]),
+            false => assert_eq!(
+                parse_list("name,git-time").err().unwrap(),
+                "field 'git-time' is not available, rs was compiled without the 'git' feature"
+            ),
+        }
+    }
+
+    #[test]
+    fn default_lists_leave_out_git() {
+        for fields in [&DEFAULT_FIELDS[..], &JSON_FIELDS, &RECORD_FIELDS] {
+            assert!(!fields.iter().any(|field| field.is_git()));
+        }
+    }
+}
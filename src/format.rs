@@ -1,68 +1,748 @@
-use std::collections::HashMap;
+use std::{cmp::Reverse, collections::HashMap};
 
 // Escape codes
-const ESCAPE_BLUE_BOLD: &str = "\x1b[34;1m";
 const ESCAPE_RESET: &str = "\x1b[0m";
 
+// Name suffixes the built-in palettes color, grouped as dircolors does
+const ARCHIVE_SUFFIXES: [&str; 12] = [
+    ".tar", ".tgz", ".tar.gz", ".gz", ".bz2", ".xz", ".zst", ".zip", ".7z", ".rar", ".deb",
+    ".rpm",
+];
+const IMAGE_SUFFIXES: [&str; 8] = [
+    ".png", ".jpg", ".jpeg", ".gif", ".bmp", ".svg", ".webp", ".tiff",
+];
+const AUDIO_SUFFIXES: [&str; 6] = [".mp3", ".flac", ".ogg", ".wav", ".m4a", ".opus"];
+
+// Terminal background colors that count as light in COLORFGBG, like vim
+const LIGHT_BACKGROUNDS: [u8; 2] = [7, 15];
+
 // Bytes
 const KB_IN_BYTES: f64 = 1024.0;
 const MB_IN_BYTES: f64 = 1048576.0;
 const GB_IN_BYTES: f64 = 1073741824.0;
 const TB_IN_BYTES: f64 = 1099511627776.0;
+const UNIT_IN_BYTES: u128 = 1024;
+const HUMAN_READABLE_LABELS: [&str; 5] = ["K", "M", "G", "T", "P"];
+
+// The eight ANSI colors and their bright variants
+#[derive(Clone, Copy)]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    BrightBlack,
+    BrightRed,
+    BrightGreen,
+    BrightYellow,
+    BrightBlue,
+    BrightMagenta,
+    BrightCyan,
+    BrightWhite,
+}
+
+impl Color {
+    // As --color-override takes them, "red" or "bright-red"
+    fn from_name(name: &str) -> Option<Color> {
+        let (is_bright, base) = match name.strip_prefix("bright-") {
+            Some(base) => (true, base),
+            None => (false, name),
+        };
+        let color = match (base, is_bright) {
+            ("black", false) => Color::Black,
+            ("red", false) => Color::Red,
+            ("green", false) => Color::Green,
+            ("yellow", false) => Color::Yellow,
+            ("blue", false) => Color::Blue,
+            ("magenta", false) => Color::Magenta,
+            ("cyan", false) => Color::Cyan,
+            ("white", false) => Color::White,
+            ("black", true) => Color::BrightBlack,
+            ("red", true) => Color::BrightRed,
+            ("green", true) => Color::BrightGreen,
+            ("yellow", true) => Color::BrightYellow,
+            ("blue", true) => Color::BrightBlue,
+            ("magenta", true) => Color::BrightMagenta,
+            ("cyan", true) => Color::BrightCyan,
+            ("white", true) => Color::BrightWhite,
+            _ => return None,
+        };
+        Some(color)
+    }
+
+    // The SGR code for the color as a foreground, a background's is 10 more
+    fn code(self) -> u8 {
+        match self {
+            Color::Black => 30,
+            Color::Red => 31,
+            Color::Green => 32,
+            Color::Yellow => 33,
+            Color::Blue => 34,
+            Color::Magenta => 35,
+            Color::Cyan => 36,
+            Color::White => 37,
+            Color::BrightBlack => 90,
+            Color::BrightRed => 91,
+            Color::BrightGreen => 92,
+            Color::BrightYellow => 93,
+            Color::BrightBlue => 94,
+            Color::BrightMagenta => 95,
+            Color::BrightCyan => 96,
+            Color::BrightWhite => 97,
+        }
+    }
+}
+
+// Colors and attributes for a run of text, built up like
+// Style::fg(Color::Blue).bold()
+#[derive(Clone, Copy)]
+pub struct Style {
+    foreground: Option<Color>,
+    background: Option<Color>,
+    is_bold: bool,
+    is_dim: bool,
+    is_underline: bool,
+    is_reverse: bool,
+}
+
+impl Style {
+    const PLAIN: Style = Style {
+        foreground: None,
+        background: None,
+        is_bold: false,
+        is_dim: false,
+        is_underline: false,
+        is_reverse: false,
+    };
+
+    const fn fg(color: Color) -> Style {
+        Style {
+            foreground: Some(color),
+            ..Style::PLAIN
+        }
+    }
+
+    const fn on(self, color: Color) -> Style {
+        Style {
+            background: Some(color),
+            ..self
+        }
+    }
+
+    const fn bold(self) -> Style {
+        Style {
+            is_bold: true,
+            ..self
+        }
+    }
+
+    const fn dim(self) -> Style {
+        Style {
+            is_dim: true,
+            ..self
+        }
+    }
+
+    const fn underline(self) -> Style {
+        Style {
+            is_underline: true,
+            ..self
+        }
+    }
+
+    const fn reverse(self) -> Style {
+        Style {
+            is_reverse: true,
+            ..self
+        }
+    }
+
+    // The escape code that turns the style on, colors first
+    pub fn escape(&self) -> String {
+        let mut codes: Vec<String> = vec![];
+        if let Some(color) = self.foreground {
+            codes.push(color.code().to_string());
+        }
+        if let Some(color) = self.background {
+            codes.push((color.code() + 10).to_string());
+        }
+        let attributes = [
+            (self.is_bold, "1"),
+            (self.is_dim, "2"),
+            (self.is_underline, "4"),
+            (self.is_reverse, "7"),
+        ];
+        for (is_set, code) in attributes {
+            if is_set {
+                codes.push(code.to_string());
+            }
+        }
+        format!("\x1b[{}m", codes.join(";"))
+    }
+}
 
-pub fn blue_bold(str: &String) -> String {
-    format!("\x1b[34;1m{}\x1b[0m", str)
+// The kinds of output that can be styled, named as --color-override takes them
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StyleClass {
+    Directory,
+    Symlink,
+    Executable,
+    Broken,
+    // What a broken link points at
+    Missing,
+    File,
+    Fifo,
+    Socket,
+    BlockDevice,
+    CharDevice,
+    Setuid,
+    Setgid,
+    // A directory anyone can write to, with and without the sticky bit
+    StickyOtherWritable,
+    OtherWritable,
+    Sticky,
+    Size,
+    Date,
+    User,
+    Group,
+    Header,
+    // The column names of --header
+    TableHeader,
+    Highlight,
+    Collision,
 }
 
-pub fn bytes_to_human_readable(bytes: u64) -> String {
+impl StyleClass {
+    pub fn from_name(name: &str) -> Option<StyleClass> {
+        match name {
+            "dir" => Some(StyleClass::Directory),
+            "symlink" => Some(StyleClass::Symlink),
+            "exec" => Some(StyleClass::Executable),
+            "broken" => Some(StyleClass::Broken),
+            "missing" => Some(StyleClass::Missing),
+            "file" => Some(StyleClass::File),
+            "fifo" => Some(StyleClass::Fifo),
+            "socket" => Some(StyleClass::Socket),
+            "block" => Some(StyleClass::BlockDevice),
+            "char" => Some(StyleClass::CharDevice),
+            "setuid" => Some(StyleClass::Setuid),
+            "setgid" => Some(StyleClass::Setgid),
+            "sticky-other-writable" => Some(StyleClass::StickyOtherWritable),
+            "other-writable" => Some(StyleClass::OtherWritable),
+            "sticky" => Some(StyleClass::Sticky),
+            "size" => Some(StyleClass::Size),
+            "date" => Some(StyleClass::Date),
+            "user" => Some(StyleClass::User),
+            "group" => Some(StyleClass::Group),
+            "header" => Some(StyleClass::Header),
+            "table-header" => Some(StyleClass::TableHeader),
+            "highlight" => Some(StyleClass::Highlight),
+            "collision" => Some(StyleClass::Collision),
+            _ => None,
+        }
+    }
+
+    // The class for a two letter LS_COLORS key
+    fn from_ls_colors_key(key: &str) -> Option<StyleClass> {
+        match key {
+            "di" => Some(StyleClass::Directory),
+            "ln" => Some(StyleClass::Symlink),
+            "ex" => Some(StyleClass::Executable),
+            "or" => Some(StyleClass::Broken),
+            "mi" => Some(StyleClass::Missing),
+            "fi" => Some(StyleClass::File),
+            "pi" => Some(StyleClass::Fifo),
+            "so" => Some(StyleClass::Socket),
+            "bd" => Some(StyleClass::BlockDevice),
+            "cd" => Some(StyleClass::CharDevice),
+            "su" => Some(StyleClass::Setuid),
+            "sg" => Some(StyleClass::Setgid),
+            "tw" => Some(StyleClass::StickyOtherWritable),
+            "ow" => Some(StyleClass::OtherWritable),
+            "st" => Some(StyleClass::Sticky),
+            _ => None,
+        }
+    }
+}
+
+// Turns a color name, a "#rrggbb" color or a comma separated mix of those and
+// attributes (e.g. "bold,red") into an escape code
+pub fn parse_style_spec(spec: &str) -> Result<String, String> {
+    let mut codes: Vec<String> = vec![];
+    for token in spec.split(',') {
+        if let Some(color) = Color::from_name(token) {
+            codes.push(color.code().to_string());
+            continue;
+        }
+        let code = match token {
+            "bold" => "1",
+            "dim" => "2",
+            "italic" => "3",
+            "underline" => "4",
+            "blink" => "5",
+            "reverse" => "7",
+            _ => match token.strip_prefix('#').filter(|hex| hex.len() == 6) {
+                Some(hex) => {
+                    let channel = |index: usize| u8::from_str_radix(&hex[index..index + 2], 16);
+                    match (channel(0), channel(2), channel(4)) {
+                        (Ok(r), Ok(g), Ok(b)) => {
+                            codes.push(format!("38;2;{};{};{}", r, g, b));
+                            continue;
+                        }
+                        _ => return Err(token.to_string()),
+                    }
+                }
+                None => return Err(token.to_string()),
+            },
+        };
+        codes.push(code.to_string());
+    }
+    Ok(format!("\x1b[{}m", codes.join(";")))
+}
+
+// Wraps text in an escape code and the reset after it
+pub fn styled(escape: &str, str: &str) -> String {
+    format!("{}{}{}", escape, str, ESCAPE_RESET)
+}
+
+// The escape codes used for each kind of styled output, classes without one
+// are left plain
+pub struct Palette {
+    styles: HashMap<StyleClass, String>,
+    // Styles for files by the end of their name, lowercase and longest
+    // first so "*.tar.gz" is tried before "*.gz"
+    suffixes: Vec<(String, String)>,
+}
+
+// The built-in suffix styles, archives, images and audio in the given escapes
+fn default_suffixes(archive: &str, image: &str, audio: &str) -> Vec<(String, String)> {
+    let groups = [
+        (&ARCHIVE_SUFFIXES[..], archive),
+        (&IMAGE_SUFFIXES[..], image),
+        (&AUDIO_SUFFIXES[..], audio),
+    ];
+    let mut suffixes: Vec<(String, String)> = groups
+        .iter()
+        .flat_map(|(suffixes, escape)| {
+            suffixes
+                .iter()
+                .map(|suffix| (suffix.to_string(), escape.to_string()))
+        })
+        .collect();
+    suffixes.sort_by_key(|(suffix, _)| Reverse(suffix.len()));
+    suffixes
+}
+
+// Whether the name ends with the suffix, ignoring ASCII case like GNU ls does
+// for "*.JPG"
+fn ends_with_ignore_case(name: &str, suffix: &str) -> bool {
+    name.len() >= suffix.len()
+        && name.as_bytes()[name.len() - suffix.len()..].eq_ignore_ascii_case(suffix.as_bytes())
+}
+
+impl Palette {
+    // Bold stands out on the dark backgrounds most terminals have
+    fn dark() -> Palette {
+        Palette::with_styles(
+            [
+                (StyleClass::Directory, Style::fg(Color::Blue).bold()),
+                (StyleClass::Symlink, Style::fg(Color::Cyan).bold()),
+                (StyleClass::Executable, Style::fg(Color::Green).bold()),
+                (StyleClass::Broken, Style::fg(Color::Red).bold()),
+            ],
+            [
+                Style::fg(Color::Red).bold(),
+                Style::fg(Color::Magenta).bold(),
+                Style::fg(Color::Cyan),
+            ],
+        )
+    }
+
+    // Bold is often drawn in the bright variant, which washes out on light backgrounds
+    fn light() -> Palette {
+        Palette::with_styles(
+            [
+                (StyleClass::Directory, Style::fg(Color::Blue)),
+                (StyleClass::Symlink, Style::fg(Color::Cyan)),
+                (StyleClass::Executable, Style::fg(Color::Green)),
+                (StyleClass::Broken, Style::fg(Color::Red)),
+            ],
+            [
+                Style::fg(Color::Red),
+                Style::fg(Color::Magenta),
+                Style::fg(Color::Cyan),
+            ],
+        )
+    }
+
+    // A built-in palette from the styles that differ with the background and
+    // the archive, image and audio suffix styles, with the rest shared
+    fn with_styles(
+        types: [(StyleClass, Style); 4],
+        [archive, image, audio]: [Style; 3],
+    ) -> Palette {
+        let shared = [
+            (StyleClass::Header, Style::PLAIN.dim()),
+            (StyleClass::TableHeader, Style::PLAIN.underline()),
+            (StyleClass::Highlight, Style::PLAIN.bold().reverse()),
+            (StyleClass::Collision, Style::fg(Color::Red).bold()),
+            (StyleClass::Setuid, Style::fg(Color::White).on(Color::Red)),
+            (StyleClass::Setgid, Style::fg(Color::Black).on(Color::Yellow)),
+            (StyleClass::StickyOtherWritable, Style::fg(Color::Black).on(Color::Green)),
+            (StyleClass::OtherWritable, Style::fg(Color::Blue).on(Color::Green)),
+            (StyleClass::Sticky, Style::fg(Color::White).on(Color::Blue)),
+        ];
+        Palette {
+            styles: types
+                .into_iter()
+                .chain(shared)
+                .map(|(class, style)| (class, style.escape()))
+                .collect(),
+            suffixes: default_suffixes(&archive.escape(), &image.escape(), &audio.escape()),
+        }
+    }
+
+    // Picks the built-in palette from COLORFGBG, which terminals set to "fg;bg"
+    // (or "fg;default;bg"). Anything unset or unrecognised is treated as dark.
+    pub fn from_colorfgbg(colorfgbg: Option<&str>) -> Palette {
+        let background = colorfgbg
+            .and_then(|value| value.rsplit(';').next())
+            .and_then(|bg| bg.parse::<u8>().ok());
+        match background {
+            Some(bg) if LIGHT_BACKGROUNDS.contains(&bg) => Palette::light(),
+            _ => Palette::dark(),
+        }
+    }
+
+    pub fn set(&mut self, class: StyleClass, escape: String) {
+        self.styles.insert(class, escape);
+    }
+
+    // Takes the styles in LS_COLORS, "key=codes" pairs separated by colons
+    // as dircolors writes them, over the built-in ones. Its suffixes replace
+    // the built-in ones altogether, a later one winning over the same suffix
+    // given before. Keys rs has no class for, codes that aren't SGR
+    // parameters and anything else malformed are skipped.
+    pub fn apply_ls_colors(&mut self, ls_colors: &str) {
+        let mut suffixes: Vec<(String, String)> = vec![];
+        for (key, codes) in ls_colors.split(':').filter_map(|pair| pair.split_once('=')) {
+            let is_sgr = !codes.is_empty()
+                && codes.chars().all(|ch| ch.is_ascii_digit() || ch == ';');
+            if !is_sgr {
+                continue;
+            }
+            let escape = format!("\x1b[{}m", codes);
+            match key.strip_prefix('*') {
+                Some(suffix) if !suffix.is_empty() => {
+                    let suffix = suffix.to_ascii_lowercase();
+                    suffixes.retain(|(given, _)| *given != suffix);
+                    suffixes.push((suffix, escape));
+                }
+                Some(_) => {}
+                None => {
+                    if let Some(class) = StyleClass::from_ls_colors_key(key) {
+                        self.styles.insert(class, escape);
+                    }
+                }
+            }
+        }
+        if !suffixes.is_empty() {
+            suffixes.sort_by_key(|(suffix, _)| Reverse(suffix.len()));
+            self.suffixes = suffixes;
+        }
+    }
+
+    // The style of the first of the classes that has one, most specific
+    // first. A file takes the style of the longest suffix its name ends with
+    // before the plain file style.
+    fn style(&self, classes: &[StyleClass], name: &str) -> Option<&String> {
+        classes.iter().find_map(|class| match class {
+            StyleClass::File => self
+                .suffixes
+                .iter()
+                .find(|(suffix, _)| ends_with_ignore_case(name, suffix))
+                .map(|(_, escape)| escape)
+                .or_else(|| self.styles.get(class)),
+            class => self.styles.get(class),
+        })
+    }
+
+    // Paints with the first of the classes that has a style
+    pub fn paint(&self, classes: &[StyleClass], str: &str) -> String {
+        match self.style(classes, str) {
+            Some(escape) => styled(escape, str),
+            None => str.to_string(),
+        }
+    }
+
+    // Paints like paint, with the highlight style added over the byte
+    // ranges given. Each run gets its own escape and reset, so the text
+    // around a highlight keeps its style.
+    pub fn paint_highlighted(
+        &self,
+        classes: &[StyleClass],
+        str: &str,
+        ranges: &[(usize, usize)],
+    ) -> String {
+        let base = self.style(classes, str);
+        let highlight = match (base, self.styles.get(&StyleClass::Highlight)) {
+            (Some(base), Some(highlight)) => format!("{}{}", base, highlight),
+            (None, Some(highlight)) => highlight.to_string(),
+            (_, None) => return self.paint(classes, str),
+        };
+        let paint_base = |part: &str| match base {
+            Some(escape) => styled(escape, part),
+            None => part.to_string(),
+        };
+
+        let mut painted = String::new();
+        let mut start = 0;
+        for &(range_start, range_end) in ranges {
+            if range_start > start {
+                painted.push_str(&paint_base(&str[start..range_start]));
+            }
+            painted.push_str(&styled(&highlight, &str[range_start..range_end]));
+            start = range_end;
+        }
+        if start < str.len() {
+            painted.push_str(&paint_base(&str[start..]));
+        }
+        painted
+    }
+}
+
+// Byte ranges of the text covered by any of the patterns, in order and with
+// overlapping matches merged. Case is ignored unless is_case_sensitive.
+pub fn find_highlights(
+    text: &str,
+    patterns: &[String],
+    is_case_sensitive: bool,
+) -> Vec<(usize, usize)> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let is_same = |a: char, b: char| match is_case_sensitive {
+        true => a == b,
+        false => a.to_lowercase().eq(b.to_lowercase()),
+    };
+
+    let mut is_highlighted = vec![false; chars.len()];
+    for pattern in patterns.iter().filter(|pattern| !pattern.is_empty()) {
+        let pattern: Vec<char> = pattern.chars().collect();
+        let mut index = 0;
+        while index + pattern.len() <= chars.len() {
+            let is_match = pattern
+                .iter()
+                .zip(&chars[index..])
+                .all(|(p, (_, ch))| is_same(*p, *ch));
+            match is_match {
+                true => {
+                    is_highlighted[index..index + pattern.len()].fill(true);
+                    index += pattern.len();
+                }
+                false => index += 1,
+            }
+        }
+    }
+
+    let mut ranges: Vec<(usize, usize)> = vec![];
+    for (index, &(byte, ch)) in chars.iter().enumerate() {
+        if !is_highlighted[index] {
+            continue;
+        }
+        let end = byte + ch.len_utf8();
+        match ranges.last_mut() {
+            Some(last) if last.1 == byte => last.1 = end,
+            _ => ranges.push((byte, end)),
+        }
+    }
+    ranges
+}
+
+// e.g. "4.0K" with one decimal or "213M" with none. Always rounds up so
+// sizes never look smaller than they are, moving to the next unit when
+// rounding reaches 1024.
+pub fn bytes_to_human_readable(bytes: u64, precision: usize) -> String {
+    if (bytes as u128) < UNIT_IN_BYTES {
+        return bytes.to_string();
+    }
+
+    let scale = 10u128.pow(precision as u32);
+    let mut unit = UNIT_IN_BYTES;
+    for (index, label) in HUMAN_READABLE_LABELS.iter().enumerate() {
+        // Fixed point with `precision` decimals, so the rounding is exact
+        let scaled = (bytes as u128 * scale).div_ceil(unit);
+        if scaled < UNIT_IN_BYTES * scale || index == HUMAN_READABLE_LABELS.len() - 1 {
+            return match precision {
+                0 => format!("{}{}", scaled, label),
+                _ => format!(
+                    "{}.{:0width$}{}",
+                    scaled / scale,
+                    scaled % scale,
+                    label,
+                    width = precision
+                ),
+            };
+        }
+        unit *= UNIT_IN_BYTES;
+    }
+
+    unreachable!()
+}
+
+// Spelled out for --describe, e.g. "4.2 kilobytes"
+pub fn bytes_to_words(bytes: u64) -> String {
     let mut num = bytes as f64;
     let label: &str;
     if (KB_IN_BYTES..MB_IN_BYTES).contains(&num) {
         num /= KB_IN_BYTES;
-        label = "K";
+        label = "kilobytes";
     } else if (MB_IN_BYTES..GB_IN_BYTES).contains(&num) {
         num /= MB_IN_BYTES;
-        label = "M";
+        label = "megabytes";
     } else if (GB_IN_BYTES..TB_IN_BYTES).contains(&num) {
         num /= GB_IN_BYTES;
-        label = "G"
+        label = "gigabytes";
+    } else if num >= TB_IN_BYTES {
+        num /= TB_IN_BYTES;
+        label = "terabytes";
+    } else if bytes == 1 {
+        return String::from("1 byte");
     } else {
-        return format!("{}", num);
+        return format!("{} bytes", bytes);
     }
 
-    format!("{:.1}{}", num, label)
+    format!("{:.1} {}", num, label)
 }
 
-// This is horrible!!
-// But to fix it we need to refactor the metadata logic :[
+// Terminal columns taken by a single character
+fn char_width(ch: char) -> usize {
+    match ch as u32 {
+        // Combining marks, zero width spaces and joiners, variation selectors
+        0x0300..=0x036F | 0x200B..=0x200F | 0x20D0..=0x20FF | 0xFE00..=0xFE0F => 0,
+        // East Asian wide and fullwidth forms, and emoji
+        0x1100..=0x115F
+        | 0x2E80..=0x303E
+        | 0x3041..=0xA4CF
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6
+        | 0x1F300..=0x1F64F
+        | 0x1F680..=0x1F6FF
+        | 0x1F900..=0x1F9FF
+        | 0x1FA70..=0x1FAFF
+        | 0x20000..=0x3FFFD => 2,
+        _ => 1,
+    }
+}
+
+// Terminal columns taken by a string. A U+FE0F variation selector asks for
+// emoji presentation, which widens a preceding narrow character to two.
+pub fn display_width(str: &str) -> usize {
+    let mut width = 0;
+    let mut previous_width = 0;
+    for ch in str.chars() {
+        if ch == '\u{fe0f}' {
+            if previous_width == 1 {
+                width += 1;
+            }
+            previous_width = 0;
+            continue;
+        }
+        previous_width = char_width(ch);
+        width += previous_width;
+    }
+    width
+}
+
+// The length of the escape sequence the text starts with, if it does. A CSI
+// sequence runs from `ESC [` to its final byte, a letter, and an OSC one like
+// a hyperlink from `ESC ]` to a BEL or `ESC \`.
+fn escape_len(text: &str) -> Option<usize> {
+    let rest = text.strip_prefix('\x1b')?;
+    let body = match rest.chars().next() {
+        Some('[') => rest[1..]
+            .find(|ch: char| ch.is_ascii_alphabetic())
+            .map_or(rest.len() - 1, |end| end + 1),
+        Some(']') => match (rest.find('\x07'), rest.find("\x1b\\")) {
+            (Some(bel), Some(st)) if st < bel => st + 1,
+            (Some(bel), _) => bel,
+            (None, Some(st)) => st + 1,
+            (None, None) => rest.len() - 1,
+        },
+        Some(ch) => ch.len_utf8() - 1,
+        None => return Some(1),
+    };
+    // ESC, the byte after it and the rest of the sequence
+    Some(2 + body)
+}
+
+// Display width once escape sequences are taken out, so styled cells line up
 fn unescaped_length(str: &str) -> usize {
-    str
-        .replace(ESCAPE_BLUE_BOLD, "")
-        .replace(ESCAPE_RESET, "")
-        .to_string()
-        .len()
+    let mut unescaped = String::new();
+    let mut rest = str;
+    while let Some(ch) = rest.chars().next() {
+        match escape_len(rest) {
+            Some(len) => rest = &rest[len..],
+            None => {
+                unescaped.push(ch);
+                rest = &rest[ch.len_utf8()..];
+            }
+        }
+    }
+    display_width(&unescaped)
+}
+
+// Characters a file URI can hold as they are
+fn is_uri_safe(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || b"-._~/".contains(&byte)
+}
+
+// A file:// URI for an absolute path on the host, every byte outside the
+// unreserved characters and slashes percent-encoded
+pub fn file_uri(host: &str, path: &[u8]) -> String {
+    let mut uri = format!("file://{}", host);
+    for &byte in path {
+        match is_uri_safe(byte) {
+            true => uri.push(byte as char),
+            false => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+// Text a terminal shows as a link to the URI, with OSC 8
+pub fn hyperlink(uri: &str, text: &str) -> String {
+    format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", uri, text)
 }
 
 pub enum TableAlignment {
     // Left,
     // Right,
-    RightLastLeft
+    RightLastLeft,
+    // Like RightLastLeft, but text in the given columns is left aligned.
+    // Numeric cells stay right aligned, the way GNU ls pads unknown owners.
+    LeftTextColumns(Vec<usize>),
 }
 
-// fn pad_right(input: String, length: &usize) -> String {
-//     let mut padded_string = input;
+fn pad_right(input: String, length: &usize) -> String {
+    let mut padded_string = input;
 
-//     if unescaped_length(&padded_string) == *length {
-//         return padded_string
-//     }
+    if unescaped_length(&padded_string) == *length {
+        return padded_string
+    }
 
-//     let spaces_to_add = length - unescaped_length(&padded_string);
-//     for _ in 0..spaces_to_add {
-//         padded_string.push(' ');
-//     }
+    let spaces_to_add = length - unescaped_length(&padded_string);
+    for _ in 0..spaces_to_add {
+        padded_string.push(' ');
+    }
 
-//     padded_string
-// }
+    padded_string
+}
 
 fn pad_left(input: String, length: &usize) -> String {
     let mut padded_string = String::new();
@@ -81,6 +761,390 @@ fn pad_left(input: String, length: &usize) -> String {
     padded_string
 }
 
+// Display width of each column, the widest cell in it
+pub fn column_widths(input_data: &[Vec<String>]) -> Vec<usize> {
+    let mut widths: Vec<usize> = vec![];
+    for row in input_data {
+        for (index, col) in row.iter().enumerate() {
+            let width = unescaped_length(col);
+            match widths.get_mut(index) {
+                Some(max_width) => *max_width = (*max_width).max(width),
+                None => widths.push(width),
+            }
+        }
+    }
+    widths
+}
+
+// Characters a shell reads specially, so a name holding one is quoted
+const SHELL_SPECIAL_CHARS: &str = " \t\n\r!\"$&'()*;<=>?[\\^`|";
+
+// How names are written, after GNU ls's --quoting-style
+#[derive(Clone, Copy)]
+pub enum QuotingStyle {
+    // As they are
+    Literal,
+    // In single quotes when a shell would need them
+    Shell,
+    // Always in single quotes
+    ShellAlways,
+    // Like Shell, with control characters as $'\n' escapes
+    ShellEscape,
+    // Like ShellAlways, with control characters as $'\n' escapes
+    ShellEscapeAlways,
+    // In double quotes with C escapes, as -Q
+    C,
+    // Backslash escaped without quotes, as -b
+    Escape,
+}
+
+// The C escape of a control character, or its octal
+fn push_control(escaped: &mut String, ch: char) {
+    match ch {
+        '\u{7}' => escaped.push_str("\\a"),
+        '\u{8}' => escaped.push_str("\\b"),
+        '\u{c}' => escaped.push_str("\\f"),
+        '\n' => escaped.push_str("\\n"),
+        '\r' => escaped.push_str("\\r"),
+        '\t' => escaped.push_str("\\t"),
+        '\u{b}' => escaped.push_str("\\v"),
+        ch => {
+            let mut bytes = [0; 4];
+            push_octal(escaped, ch.encode_utf8(&mut bytes).as_bytes());
+        }
+    }
+}
+
+fn push_octal(escaped: &mut String, bytes: &[u8]) {
+    for byte in bytes {
+        escaped.push_str(&format!("\\{:03o}", byte));
+    }
+}
+
+// The name with the given characters after a backslash, control characters
+// escaped and bytes that aren't UTF-8 in octal
+fn backslash_escape(name: &[u8], backslashed: &[char]) -> String {
+    let mut escaped = String::with_capacity(name.len());
+    for chunk in name.utf8_chunks() {
+        for ch in chunk.valid().chars() {
+            match ch {
+                ch if backslashed.contains(&ch) => {
+                    escaped.push('\\');
+                    escaped.push(ch);
+                }
+                ch if ch.is_control() => push_control(&mut escaped, ch),
+                ch => escaped.push(ch),
+            }
+        }
+        push_octal(&mut escaped, chunk.invalid());
+    }
+    escaped
+}
+
+// The name with a question mark for each control character, so a newline or
+// tab can't break the line or the column it's laid out in, and for each byte
+// that isn't UTF-8 when hiding them, the way ls writes names to a terminal.
+// Otherwise stray bytes are replaced.
+fn literal(name: &[u8], is_hide_control_chars: bool) -> String {
+    let mut hidden = String::with_capacity(name.len());
+    for chunk in name.utf8_chunks() {
+        for ch in chunk.valid().chars() {
+            hidden.push(if ch.is_control() { '?' } else { ch });
+        }
+        match is_hide_control_chars {
+            true => hidden.extend(chunk.invalid().iter().map(|_| '?')),
+            false if !chunk.invalid().is_empty() => hidden.push(char::REPLACEMENT_CHARACTER),
+            false => {}
+        }
+    }
+    hidden
+}
+
+// -N carries each byte of a name that isn't UTF-8 through the layouts as one
+// of the last private-use characters, U+10FF80 to U+10FFFF, until raw_bytes
+// writes it out as itself
+const RAW_BYTE_BASE: u32 = 0x10FF00;
+
+// The name as stored, control characters and all, with a stand-in for each
+// stray byte. Each takes one column, as a terminal shows such a byte.
+pub fn with_raw_bytes(name: &[u8]) -> String {
+    let mut text = String::with_capacity(name.len());
+    for chunk in name.utf8_chunks() {
+        text.push_str(chunk.valid());
+        text.extend(
+            chunk
+                .invalid()
+                .iter()
+                .filter_map(|byte| char::from_u32(RAW_BYTE_BASE + u32::from(*byte))),
+        );
+    }
+    text
+}
+
+// The text's bytes with the stand-ins with_raw_bytes put in turned back into
+// the bytes they stand for
+pub fn raw_bytes(text: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(text.len());
+    for ch in text.chars() {
+        match u32::from(ch) {
+            code @ 0x10FF80..=0x10FFFF => bytes.push((code - RAW_BYTE_BASE) as u8),
+            _ => bytes.extend_from_slice(ch.encode_utf8(&mut [0; 4]).as_bytes()),
+        }
+    }
+    bytes
+}
+
+// Whether a shell would read the name as something else unquoted. '#' and
+// '~' only matter at the start, a brace only on its own.
+fn is_shell_special(name: &[u8]) -> bool {
+    let text = String::from_utf8_lossy(name);
+    text.is_empty()
+        || text == "{"
+        || text == "}"
+        || text.starts_with(['#', '~'])
+        || text.chars().any(|ch| SHELL_SPECIAL_CHARS.contains(ch))
+}
+
+// Whether the name reads the same between double quotes, so one holding a
+// single quote can go in those instead, as GNU does
+fn is_double_quotable(text: &str) -> bool {
+    text.chars().enumerate().all(|(index, ch)| match ch {
+        ' ' | '\'' => true,
+        '#' | '~' => index == 0,
+        '{' | '}' => text.len() == 1,
+        ch if ch.is_control() || SHELL_SPECIAL_CHARS.contains(ch) => false,
+        _ => true,
+    })
+}
+
+impl QuotingStyle {
+    pub fn parse(name: &str) -> Option<QuotingStyle> {
+        match name {
+            "literal" => Some(QuotingStyle::Literal),
+            "shell" => Some(QuotingStyle::Shell),
+            "shell-always" => Some(QuotingStyle::ShellAlways),
+            "shell-escape" => Some(QuotingStyle::ShellEscape),
+            "shell-escape-always" => Some(QuotingStyle::ShellEscapeAlways),
+            "c" => Some(QuotingStyle::C),
+            "escape" => Some(QuotingStyle::Escape),
+            _ => None,
+        }
+    }
+
+    // The quote that goes either side of the name and the name's text
+    // between them. Styles that don't escape control characters write them
+    // as ?, and stray bytes too when hiding them.
+    pub fn quote(&self, name: &[u8], is_hide_control_chars: bool) -> (&'static str, String) {
+        let is_unprintable = name.utf8_chunks().any(|chunk| {
+            !chunk.invalid().is_empty() || chunk.valid().chars().any(char::is_control)
+        });
+        let is_shell_escaped = is_unprintable
+            && matches!(self, QuotingStyle::ShellEscape | QuotingStyle::ShellEscapeAlways);
+        match self {
+            QuotingStyle::Literal => ("", literal(name, is_hide_control_chars)),
+            QuotingStyle::C => ("\"", backslash_escape(name, &['"', '\\'])),
+            QuotingStyle::Escape => ("", backslash_escape(name, &[' ', '\\'])),
+            QuotingStyle::Shell | QuotingStyle::ShellEscape
+                if !(is_shell_special(name) || is_shell_escaped) =>
+            {
+                ("", literal(name, is_hide_control_chars))
+            }
+            _ if is_shell_escaped => ("'", shell_escape(name)),
+            _ => {
+                let text = literal(name, is_hide_control_chars);
+                match text.contains('\'') && is_double_quotable(&text) {
+                    true => ("\"", text),
+                    false => ("'", text.replace('\'', "'\\''")),
+                }
+            }
+        }
+    }
+}
+
+// The text between single quotes, control characters and stray bytes
+// stepping out of them into $'...', like 'a'$'\n''b'
+fn shell_escape(name: &[u8]) -> String {
+    let mut escaped = String::with_capacity(name.len());
+    let mut is_in_dollar = false;
+    for chunk in name.utf8_chunks() {
+        for ch in chunk.valid().chars() {
+            if ch.is_control() {
+                if !is_in_dollar {
+                    escaped.push_str("'$'");
+                    is_in_dollar = true;
+                }
+                push_control(&mut escaped, ch);
+                continue;
+            }
+            if is_in_dollar {
+                escaped.push_str("''");
+                is_in_dollar = false;
+            }
+            match ch {
+                '\'' => escaped.push_str("'\\''"),
+                ch => escaped.push(ch),
+            }
+        }
+        if !chunk.invalid().is_empty() && !is_in_dollar {
+            escaped.push_str("'$'");
+            is_in_dollar = true;
+        }
+        push_octal(&mut escaped, chunk.invalid());
+    }
+    escaped
+}
+
+// Cuts a cell down to the given display width, ending it with an ellipsis.
+// Escape sequences are kept whole, those after the cut too, so a style is
+// still reset and a hyperlink still closed.
+pub fn truncate_with_ellipsis(str: &str, width: usize) -> String {
+    if unescaped_length(str) <= width {
+        return str.to_string();
+    }
+
+    let mut truncated = String::new();
+    let mut used = 0;
+    let mut is_cut = false;
+    let mut rest = str;
+    while let Some(ch) = rest.chars().next() {
+        if let Some(len) = escape_len(rest) {
+            truncated.push_str(&rest[..len]);
+            rest = &rest[len..];
+            continue;
+        }
+        rest = &rest[ch.len_utf8()..];
+        if is_cut {
+            continue;
+        }
+        let ch_width = display_width(&ch.to_string());
+        // Room is left for the ellipsis
+        if used + ch_width + 1 > width {
+            is_cut = true;
+            if width > 0 {
+                truncated.push('…');
+            }
+            continue;
+        }
+        used += ch_width;
+        truncated.push(ch);
+    }
+    truncated
+}
+
+// The order cells go into the grid in
+pub enum GridFill {
+    // Top to bottom, then the next column, like ls -C
+    Down,
+    // Left to right, then the next row, like ls -x
+    Across,
+}
+
+impl GridFill {
+    // Which cell sits at the row and column of a grid that size
+    fn index(&self, row: usize, column: usize, rows: usize, columns: usize) -> usize {
+        match self {
+            GridFill::Down => column * rows + row,
+            GridFill::Across => row * columns + column,
+        }
+    }
+
+    // Rows and columns of the grid when asked for that many columns. Filled
+    // down, fewer columns can hold the cells once the rows are counted.
+    fn shape(&self, count: usize, columns: usize) -> (usize, usize) {
+        let rows = count.div_ceil(columns);
+        match self {
+            GridFill::Down => (rows, count.div_ceil(rows)),
+            GridFill::Across => (rows, columns),
+        }
+    }
+}
+
+// Display width of each column of the grid with that shape
+fn grid_column_widths(
+    widths: &[usize],
+    (rows, columns): (usize, usize),
+    fill: &GridFill,
+) -> Vec<usize> {
+    (0..columns)
+        .map(|column| {
+            (0..rows)
+                .filter_map(|row| widths.get(fill.index(row, column, rows, columns)))
+                .max()
+                .copied()
+                .unwrap_or(0)
+        })
+        .collect()
+}
+
+// Lays the cells out in as many columns as fit the width, like ls, in the
+// given fill order. When a cell is wider than the width, it's one per line.
+pub fn grid(cells: &[String], width: usize, separator: &str, fill: GridFill) -> String {
+    if cells.is_empty() {
+        return String::new();
+    }
+    let widths: Vec<usize> = cells.iter().map(|cell| unescaped_length(cell)).collect();
+    let separator_width = separator.len();
+    let fits = |shape: (usize, usize)| {
+        let column_widths = grid_column_widths(&widths, shape, &fill);
+        let separators = separator_width * (column_widths.len() - 1);
+        column_widths.iter().sum::<usize>() + separators <= width
+    };
+    // Every column takes at least one cell's width and a separator
+    let most_columns = cells.len().min(width / (separator_width + 1) + 1);
+    let (rows, columns) = (1..=most_columns)
+        .rev()
+        .map(|columns| fill.shape(cells.len(), columns))
+        .find(|&shape| fits(shape))
+        .unwrap_or((cells.len(), 1));
+
+    let column_widths = grid_column_widths(&widths, (rows, columns), &fill);
+    let mut lines = vec![];
+    for row in 0..rows {
+        let mut line = String::new();
+        for (column, column_width) in column_widths.iter().enumerate() {
+            let Some(cell) = cells.get(fill.index(row, column, rows, columns)) else {
+                break;
+            };
+            if column > 0 {
+                line.push_str(separator);
+            }
+            // Nothing trails the last cell on a line
+            let next = fill.index(row, column + 1, rows, columns);
+            match column + 1 < columns && next < cells.len() {
+                true => line.push_str(&pad_right(cell.clone(), column_width)),
+                false => line.push_str(cell),
+            }
+        }
+        lines.push(line);
+    }
+    lines.join("\n")
+}
+
+// Joins the items with commas, moving an item to the next line when it and
+// the comma after it wouldn't fit in the width, the way ls -m wraps
+pub fn comma_list(items: &[String], width: usize) -> String {
+    let mut out = String::new();
+    let mut position: usize = 0;
+    for (index, item) in items.iter().enumerate() {
+        let item_width = unescaped_length(item);
+        if index > 0 {
+            match position.saturating_add(item_width + 2) < width {
+                true => {
+                    out.push_str(", ");
+                    position += 2;
+                }
+                false => {
+                    out.push_str(",\n");
+                    position = 0;
+                }
+            }
+        }
+        out.push_str(item);
+        position = position.saturating_add(item_width);
+    }
+    out
+}
+
 fn col_max_size_map(input_data: &[Vec<String>]) -> HashMap<usize, usize> {
     let mut col_max_size_map: HashMap<usize, usize> = HashMap::new();
     for row in input_data {
@@ -104,7 +1168,10 @@ fn validate_table_equality(input_data: &Vec<Vec<String>>, num_cols: usize) -> Re
 }
 
 pub fn table(input_data: Vec<Vec<String>>, align: TableAlignment) -> Result<String, &'static str> {
-    let num_cols = input_data[0].len();
+    let num_cols = match input_data.first() {
+        Some(row) => row.len(),
+        None => return Ok(String::new()),
+    };
 
     // Validate
     validate_table_equality(&input_data, num_cols)?;
@@ -113,7 +1180,7 @@ pub fn table(input_data: Vec<Vec<String>>, align: TableAlignment) -> Result<Stri
     let output_string = input_data
         .iter()
         .map(|row| {
-            return row
+            row
                 .iter()
                 .enumerate()
                 .map(|(index, col)| {
@@ -128,16 +1195,387 @@ pub fn table(input_data: Vec<Vec<String>>, align: TableAlignment) -> Result<Stri
                                     col.clone()
                                 }
                             }
+                            TableAlignment::LeftTextColumns(ref left_columns) => {
+                                let is_numeric = col.chars().all(|c| c.is_ascii_digit());
+                                if index == num_cols - 1 {
+                                    col.clone()
+                                } else if left_columns.contains(&index) && !is_numeric {
+                                    pad_right(col.clone(), max_length)
+                                } else {
+                                    pad_left(col.clone(), max_length)
+                                }
+                            }
                         };
                     }
 
                     col.to_string()
                 })
                 .collect::<Vec<String>>()
-                .join(" ");
+                .join(" ")
         })
         .collect::<Vec<String>>()
         .join("\n");
 
     Ok(output_string)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn directory_escape(palette: &Palette) -> &str {
+        palette.style(&[StyleClass::Directory], "dir").unwrap()
+    }
+
+    #[test]
+    fn colorfgbg_picks_the_palette_by_background() {
+        for (colorfgbg, escape) in [
+            (Some("15;0"), "\x1b[34;1m"),
+            (Some("0;15"), "\x1b[34m"),
+            (Some("0;7"), "\x1b[34m"),
+            (Some("0;default;15"), "\x1b[34m"),
+            (Some("15;default;0"), "\x1b[34;1m"),
+            (Some("default"), "\x1b[34;1m"),
+            (Some(""), "\x1b[34;1m"),
+            (None, "\x1b[34;1m"),
+        ] {
+            let palette = Palette::from_colorfgbg(colorfgbg);
+            assert_eq!(directory_escape(&palette), escape, "{:?}", colorfgbg);
+        }
+    }
+
+    #[test]
+    fn light_palette_drops_bold_from_suffix_styles() {
+        let dark = Palette::from_colorfgbg(Some("15;0"));
+        let light = Palette::from_colorfgbg(Some("0;15"));
+        assert_eq!(
+            dark.paint(&[StyleClass::File], "a.zip"),
+            "\x1b[31;1ma.zip\x1b[0m"
+        );
+        assert_eq!(
+            light.paint(&[StyleClass::File], "a.zip"),
+            "\x1b[31ma.zip\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn ls_colors_overrides_the_detected_palette() {
+        let mut palette = Palette::from_colorfgbg(Some("0;15"));
+        palette.apply_ls_colors("di=01;35");
+        assert_eq!(directory_escape(&palette), "\x1b[01;35m");
+    }
+
+    #[test]
+    fn human_readable_precision() {
+        for (bytes, zero, one, two) in [
+            (0, "0", "0", "0"),
+            (1023, "1023", "1023", "1023"),
+            (1024, "1K", "1.0K", "1.00K"),
+            (1025, "2K", "1.1K", "1.01K"),
+            (4096, "4K", "4.0K", "4.00K"),
+            (223_000_000, "213M", "212.7M", "212.67M"),
+        ] {
+            assert_eq!(bytes_to_human_readable(bytes, 0), zero);
+            assert_eq!(bytes_to_human_readable(bytes, 1), one);
+            assert_eq!(bytes_to_human_readable(bytes, 2), two);
+        }
+    }
+
+    // Rounding up to 1024 of a unit moves to the next unit
+    #[test]
+    fn human_readable_rounds_up_into_the_next_unit() {
+        let just_under_a_mebibyte = 1024 * 1024 - 1;
+        assert_eq!(bytes_to_human_readable(just_under_a_mebibyte, 0), "1M");
+        assert_eq!(bytes_to_human_readable(just_under_a_mebibyte, 1), "1.0M");
+        assert_eq!(bytes_to_human_readable(just_under_a_mebibyte, 2), "1.00M");
+        // 1023.95K only reaches 1024.0K with one decimal
+        let kibibytes = 1023 * 1024 + 972;
+        assert_eq!(bytes_to_human_readable(kibibytes, 2), "1023.95K");
+        assert_eq!(bytes_to_human_readable(kibibytes, 1), "1.0M");
+        assert_eq!(bytes_to_human_readable(kibibytes, 3), "1023.950K");
+    }
+
+    #[test]
+    fn style_specs() {
+        for (spec, escape) in [
+            ("red", "\x1b[31m"),
+            ("bright-blue", "\x1b[94m"),
+            ("bold,red", "\x1b[1;31m"),
+            ("underline,bright-white,reverse", "\x1b[4;97;7m"),
+            ("#ff8000", "\x1b[38;2;255;128;0m"),
+        ] {
+            assert_eq!(parse_style_spec(spec).as_deref(), Ok(escape), "{}", spec);
+        }
+    }
+
+    // The error names the token that couldn't be read
+    #[test]
+    fn invalid_style_specs() {
+        for (spec, token) in [
+            ("purple", "purple"),
+            ("bold,purple", "purple"),
+            ("#ff80", "#ff80"),
+            ("#gg0000", "#gg0000"),
+            ("bright-bold", "bright-bold"),
+            ("", ""),
+        ] {
+            assert_eq!(parse_style_spec(spec), Err(token.to_string()), "{}", spec);
+        }
+    }
+
+    #[test]
+    fn override_wins_over_ls_colors() {
+        let mut palette = Palette::from_colorfgbg(None);
+        palette.apply_ls_colors("di=01;35");
+        palette.set(StyleClass::Directory, parse_style_spec("green").unwrap());
+        assert_eq!(directory_escape(&palette), "\x1b[32m");
+    }
+
+    #[test]
+    fn highlights_are_merged_byte_ranges() {
+        let patterns = |list: &[&str]| list.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            find_highlights("conf.d.conf", &patterns(&["conf"]), false),
+            [(0, 4), (7, 11)]
+        );
+        // Overlapping and touching matches make one range
+        assert_eq!(
+            find_highlights("abcdef", &patterns(&["abc", "bcd", "ef"]), false),
+            [(0, 6)]
+        );
+        assert_eq!(find_highlights("aaa", &patterns(&["aa"]), false), [(0, 2)]);
+        // Ranges are in bytes, with case folded per character
+        assert_eq!(
+            find_highlights("CAFÉ.txt", &patterns(&["é"]), false),
+            [(3, 5)]
+        );
+        assert_eq!(find_highlights("CAFÉ.txt", &patterns(&["é"]), true), []);
+        assert_eq!(find_highlights("name", &patterns(&[""]), false), []);
+    }
+
+    #[test]
+    fn grid_fill_orders() {
+        let cells: Vec<String> = ["a", "bb", "ccc", "d", "ee"]
+            .iter()
+            .map(|cell| cell.to_string())
+            .collect();
+        assert_eq!(
+            grid(&cells, 10, "  ", GridFill::Down),
+            "a    d\nbb   ee\nccc"
+        );
+        assert_eq!(
+            grid(&cells, 10, "  ", GridFill::Across),
+            "a  bb  ccc\nd  ee"
+        );
+        assert_eq!(grid(&cells, 1, "  ", GridFill::Across), "a\nbb\nccc\nd\nee");
+        assert_eq!(grid(&[], 80, "  ", GridFill::Down), "");
+    }
+
+    // Escapes take no columns, so styled cells line up like plain ones
+    #[test]
+    fn grid_widths_skip_escapes() {
+        let cells = vec![
+            styled("\x1b[34;1m", "dir"),
+            String::from("file"),
+            String::from("x"),
+        ];
+        assert_eq!(
+            grid(&cells, 11, "  ", GridFill::Across),
+            "\x1b[34;1mdir\x1b[0m  file\nx"
+        );
+        assert_eq!(
+            grid(&cells, 13, "  ", GridFill::Across),
+            "\x1b[34;1mdir\x1b[0m  file  x"
+        );
+    }
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|item| item.to_string()).collect()
+    }
+
+    #[test]
+    fn comma_lists_wrap_before_a_name_that_would_not_fit() {
+        let names = strings(&["alpha", "beta", "delta", "epsilon", "eta"]);
+        assert_eq!(comma_list(&names, 80), "alpha, beta, delta, epsilon, eta");
+        // A line and its comma stay under the width, like ls -m
+        assert_eq!(comma_list(&names, 20), "alpha, beta, delta,\nepsilon, eta");
+        assert_eq!(comma_list(&names, 19), "alpha, beta, delta,\nepsilon, eta");
+        assert_eq!(comma_list(&names, 18), "alpha, beta,\ndelta, epsilon,\neta");
+        assert_eq!(
+            comma_list(&names, 1),
+            "alpha,\nbeta,\ndelta,\nepsilon,\neta"
+        );
+    }
+
+    #[test]
+    fn comma_lists_keep_long_names_whole() {
+        let names = strings(&["a", "averyverylongname", "b", "c"]);
+        assert_eq!(comma_list(&names, 10), "a,\naveryverylongname,\nb, c");
+        assert_eq!(comma_list(&strings(&["only"]), 2), "only");
+        assert_eq!(comma_list(&[], 80), "");
+    }
+
+    #[test]
+    fn comma_lists_measure_without_escapes() {
+        let names = vec![
+            styled("\x1b[34;1m", "dir"),
+            String::from("file"),
+            String::from("x"),
+        ];
+        assert_eq!(comma_list(&names, 13), "\x1b[34;1mdir\x1b[0m, file, x");
+        assert_eq!(comma_list(&names, 12), "\x1b[34;1mdir\x1b[0m, file,\nx");
+    }
+
+    // The quotes and text together, as they are written
+    fn quoted(style: QuotingStyle, name: &[u8]) -> String {
+        let (quote, text) = style.quote(name, false);
+        format!("{}{}{}", quote, text, quote)
+    }
+
+    #[test]
+    fn c_style_quotes_and_escapes() {
+        for (name, expected) in [
+            (&b"plain"[..], "\"plain\""),
+            (b"my \"file\".txt", "\"my \\\"file\\\".txt\""),
+            (b"back\\slash", "\"back\\\\slash\""),
+            (b"tab\there", "\"tab\\there\""),
+            (b"new\nline", "\"new\\nline\""),
+            (b"it's", "\"it's\""),
+            (b"with space", "\"with space\""),
+            (b"", "\"\""),
+        ] {
+            assert_eq!(
+                quoted(QuotingStyle::C, name),
+                expected,
+                "{}",
+                String::from_utf8_lossy(name)
+            );
+        }
+    }
+
+    // Each style's table, checked against GNU ls under LC_ALL=C.UTF-8,
+    // other than control characters coming out as ? where it writes them
+    fn assert_quotes(style: QuotingStyle, table: &[(&[u8], &str)]) {
+        for (name, expected) in table {
+            assert_eq!(
+                quoted(style, name),
+                *expected,
+                "{}",
+                String::from_utf8_lossy(name)
+            );
+        }
+    }
+
+    #[test]
+    fn literal_style_hides_only_control_characters() {
+        assert_quotes(
+            QuotingStyle::Literal,
+            &[
+                (b"plain", "plain"),
+                (b"with space", "with space"),
+                (b"say \"hi\"", "say \"hi\""),
+                (b"back\\slash", "back\\slash"),
+                (b"tab\there", "tab?here"),
+                (b"new\nline", "new?line"),
+                (b"bad\x80", "bad\u{fffd}"),
+            ],
+        );
+        assert_eq!(QuotingStyle::Literal.quote(b"bad\x80", true).1, "bad?");
+    }
+
+    #[test]
+    fn raw_bytes_come_back_as_they_were() {
+        for name in [
+            &b"plain"[..],
+            b"caf\xe9",
+            b"\x80\xff",
+            b"tab\there\xc3",
+            "caf\u{e9}".as_bytes(),
+        ] {
+            let text = with_raw_bytes(name);
+            assert_eq!(raw_bytes(&text), name);
+        }
+        // A stand-in takes a column, as the byte does on a terminal
+        assert_eq!(display_width(&with_raw_bytes(b"x\xffy")), 3);
+    }
+
+    #[test]
+    fn shell_style_quotes_only_what_a_shell_would_misread() {
+        assert_quotes(
+            QuotingStyle::Shell,
+            &[
+                (b"plain", "plain"),
+                (b"a#b", "a#b"),
+                (b"with space", "'with space'"),
+                (b"$HOME", "'$HOME'"),
+                (b"~tilde", "'~tilde'"),
+                (b"{", "'{'"),
+                (b"it's", "\"it's\""),
+                (b"it's $x", "'it'\\''s $x'"),
+                (b"say \"hi\"", "'say \"hi\"'"),
+                (b"back\\slash", "'back\\slash'"),
+                (b"tab\there", "'tab?here'"),
+                (b"", "''"),
+            ],
+        );
+    }
+
+    #[test]
+    fn shell_always_style_quotes_every_name() {
+        assert_quotes(
+            QuotingStyle::ShellAlways,
+            &[
+                (b"plain", "'plain'"),
+                (b"a#b", "'a#b'"),
+                (b"with space", "'with space'"),
+                (b"it's", "\"it's\""),
+                (b"new\nline", "'new?line'"),
+            ],
+        );
+    }
+
+    #[test]
+    fn shell_escape_style_writes_control_characters_as_dollar_quotes() {
+        assert_quotes(
+            QuotingStyle::ShellEscape,
+            &[
+                (b"plain", "plain"),
+                (b"with space", "'with space'"),
+                (b"it's", "\"it's\""),
+                (b"tab\there", "'tab'$'\\t''here'"),
+                (b"new\nline", "'new'$'\\n''line'"),
+                (b"bad\x80", "'bad'$'\\200'"),
+                (b"\x1b[0m", "''$'\\033''[0m'"),
+            ],
+        );
+    }
+
+    #[test]
+    fn shell_escape_always_style_quotes_every_name() {
+        assert_quotes(
+            QuotingStyle::ShellEscapeAlways,
+            &[
+                (b"plain", "'plain'"),
+                (b"a#b", "'a#b'"),
+                (b"tab\there", "'tab'$'\\t''here'"),
+            ],
+        );
+    }
+
+    #[test]
+    fn escape_style_backslashes_spaces_and_control_characters() {
+        assert_quotes(
+            QuotingStyle::Escape,
+            &[
+                (b"plain", "plain"),
+                (b"with space", "with\\ space"),
+                (b"say \"hi\"", "say\\ \"hi\""),
+                (b"back\\slash", "back\\\\slash"),
+                (b"tab\there", "tab\\there"),
+                (b"new\nline", "new\\nline"),
+                (b"bad\x80", "bad\\200"),
+                (b"bell\x07", "bell\\a"),
+            ],
+        );
+    }
+}
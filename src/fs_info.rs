@@ -0,0 +1,247 @@
+use std::{ffi::CString, fs, io, os::unix::ffi::OsStrExt, path::Path};
+
+use crate::format;
+
+const MOUNTS_PATH: &str = "/proc/self/mounts";
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+mod sys {
+    use std::os::raw::{c_char, c_int, c_ulong};
+
+    // fsblkcnt_t and fsfilcnt_t
+    #[cfg(target_os = "linux")]
+    pub type Count = u64;
+    #[cfg(target_os = "macos")]
+    pub type Count = u32;
+
+    #[repr(C)]
+    pub struct StatVfs {
+        pub f_bsize: c_ulong,
+        pub f_frsize: c_ulong,
+        pub f_blocks: Count,
+        pub f_bfree: Count,
+        pub f_bavail: Count,
+        pub f_files: Count,
+        pub f_ffree: Count,
+        pub f_favail: Count,
+        pub f_fsid: c_ulong,
+        pub f_flag: c_ulong,
+        pub f_namemax: c_ulong,
+        #[cfg(target_os = "linux")]
+        pub f_spare: [c_int; 6],
+    }
+
+    extern "C" {
+        pub fn statvfs(path: *const c_char, buf: *mut StatVfs) -> c_int;
+    }
+}
+
+// The parts of statvfs(3) that --fs-info reports, all counts in units of fragment_size
+pub struct FsStats {
+    pub fragment_size: u64,
+    pub blocks: u64,
+    pub blocks_free: u64,
+    pub blocks_available: u64,
+    pub files_free: u64,
+}
+
+impl FsStats {
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    pub fn of(path: &Path) -> io::Result<FsStats> {
+        let c_path = CString::new(path.as_os_str().as_bytes())?;
+        // SAFETY: StatVfs is plain old data, so all zeroes is a valid value
+        let mut buf: sys::StatVfs = unsafe { std::mem::zeroed() };
+        // SAFETY: c_path is NUL terminated and buf is a valid, writable statvfs
+        if unsafe { sys::statvfs(c_path.as_ptr(), &mut buf) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(FsStats {
+            fragment_size: buf.f_frsize as u64,
+            blocks: buf.f_blocks as u64,
+            blocks_free: buf.f_bfree as u64,
+            blocks_available: buf.f_bavail as u64,
+            files_free: buf.f_ffree as u64,
+        })
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    pub fn of(_path: &Path) -> io::Result<FsStats> {
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
+
+    pub fn total_bytes(&self) -> u64 {
+        self.blocks * self.fragment_size
+    }
+
+    pub fn used_bytes(&self) -> u64 {
+        self.blocks.saturating_sub(self.blocks_free) * self.fragment_size
+    }
+
+    // Like df, the share of the space available to unprivileged users, rounded up
+    pub fn used_percent(&self) -> u64 {
+        let used = self.blocks.saturating_sub(self.blocks_free);
+        let usable = used + self.blocks_available;
+        if usable == 0 {
+            return 0;
+        }
+        (used * 100).div_ceil(usable)
+    }
+}
+
+pub struct Mount {
+    pub device: String,
+    pub fs_type: String,
+}
+
+// The mount table escapes whitespace in paths as octal, e.g. `\040`
+fn unescape_mount_field(field: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = field.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            unescaped.push(ch);
+            continue;
+        }
+        let octal: String = chars.by_ref().take(3).collect();
+        match u8::from_str_radix(&octal, 8) {
+            Ok(byte) => unescaped.push(byte as char),
+            Err(_) => {
+                unescaped.push(ch);
+                unescaped.push_str(&octal);
+            }
+        }
+    }
+    unescaped
+}
+
+// Finds the mount holding the path from the deepest matching mount point
+pub fn find_mount(path: &Path) -> Option<Mount> {
+    let path = fs::canonicalize(path).ok()?;
+    let mounts = fs::read_to_string(MOUNTS_PATH).ok()?;
+
+    let mut best: Option<(usize, Mount)> = None;
+    for line in mounts.lines() {
+        let fields: Vec<&str> = line.split(' ').collect();
+        if fields.len() < 3 {
+            continue;
+        }
+        let mount_point = unescape_mount_field(fields[1]);
+        if !path.starts_with(&mount_point) {
+            continue;
+        }
+        // Later entries are mounted over earlier ones at the same point
+        let depth = Path::new(&mount_point).components().count();
+        if best
+            .as_ref()
+            .is_none_or(|(best_depth, _)| depth >= *best_depth)
+        {
+            let mount = Mount {
+                device: unescape_mount_field(fields[0]),
+                fs_type: fields[2].to_string(),
+            };
+            best = Some((depth, mount));
+        }
+    }
+
+    best.map(|(_, mount)| mount)
+}
+
+// Human readable with the given precision, or raw when there is none
+fn size_display(bytes: u64, human_precision: Option<usize>) -> String {
+    match human_precision {
+        Some(precision) => format::bytes_to_human_readable(bytes, precision),
+        None => bytes.to_string(),
+    }
+}
+
+// e.g. "filesystem: /dev/sda2 (ext4) — 412G used / 931G total (44%), 9.1M inodes free"
+pub fn summary(mount: Option<&Mount>, stats: &FsStats, human_precision: Option<usize>) -> String {
+    let name = match mount {
+        Some(mount) => format!("{} ({})", mount.device, mount.fs_type),
+        None => String::from("?"),
+    };
+    format!(
+        "filesystem: {} — {} used / {} total ({}%), {} inodes free",
+        name,
+        size_display(stats.used_bytes(), human_precision),
+        size_display(stats.total_bytes(), human_precision),
+        stats.used_percent(),
+        size_display(stats.files_free, human_precision),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 1000 blocks of 4K, 600 free of which 500 are available to users
+    fn stats() -> FsStats {
+        FsStats {
+            fragment_size: 4096,
+            blocks: 1000,
+            blocks_free: 600,
+            blocks_available: 500,
+            files_free: 9_100_000,
+        }
+    }
+
+    #[test]
+    fn used_and_total_bytes() {
+        assert_eq!(stats().total_bytes(), 4_096_000);
+        assert_eq!(stats().used_bytes(), 1_638_400);
+    }
+
+    #[test]
+    fn percentage_leaves_out_reserved_blocks_and_rounds_up() {
+        // 400 used of the 900 users can have is 44.4%
+        assert_eq!(stats().used_percent(), 45);
+        let full = FsStats {
+            blocks_free: 0,
+            blocks_available: 0,
+            ..stats()
+        };
+        assert_eq!(full.used_percent(), 100);
+        let exact = FsStats {
+            blocks_free: 500,
+            ..stats()
+        };
+        assert_eq!(exact.used_percent(), 50);
+    }
+
+    #[test]
+    fn empty_filesystem_is_zero_percent() {
+        let empty = FsStats {
+            fragment_size: 4096,
+            blocks: 0,
+            blocks_free: 0,
+            blocks_available: 0,
+            files_free: 0,
+        };
+        assert_eq!(empty.used_percent(), 0);
+    }
+
+    #[test]
+    fn summary_line() {
+        let mount = Mount {
+            device: String::from("/dev/nvme0n1p2"),
+            fs_type: String::from("ext4"),
+        };
+        assert_eq!(
+            summary(Some(&mount), &stats(), None),
+            "filesystem: /dev/nvme0n1p2 (ext4) — 1638400 used / 4096000 total (45%), \
+             9100000 inodes free"
+        );
+        assert_eq!(
+            summary(None, &stats(), Some(1)),
+            "filesystem: ? — 1.6M used / 4.0M total (45%), 8.7M inodes free"
+        );
+    }
+
+    #[test]
+    fn mount_fields_unescape_octal() {
+        assert_eq!(unescape_mount_field("/mnt/my\\040disk"), "/mnt/my disk");
+        assert_eq!(unescape_mount_field("tab\\011"), "tab\t");
+        assert_eq!(unescape_mount_field("bad\\9x"), "bad\\9x");
+    }
+}
@@ -0,0 +1,299 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    ffi::{OsStr, OsString},
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+    rc::Rc,
+};
+
+// Starts each commit's line in the log, ahead of its time and author
+const COMMIT_MARKER: char = '\0';
+const LOG_FORMAT: &str = "--format=%x00%at%x09%an";
+
+// What `git status --porcelain` gives a path: its state in the index, then
+// in the work tree
+#[derive(Clone, Copy, PartialEq)]
+pub struct Status(char, char);
+
+impl Status {
+    const CLEAN: Status = Status(' ', ' ');
+    const IGNORED: Status = Status('!', '!');
+    const UNTRACKED: Status = Status('?', '?');
+
+    // e.g. "-M", a dash for a side without changes, or "I" when ignored
+    pub fn display(self) -> String {
+        match self {
+            Status::IGNORED => String::from("I"),
+            Status(index, work_tree) => [index, work_tree]
+                .iter()
+                .map(|side| if *side == ' ' { '-' } else { *side })
+                .collect(),
+        }
+    }
+
+    // Each side keeps the first change seen on it, so a directory shows
+    // something of what changed below it
+    fn merge(self, other: Status) -> Status {
+        let pick = |mine: char, theirs: char| if mine == ' ' { theirs } else { mine };
+        Status(pick(self.0, other.0), pick(self.1, other.1))
+    }
+}
+
+// The status of every changed, untracked or ignored path in a repository.
+// An untracked or ignored directory is listed by itself, not its contents.
+pub struct RepoStatus {
+    root: PathBuf,
+    statuses: HashMap<PathBuf, Status>,
+    // What changed below each directory, ignored paths left out
+    below: HashMap<PathBuf, Status>,
+}
+
+// What git ignores in one directory
+pub enum Ignored {
+    Names(HashSet<OsString>),
+    // The directory is ignored itself, or is inside one that is
+    All,
+}
+
+impl Ignored {
+    pub fn contains(&self, name: &OsStr) -> bool {
+        match self {
+            Ignored::Names(names) => names.contains(name),
+            Ignored::All => true,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Commit {
+    pub time: u64,
+    pub author: String,
+}
+
+// The last commit to touch each path in a repository, directories included
+pub struct RepoLog {
+    root: PathBuf,
+    commits: HashMap<PathBuf, Commit>,
+}
+
+thread_local! {
+    // Walked once per repository however many directories are listed in it
+    static REPO_LOGS: RefCell<HashMap<PathBuf, Rc<RepoLog>>> = RefCell::new(HashMap::new());
+    static REPO_STATUSES: RefCell<HashMap<PathBuf, Rc<RepoStatus>>> =
+        RefCell::new(HashMap::new());
+    // The repository root of each directory asked about, none outside of one
+    static REPO_ROOTS: RefCell<HashMap<PathBuf, Option<PathBuf>>> = RefCell::new(HashMap::new());
+}
+
+fn git(dir: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8(output.stdout).ok()
+}
+
+// One `git log --name-only` over the whole history, newest first, so the
+// first commit seen for a path is its last
+fn read_log(root: PathBuf) -> RepoLog {
+    let mut commits: HashMap<PathBuf, Commit> = HashMap::new();
+    let log = git(
+        &root,
+        &[
+            "-c",
+            "core.quotePath=false",
+            "log",
+            LOG_FORMAT,
+            "--name-only",
+        ],
+    )
+    .unwrap_or_default();
+
+    let mut current: Option<Commit> = None;
+    for line in log.lines() {
+        if let Some(header) = line.strip_prefix(COMMIT_MARKER) {
+            current = header.split_once('\t').map(|(time, author)| Commit {
+                time: time.parse().unwrap_or(0),
+                author: author.to_string(),
+            });
+            continue;
+        }
+        let Some(commit) = &current else {
+            continue;
+        };
+        if line.is_empty() {
+            continue;
+        }
+        // A directory changed whenever anything below it did
+        for path in Path::new(line)
+            .ancestors()
+            .filter(|p| !p.as_os_str().is_empty())
+        {
+            commits
+                .entry(path.to_path_buf())
+                .or_insert_with(|| commit.clone());
+        }
+    }
+
+    RepoLog { root, commits }
+}
+
+// One `git status -z` for the whole work tree. Each record is "XY path", and
+// a rename or copy is followed by the path it came from.
+fn read_status(root: PathBuf) -> RepoStatus {
+    let mut statuses = HashMap::new();
+    let mut below: HashMap<PathBuf, Status> = HashMap::new();
+    let output = git(&root, &["status", "--porcelain=v1", "-z", "--ignored"]).unwrap_or_default();
+
+    let mut records = output.split('\0');
+    while let Some(record) = records.next() {
+        let mut chars = record.chars();
+        let (Some(index), Some(work_tree), Some(' ')) = (chars.next(), chars.next(), chars.next())
+        else {
+            continue;
+        };
+        if matches!(index, 'R' | 'C') {
+            records.next();
+        }
+        let status = Status(index, work_tree);
+        let path = PathBuf::from(chars.as_str().trim_end_matches('/'));
+        if status != Status::IGNORED {
+            for dir in path
+                .ancestors()
+                .skip(1)
+                .filter(|dir| !dir.as_os_str().is_empty())
+            {
+                let merged = below
+                    .get(dir)
+                    .map_or(status, |known| known.merge(status));
+                below.insert(dir.to_path_buf(), merged);
+            }
+        }
+        statuses.insert(path, status);
+    }
+
+    RepoStatus {
+        root,
+        statuses,
+        below,
+    }
+}
+
+// The parent of a bare name like an operand is empty, meaning the current
+// directory
+fn or_current(dir: &Path) -> &Path {
+    match dir.as_os_str().is_empty() {
+        true => Path::new("."),
+        false => dir,
+    }
+}
+
+// The top of the work tree holding the directory, None outside of one or
+// without git installed
+fn repo_root(dir: &Path) -> Option<PathBuf> {
+    let dir = or_current(dir);
+    REPO_ROOTS.with(|roots| {
+        roots
+            .borrow_mut()
+            .entry(dir.to_path_buf())
+            .or_insert_with(|| {
+                git(dir, &["rev-parse", "--show-toplevel"])
+                    .map(|root| PathBuf::from(root.trim_end()))
+            })
+            .clone()
+    })
+}
+
+// The status of the repository holding the directory, None outside of one
+// or without git installed
+pub fn repo_status(dir: &Path) -> Option<Rc<RepoStatus>> {
+    let root = repo_root(dir)?;
+    REPO_STATUSES.with(|statuses| {
+        let mut statuses = statuses.borrow_mut();
+        let status = statuses
+            .entry(root.clone())
+            .or_insert_with(|| Rc::new(read_status(root)));
+        Some(Rc::clone(status))
+    })
+}
+
+// The names git ignores in the directory, none outside of a repository
+pub fn ignored(dir: &Path) -> Ignored {
+    let relative = repo_status(dir).and_then(|status| {
+        let dir = fs::canonicalize(dir).ok()?;
+        let relative = dir.strip_prefix(&status.root).ok()?.to_path_buf();
+        Some((status, relative))
+    });
+    let Some((status, dir)) = relative else {
+        return Ignored::Names(HashSet::new());
+    };
+    if dir
+        .ancestors()
+        .any(|dir| status.statuses.get(dir) == Some(&Status::IGNORED))
+    {
+        return Ignored::All;
+    }
+    let names = status
+        .statuses
+        .iter()
+        .filter(|(path, status)| **status == Status::IGNORED && path.parent() == Some(&dir))
+        .filter_map(|(path, _)| path.file_name().map(OsStr::to_os_string))
+        .collect();
+    Ignored::Names(names)
+}
+
+// The log of the repository holding the directory, None outside of one or
+// without git installed
+pub fn repo_log(dir: &Path) -> Option<Rc<RepoLog>> {
+    let root = repo_root(dir)?;
+    REPO_LOGS.with(|logs| {
+        let mut logs = logs.borrow_mut();
+        let log = logs
+            .entry(root.clone())
+            .or_insert_with(|| Rc::new(read_log(root)));
+        Some(Rc::clone(log))
+    })
+}
+
+// The entry's path inside the repository. Only the parent is resolved, so
+// a symlink is looked up as itself.
+fn relative_path(root: &Path, path: &Path) -> Option<PathBuf> {
+    let parent = fs::canonicalize(or_current(path.parent()?)).ok()?;
+    let path = parent.join(path.file_name()?);
+    path.strip_prefix(root).ok().map(Path::to_path_buf)
+}
+
+impl RepoLog {
+    // The last commit to touch the entry, by its path inside the repository
+    pub fn last_commit(&self, path: &Path) -> Option<&Commit> {
+        self.commits.get(&relative_path(&self.root, path)?)
+    }
+}
+
+impl RepoStatus {
+    // The entry's own status, else that of an untracked or ignored directory
+    // it is in, else what changed below it. Anything else is clean.
+    pub fn status(&self, path: &Path) -> Option<Status> {
+        let relative = relative_path(&self.root, path)?;
+        let inherited = relative
+            .ancestors()
+            .skip(1)
+            .filter_map(|dir| self.statuses.get(dir))
+            .find(|status| matches!(**status, Status::IGNORED | Status::UNTRACKED));
+        let status = self
+            .statuses
+            .get(&relative)
+            .or(inherited)
+            .or_else(|| self.below.get(&relative))
+            .copied()
+            .unwrap_or(Status::CLEAN);
+        Some(status)
+    }
+}
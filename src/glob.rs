@@ -0,0 +1,199 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+// A path segment that matches any number of directories, including none
+const RECURSIVE_SEGMENT: &str = "**";
+
+fn has_wildcards(pattern: &str) -> bool {
+    pattern.contains(['*', '?', '[', '\\'])
+}
+
+// Matches a bracket expression, `start` being the index just after the `[`.
+// Returns whether ch is in the set and the index after the closing `]`, or
+// None when the bracket is never closed and so is just a literal `[`.
+fn match_class(pattern: &[char], start: usize, ch: char) -> Option<(bool, usize)> {
+    let mut index = start;
+    let is_negated = index < pattern.len() && (pattern[index] == '!' || pattern[index] == '^');
+    if is_negated {
+        index += 1;
+    }
+
+    let mut is_matched = false;
+    // A `]` straight after the opening bracket is part of the set
+    let mut is_first = true;
+    while index < pattern.len() {
+        let class_char = pattern[index];
+        if class_char == ']' && !is_first {
+            return Some((is_matched != is_negated, index + 1));
+        }
+        is_first = false;
+
+        if index + 2 < pattern.len() && pattern[index + 1] == '-' && pattern[index + 2] != ']' {
+            is_matched |= (class_char..=pattern[index + 2]).contains(&ch);
+            index += 3;
+        } else {
+            is_matched |= class_char == ch;
+            index += 1;
+        }
+    }
+    None
+}
+
+// Matches a single non-star pattern element, returning the index after it
+fn match_one(pattern: &[char], index: usize, ch: char) -> Option<usize> {
+    match pattern[index] {
+        '?' => Some(index + 1),
+        '[' => match match_class(pattern, index + 1, ch) {
+            Some((is_matched, next)) => is_matched.then_some(next),
+            None => (ch == '[').then_some(index + 1),
+        },
+        '\\' if index + 1 < pattern.len() => (pattern[index + 1] == ch).then_some(index + 2),
+        literal => (literal == ch).then_some(index + 1),
+    }
+}
+
+// Shell-style matching of a whole name against `*`, `?` and `[...]`, with
+// `\` escaping the next character. Like the shell, a leading dot has to be
+// matched by a literal dot rather than a wildcard.
+pub fn is_match(pattern: &str, name: &str) -> bool {
+    if name.starts_with('.') && !(pattern.starts_with('.') || pattern.starts_with("\\.")) {
+        return false;
+    }
+
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    let (mut pattern_index, mut name_index) = (0, 0);
+    // Where to resume after the last star if the rest fails to match
+    let mut star: Option<(usize, usize)> = None;
+    while name_index < name.len() {
+        if pattern_index < pattern.len() && pattern[pattern_index] == '*' {
+            star = Some((pattern_index + 1, name_index));
+            pattern_index += 1;
+            continue;
+        }
+
+        let next = match pattern_index < pattern.len() {
+            true => match_one(&pattern, pattern_index, name[name_index]),
+            false => None,
+        };
+        match (next, star) {
+            (Some(next), _) => {
+                pattern_index = next;
+                name_index += 1;
+            }
+            // Let the star swallow one more character and try again
+            (None, Some((star_pattern_index, star_name_index))) => {
+                pattern_index = star_pattern_index;
+                name_index = star_name_index + 1;
+                star = Some((star_pattern_index, star_name_index + 1));
+            }
+            (None, None) => return false,
+        }
+    }
+
+    pattern[pattern_index..].iter().all(|ch| *ch == '*')
+}
+
+fn read_dir_names(dir: &Path) -> Vec<String> {
+    let dir = match dir.as_os_str().is_empty() {
+        true => Path::new("."),
+        false => dir,
+    };
+    match fs::read_dir(dir) {
+        Ok(read_dir) => read_dir
+            .filter_map(|d| d.ok())
+            .filter_map(|d| d.file_name().into_string().ok())
+            .collect(),
+        Err(_) => vec![],
+    }
+}
+
+fn expand_segments(base: &Path, segments: &[&str], matches: &mut Vec<PathBuf>) {
+    let Some((segment, rest)) = segments.split_first() else {
+        matches.push(base.to_path_buf());
+        return;
+    };
+
+    if *segment == RECURSIVE_SEGMENT {
+        expand_segments(base, rest, matches);
+        // Symlinked directories aren't followed, so cycles can't recurse forever
+        for name in read_dir_names(base).iter().filter(|n| !n.starts_with('.')) {
+            let path = base.join(name);
+            if fs::symlink_metadata(&path).is_ok_and(|meta| meta.is_dir()) {
+                expand_segments(&path, segments, matches);
+            }
+        }
+        return;
+    }
+
+    if !has_wildcards(segment) {
+        let path = base.join(segment);
+        if fs::symlink_metadata(&path).is_ok() {
+            expand_segments(&path, rest, matches);
+        }
+        return;
+    }
+
+    for name in read_dir_names(base) {
+        if is_match(segment, &name) {
+            expand_segments(&base.join(name), rest, matches);
+        }
+    }
+}
+
+// Expands a pattern against the filesystem, where a `**` segment matches
+// any number of nested directories. Matches are sorted like the shell's.
+pub fn expand(pattern: &str) -> Vec<PathBuf> {
+    let (root, relative_pattern) = match pattern.strip_prefix('/') {
+        Some(rest) => (PathBuf::from("/"), rest),
+        None => (PathBuf::new(), pattern),
+    };
+    let segments: Vec<&str> = relative_pattern
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect();
+
+    let mut matches = vec![];
+    expand_segments(&root, &segments, &mut matches);
+    // Consecutive `**` segments can reach the same path more than once
+    matches.sort();
+    matches.dedup();
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wildcards() {
+        for (pattern, name, is_matched) in [
+            ("*.rs", "main.rs", true),
+            ("*.rs", "main.rs.bak", false),
+            ("?.rs", "a.rs", true),
+            ("?.rs", "ab.rs", false),
+            ("[abc].rs", "b.rs", true),
+            ("[!abc].rs", "b.rs", false),
+            ("[a-c]*", "cat", true),
+            ("[]]", "]", true),
+            ("[", "[", true),
+            ("\\*", "*", true),
+            ("\\*", "a", false),
+            ("*", "", true),
+            ("a*b*c", "aXbYbZc", true),
+        ] {
+            assert_eq!(is_match(pattern, name), is_matched, "{} {}", pattern, name);
+        }
+    }
+
+    #[test]
+    fn leading_dot_needs_a_literal_dot() {
+        assert!(!is_match("*", ".hidden"));
+        assert!(!is_match("?hidden", ".hidden"));
+        assert!(is_match(".*", ".hidden"));
+        assert!(is_match("\\.hidden", ".hidden"));
+    }
+}
@@ -0,0 +1,126 @@
+use std::path::Path;
+
+use crate::mode::{EntryKind, Mode};
+
+// Extensions, compared case-insensitively
+const IMAGE_EXTENSIONS: &[&str] = &[
+    "bmp", "gif", "heic", "ico", "jpeg", "jpg", "png", "svg", "tif", "tiff", "webp",
+];
+const ARCHIVE_EXTENSIONS: &[&str] = &[
+    "7z", "bz2", "deb", "gz", "jar", "rar", "rpm", "tar", "tgz", "xz", "zip", "zst",
+];
+const AUDIO_EXTENSIONS: &[&str] = &["aac", "flac", "m4a", "mp3", "ogg", "opus", "wav"];
+const VIDEO_EXTENSIONS: &[&str] = &["avi", "m4v", "mkv", "mov", "mp4", "webm"];
+const DOCUMENT_EXTENSIONS: &[&str] = &["doc", "docx", "md", "odt", "pdf", "rtf", "txt"];
+
+// Nerd Font glyphs for extensions with one of their own, compared
+// case-insensitively. Anything else gets its class's glyph.
+const NERD_FONT_EXTENSIONS: &[(&str, &str)] = &[
+    ("c", "\u{e61e}"),
+    ("cpp", "\u{e61d}"),
+    ("css", "\u{e749}"),
+    ("go", "\u{e627}"),
+    ("h", "\u{f0fd}"),
+    ("html", "\u{e60e}"),
+    ("java", "\u{e738}"),
+    ("js", "\u{e74e}"),
+    ("json", "\u{e60b}"),
+    ("lock", "\u{f023}"),
+    ("md", "\u{f48a}"),
+    ("pdf", "\u{f1c1}"),
+    ("py", "\u{e606}"),
+    ("rb", "\u{e739}"),
+    ("rs", "\u{e7a8}"),
+    ("sh", "\u{f489}"),
+    ("toml", "\u{e615}"),
+    ("ts", "\u{e628}"),
+    ("yaml", "\u{e615}"),
+    ("yml", "\u{e615}"),
+];
+
+// What an entry looks like at a glance, shared by every indicator style
+pub enum FileClass {
+    Directory,
+    Symlink,
+    Executable,
+    Image,
+    Archive,
+    Audio,
+    Video,
+    Document,
+    File,
+}
+
+pub fn classify(path: &Path, kind: EntryKind, mode: Option<Mode>) -> FileClass {
+    if path.is_symlink() {
+        return FileClass::Symlink;
+    }
+
+    match kind {
+        EntryKind::Dir => return FileClass::Directory,
+        EntryKind::File if mode.is_some_and(|mode| mode.is_executable()) => {
+            return FileClass::Executable
+        }
+        _ => {}
+    }
+
+    let extension = match path.extension() {
+        Some(ext) => ext.to_string_lossy().to_lowercase(),
+        None => return FileClass::File,
+    };
+    let extension = extension.as_str();
+    if IMAGE_EXTENSIONS.contains(&extension) {
+        FileClass::Image
+    } else if ARCHIVE_EXTENSIONS.contains(&extension) {
+        FileClass::Archive
+    } else if AUDIO_EXTENSIONS.contains(&extension) {
+        FileClass::Audio
+    } else if VIDEO_EXTENSIONS.contains(&extension) {
+        FileClass::Video
+    } else if DOCUMENT_EXTENSIONS.contains(&extension) {
+        FileClass::Document
+    } else {
+        FileClass::File
+    }
+}
+
+// Standard emoji, so they render without a patched font. Some of these are
+// text-presentation characters made wide by a trailing U+FE0F.
+pub fn emoji(class: &FileClass) -> &'static str {
+    match class {
+        FileClass::Directory => "📁",
+        FileClass::Symlink => "🔗",
+        FileClass::Executable => "⚙\u{fe0f}",
+        FileClass::Image => "🖼\u{fe0f}",
+        FileClass::Archive => "📦",
+        FileClass::Audio => "🎵",
+        FileClass::Video => "🎬",
+        FileClass::Document => "📝",
+        FileClass::File => "📄",
+    }
+}
+
+// Nerd Font glyphs, which need a patched font but are a single column wide
+pub fn nerd_font(path: &Path, class: &FileClass) -> &'static str {
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase());
+    let glyph = match class {
+        FileClass::Directory | FileClass::Symlink | FileClass::Executable => None,
+        _ => NERD_FONT_EXTENSIONS
+            .iter()
+            .find(|(known, _)| Some(*known) == extension.as_deref())
+            .map(|(_, glyph)| *glyph),
+    };
+    glyph.unwrap_or(match class {
+        FileClass::Directory => "\u{f115}",
+        FileClass::Symlink => "\u{f0c1}",
+        FileClass::Executable => "\u{f489}",
+        FileClass::Image => "\u{f1c5}",
+        FileClass::Archive => "\u{f410}",
+        FileClass::Audio => "\u{f001}",
+        FileClass::Video => "\u{f03d}",
+        FileClass::Document => "\u{f15c}",
+        FileClass::File => "\u{f15b}",
+    })
+}
@@ -0,0 +1,132 @@
+use std::fmt::Write;
+
+const INDENT: &str = "  ";
+
+pub enum Value {
+    Null,
+    Number(u64),
+    String(String),
+    Array(Vec<Value>),
+    // Keys keep the order they were added in
+    Object(Vec<(&'static str, Value)>),
+}
+
+// Quotes the text as a JSON string, escaping quotes, backslashes and
+// control characters
+fn quote(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for ch in text.chars() {
+        match ch {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            '\u{8}' => quoted.push_str("\\b"),
+            '\u{c}' => quoted.push_str("\\f"),
+            ch if (ch as u32) < 0x20 => {
+                let _ = write!(quoted, "\\u{:04x}", ch as u32);
+            }
+            ch => quoted.push(ch),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+impl Value {
+    // Pretty printed, one member per line
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        self.render_into(&mut out, 0);
+        out
+    }
+
+    fn render_into(&self, out: &mut String, depth: usize) {
+        let indent = INDENT.repeat(depth + 1);
+        match self {
+            Value::Null => out.push_str("null"),
+            Value::Number(number) => out.push_str(&number.to_string()),
+            Value::String(text) => out.push_str(&quote(text)),
+            Value::Array(items) if items.is_empty() => out.push_str("[]"),
+            Value::Array(items) => {
+                out.push_str("[\n");
+                for (index, item) in items.iter().enumerate() {
+                    if index > 0 {
+                        out.push_str(",\n");
+                    }
+                    out.push_str(&indent);
+                    item.render_into(out, depth + 1);
+                }
+                out.push('\n');
+                out.push_str(&INDENT.repeat(depth));
+                out.push(']');
+            }
+            Value::Object(members) if members.is_empty() => out.push_str("{}"),
+            Value::Object(members) => {
+                out.push_str("{\n");
+                for (index, (key, value)) in members.iter().enumerate() {
+                    if index > 0 {
+                        out.push_str(",\n");
+                    }
+                    out.push_str(&indent);
+                    out.push_str(&quote(key));
+                    out.push_str(": ");
+                    value.render_into(out, depth + 1);
+                }
+                out.push('\n');
+                out.push_str(&INDENT.repeat(depth));
+                out.push('}');
+            }
+        }
+    }
+}
+
+// A missing value is null
+impl From<Option<u64>> for Value {
+    fn from(number: Option<u64>) -> Value {
+        number.map_or(Value::Null, Value::Number)
+    }
+}
+
+impl From<Option<String>> for Value {
+    fn from(text: Option<String>) -> Value {
+        text.map_or(Value::Null, Value::String)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strings_escape_quotes_backslashes_and_control_characters() {
+        for (text, expected) in [
+            ("plain", r#""plain""#),
+            ("say \"hi\"", r#""say \"hi\"""#),
+            ("back\\slash", r#""back\\slash""#),
+            ("new\nline", r#""new\nline""#),
+            ("cr\rtab\t", r#""cr\rtab\t""#),
+            ("\u{8}\u{c}", r#""\b\f""#),
+            ("\u{1}\u{1b}\u{1f}", r#""\u0001\u001b\u001f""#),
+            // Only C0 controls must be escaped
+            ("\u{7f}caf\u{e9}\u{fffd}", "\"\u{7f}caf\u{e9}\u{fffd}\""),
+        ] {
+            assert_eq!(quote(text), expected);
+        }
+    }
+
+    #[test]
+    fn keys_are_escaped_and_kept_in_order() {
+        let object = Value::Object(vec![
+            ("z", Value::Number(1)),
+            ("a\"b", Value::Null),
+            ("list", Value::Array(vec![])),
+        ]);
+        assert_eq!(
+            object.render(),
+            "{\n  \"z\": 1,\n  \"a\\\"b\": null,\n  \"list\": []\n}"
+        );
+    }
+}
@@ -0,0 +1,16 @@
+use std::{fs, path::Path};
+
+// A symlink whose target can't be reached, either because it doesn't exist
+// or because resolving it loops. Relative targets resolve against the
+// directory holding the link, as the kernel does.
+pub fn is_broken(path: &Path) -> bool {
+    path.is_symlink() && fs::metadata(path).is_err()
+}
+
+// The target exactly as stored in the link, or `?` when it can't be read
+pub fn target_display(path: &Path) -> String {
+    match fs::read_link(path) {
+        Ok(target) => target.display().to_string(),
+        Err(_) => String::from("?"),
+    }
+}
@@ -1,14 +1,47 @@
+mod args;
+mod collisions;
+mod describe;
+mod device;
+mod dot;
+#[cfg(feature = "hash")]
+mod duplicates;
+mod fields;
 mod format;
+mod fs_info;
+#[cfg(feature = "git")]
+mod git;
+mod glob;
+#[cfg(feature = "icons")]
+mod icons;
+mod json;
+mod links;
+mod mode;
+mod normalize;
+mod records;
+mod regex;
+mod size;
+mod summary;
+mod tags;
+mod terminal;
 mod time;
+mod total_size;
+mod tree;
 mod user;
+mod version_sort;
+mod xattr;
 
 use std::{
     borrow::Borrow,
-    cmp::Ordering,
+    cell::{Cell, RefCell},
+    cmp::{Ordering, Reverse},
+    collections::{HashMap, HashSet},
+    env,
+    ffi::OsString,
     fmt,
     fs::{self, Metadata, ReadDir},
-    io::IsTerminal,
-    os::unix::prelude::PermissionsExt,
+    io::{IsTerminal, Write},
+    mem,
+    ops::AddAssign,
     path::{Path, PathBuf},
     process::exit,
     time::SystemTime,
@@ -20,14 +53,22 @@ use std::os::linux::fs::MetadataExt;
 #[cfg(target_os = "macos")]
 use std::os::macos::fs::MetadataExt;
 
-#[cfg(target_os = "unix")]
+#[cfg(all(unix, not(any(target_os = "linux", target_os = "macos"))))]
 use std::os::unix::fs::MetadataExt;
 
-use rawrgs::{App, Arg};
+use std::os::unix::ffi::OsStrExt;
 
-use crate::format::{table, TableAlignment};
+use crate::args::{App, Arg};
+use crate::describe::DescribedKind;
+use crate::fields::Field;
+use crate::format::{table, GridFill, Palette, QuotingStyle, StyleClass, TableAlignment};
+use crate::mode::{EntryKind, Mode, PermSpec};
+use crate::normalize::Normalization;
+use crate::size::{BlockCount, ByteSize};
+use crate::time::TimeStyle;
 
 // Defaults
+const APP_NAME: &str = "rs";
 const DEFAULT_PATH: &str = ".";
 
 // Argument names
@@ -40,150 +81,1003 @@ const NUMERIC_UID_GID_ARG_NAME: &str = "numeric-uid-gid";
 const HUMAN_READABLE_ARG_NAME: &str = "human-readable";
 const GROUP_DIRECTORIES_FIRST_ARG_NAME: &str = "group-directories-first";
 const IGNORE_BACKUPS_ARG_NAME: &str = "ignore-backups";
+const IGNORE_ARG_NAME: &str = "ignore";
+const HIDE_ARG_NAME: &str = "hide";
+const ONLY_DIRS_ARG_NAME: &str = "only-dirs";
+const ONLY_FILES_ARG_NAME: &str = "only-files";
 const TIME_SORT_ARG_NAME: &str = "sort-time";
 const SIZE_SORT_ARG_NAME: &str = "sort-size";
 const EXT_SORT_ARG_NAME: &str = "sort-extension";
+const VERSION_SORT_ARG_NAME: &str = "sort-version";
+const UNSORTED_ARG_NAME: &str = "unsorted";
+const DIRECTORY_ORDER_ARG_NAME: &str = "directory-order";
+const SORT_ARG_NAME: &str = "sort";
+const COMPAT_ARG_NAME: &str = "compat";
+const DESCRIBE_ARG_NAME: &str = "describe";
+const EMOJI_ARG_NAME: &str = "emoji";
+const HEADER_ARG_NAME: &str = "header";
+const NO_PERMISSIONS_ARG_NAME: &str = "no-permissions";
+const NO_USER_ARG_NAME: &str = "no-user";
+const NO_TIME_ARG_NAME: &str = "no-time";
+const NO_FILESIZE_ARG_NAME: &str = "no-filesize";
+const ICONS_ARG_NAME: &str = "icons";
+const FS_INFO_ARG_NAME: &str = "fs-info";
+const DEVICE_ARG_NAME: &str = "device";
+const SIZE_PRECISION_ARG_NAME: &str = "size-precision";
+const GLOB_ARG_NAME: &str = "glob";
+const GLOB_EMPTY_OK_ARG_NAME: &str = "glob-empty-ok";
+const DUPLICATES_ARG_NAME: &str = "duplicates";
+const SHOW_ALL_ARG_NAME: &str = "show-all";
+const BROKEN_LINKS_ARG_NAME: &str = "broken-links";
+const LARGEST_ARG_NAME: &str = "largest";
+const RECENT_ARG_NAME: &str = "recent";
+const DOT_ARG_NAME: &str = "dot";
+const PERM_ARG_NAME: &str = "perm";
+const SAMEFILE_ARG_NAME: &str = "samefile";
+const NO_DEREFERENCE_REF_ARG_NAME: &str = "no-dereference-ref";
+const STAT_ARG_NAME: &str = "stat";
+const GRAND_TOTAL_ARG_NAME: &str = "grand-total";
+const APPARENT_SIZE_ARG_NAME: &str = "apparent-size";
+const CHECK_CASE_COLLISIONS_ARG_NAME: &str = "check-case-collisions";
+const NORMALIZE_ARG_NAME: &str = "normalize";
+const FIELDS_ARG_NAME: &str = "fields";
+const TREE_ARG_NAME: &str = "tree";
+const LEVEL_ARG_NAME: &str = "level";
+const REGEX_ARG_NAME: &str = "regex";
+const PRUNE_ARG_NAME: &str = "prune";
+const FLAT_ARG_NAME: &str = "flat";
+const SUMMARY_BY_ARG_NAME: &str = "summary-by";
+const COLOR_ARG_NAME: &str = "color";
+const HYPERLINK_ARG_NAME: &str = "hyperlink";
+const COLOR_OVERRIDE_ARG_NAME: &str = "color-override";
+const NO_RESPONSIVE_ARG_NAME: &str = "no-responsive";
+const DIR_SIZE_ARG_NAME: &str = "dir-size";
+const GROUP_BY_ARG_NAME: &str = "group-by";
+const COUNT_ONLY_ARG_NAME: &str = "count-only";
+const NO_DOT_HIDDEN_ARG_NAME: &str = "no-dot-hidden";
+const TAGS_ARG_NAME: &str = "tags";
+const GIT_TIME_ARG_NAME: &str = "git-time";
+const GIT_AUTHOR_ARG_NAME: &str = "git-author";
+const GIT_ARG_NAME: &str = "git";
+const GIT_IGNORE_ARG_NAME: &str = "git-ignore";
+const JSON_TREE_ARG_NAME: &str = "json-tree";
+const HIGHLIGHT_ARG_NAME: &str = "highlight";
+const HIGHLIGHT_CASE_ARG_NAME: &str = "highlight-case";
+const TOTAL_SIZE_ARG_NAME: &str = "total-size";
+const CACHE_ARG_NAME: &str = "cache";
+const NO_CACHE_ARG_NAME: &str = "no-cache";
+const CLEAR_CACHE_ARG_NAME: &str = "clear-cache";
+const CACHE_TRUST_ARG_NAME: &str = "cache-trust";
 const REVERSE_ARG_NAME: &str = "reverse";
+const RECURSIVE_ARG_NAME: &str = "recursive";
+const DIRECTORY_ARG_NAME: &str = "directory";
+const DEREFERENCE_ARG_NAME: &str = "dereference";
+const DEREFERENCE_COMMAND_LINE_ARG_NAME: &str = "dereference-command-line";
+const CLASSIFY_ARG_NAME: &str = "classify";
+const INDICATOR_SLASH_ARG_NAME: &str = "indicator-slash";
+const TIME_STYLE_ARG_NAME: &str = "time-style";
+const CONTEXT_ARG_NAME: &str = "context";
+const QUOTE_NAME_ARG_NAME: &str = "quote-name";
+const ESCAPE_ARG_NAME: &str = "escape";
+const HIDE_CONTROL_CHARS_ARG_NAME: &str = "hide-control-chars";
+const LITERAL_ARG_NAME: &str = "literal";
+const QUOTING_STYLE_ARG_NAME: &str = "quoting-style";
 const SIZE_ARG_NAME: &str = "size";
 const ACCESS_TIME_ARG_NAME: &str = "access-time";
 const INODE_ARG_NAME: &str = "inode";
 const KIBIBYTES_ARG_NAME: &str = "kibibytes";
 const COMMA_SEPARATED_ARG_NAME: &str = "comma-separated";
+const COLUMNS_ARG_NAME: &str = "columns";
+const ACROSS_ARG_NAME: &str = "across";
+const WIDTH_ARG_NAME: &str = "width";
+const ZERO_ARG_NAME: &str = "zero";
+const JSON_ARG_NAME: &str = "json";
+const CSV_ARG_NAME: &str = "csv";
+const TSV_ARG_NAME: &str = "tsv";
+
+// Flags that need an optional Cargo feature, the feature, and whether it was
+// compiled in. They are still accepted without it, to fail with a clear error.
+const FEATURE_FLAGS: [(&str, &str, bool); 8] = [
+    (EMOJI_ARG_NAME, "icons", cfg!(feature = "icons")),
+    (ICONS_ARG_NAME, "icons", cfg!(feature = "icons")),
+    (DUPLICATES_ARG_NAME, "hash", cfg!(feature = "hash")),
+    (GIT_TIME_ARG_NAME, "git", cfg!(feature = "git")),
+    (GIT_AUTHOR_ARG_NAME, "git", cfg!(feature = "git")),
+    (GIT_ARG_NAME, "git", cfg!(feature = "git")),
+    (GIT_IGNORE_ARG_NAME, "git", cfg!(feature = "git")),
+    (CONTEXT_ARG_NAME, "selinux", cfg!(feature = "selinux")),
+];
+
+// Exit codes
+const NO_MATCHES_EXIT_CODE: i32 = 2;
+const MISSING_REFERENCE_EXIT_CODE: i32 = 2;
+
+// Environment variables
+const COLORFGBG_ENV_NAME: &str = "COLORFGBG";
+const TIME_STYLE_ENV_NAME: &str = "TIME_STYLE";
+const QUOTING_STYLE_ENV_NAME: &str = "QUOTING_STYLE";
+const COLUMNS_ENV_NAME: &str = "COLUMNS";
+const LS_COLORS_ENV_NAME: &str = "LS_COLORS";
+const TERM_ENV_NAME: &str = "TERM";
+
+// The host named in --hyperlink URIs
+const HOSTNAME_PATH: &str = "/proc/sys/kernel/hostname";
+
+// Width -C lays out for without a terminal or $COLUMNS
+const DEFAULT_GRID_WIDTH: usize = 80;
 
 // Separators
 const ENTRY_SPACE: &str = "  ";
 
+// Size cell of a directory with --dir-size=dash
+const DIR_SIZE_DASH: &str = "-";
+#[cfg(feature = "git")]
+const GIT_UNTRACKED: &str = "-";
+// After a directory whose size counts entries the tree doesn't show
+const TREE_TRUNCATED_MARKER: &str = " …";
+
+// --summary-by=extension keys for entries without one
+const DIRECTORY_SUMMARY_KEY: &str = "<dir>";
+const NO_EXTENSION_SUMMARY_KEY: &str = "<none>";
+// Owner or group of entries whose metadata couldn't be read
+const UNKNOWN_SUMMARY_KEY: &str = "?";
+
 // Directory indicators
 const CURRENT_DIR: &str = ".";
+const DOT_HIDDEN_FILE_NAME: &str = ".hidden";
 const PARENT_DIR: &str = "..";
 
-// Time
-const SECS_PER_DAY: u64 = 86400;
-
 // Size
-// const MB_BYTES: u64 = 1024;
+// GNU counts 1024-byte blocks rather than st_blocks' 512-byte units
+const GNU_BLOCK_SIZE_IN_BYTES: u64 = 1024;
+const DEFAULT_SIZE_PRECISION: usize = 1;
+const MAX_SIZE_PRECISION: usize = 3;
+
+enum OutputFormat {
+    Long,
+    OneLine,
+    CommaSeparated,
+    Describe,
+    // The grid, even when stdout isn't a terminal
+    Columns,
+    // The grid filled across rows rather than down columns
+    Across,
+    // Names as they are on disk, each ended by a NUL
+    Zero,
+    // One JSON array of every entry listed, printed once all are
+    Json,
+    // A record per entry under one header row, with --csv and --tsv
+    Delimited(records::Delimiter),
+    Default,
+}
+
+enum Compat {
+    None,
+    Gnu,
+}
+
+enum GroupBy {
+    Type,
+    Extension,
+    Date,
+}
+
+// What the size column shows for directories
+enum DirSize {
+    Bytes,
+    Dash,
+    Entries,
+}
+
+enum SummaryBy {
+    Extension,
+    Owner,
+    Group,
+}
 
 enum RSSort {
     Time,
     AccessTime,
     Size,
-    Directory,
+    Blocks,
     Extension,
+    // Numbers in names by value
+    Version,
+    #[cfg(feature = "git")]
+    GitTime,
+    None,
     Default,
 }
 
 struct RSEntries {
     entries: Vec<RSEntry>,
-    block_size: u64,
+    block_size: BlockCount,
+    // False for operands listed together, which have no total line
+    is_directory_listing: bool,
+}
+
+// What was listed, added up over every operand for --grand-total
+#[derive(Default)]
+struct ListingTotals {
+    entries: usize,
+    blocks: BlockCount,
+    bytes: ByteSize,
+    // Groups found by --check-case-collisions
+    case_collisions: usize,
+    // Paths that couldn't be read, each reported when it was reached
+    errors: usize,
+}
+
+impl AddAssign for ListingTotals {
+    fn add_assign(&mut self, other: ListingTotals) {
+        self.entries += other.entries;
+        self.blocks += other.blocks;
+        self.bytes += other.bytes;
+        self.case_collisions += other.case_collisions;
+        self.errors += other.errors;
+    }
+}
+
+// Shows allocated blocks as a size with -H, or as a block count
+fn blocks_display(blocks: BlockCount, options: &Options) -> String {
+    if options.is_human_readable {
+        return blocks.to_bytes().human_readable(options.size_precision);
+    }
+
+    match options.compat {
+        Compat::Gnu => blocks
+            .to_bytes()
+            .to_display_blocks(GNU_BLOCK_SIZE_IN_BYTES)
+            .to_string(),
+        Compat::None if options.is_kibibytes => blocks
+            .to_bytes()
+            .to_display_blocks(GNU_BLOCK_SIZE_IN_BYTES)
+            .to_string(),
+        Compat::None => blocks.to_string(),
+    }
+}
+
+// A timestamp in the active time style: one cell with --compat=gnu or an
+// ISO style, a month and a day otherwise
+fn time_cells(secs: i64, nanos: u32, options: &Options) -> Vec<String> {
+    let now = || {
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |now| now.as_secs() as i64)
+    };
+    let iso = match options.time_style {
+        TimeStyle::FullIso => Some(time::full_timestamp(secs, nanos)),
+        TimeStyle::LongIso => Some(time::long_iso_timestamp(secs)),
+        TimeStyle::Iso => Some(time::iso_timestamp(secs, now())),
+        TimeStyle::Default => None,
+    };
+    if let Some(timestamp) = iso {
+        return vec![options.paint(&[StyleClass::Date], &timestamp)];
+    }
+    match options.compat {
+        Compat::Gnu => vec![time::gnu_timestamp(secs, now())],
+        Compat::None => {
+            let date = time::SimpleDate::from_secs(secs);
+            let month = date.month_display(time::DateFormat::ShortMonth);
+            vec![
+                options.paint(&[StyleClass::Date], &month),
+                options.paint(&[StyleClass::Date], &date.day()),
+            ]
+        }
+    }
+}
+
+// The owner's name, or the uid with -n or when it has no name
+fn get_owner_display(uid: u32, options: &Options) -> String {
+    match options.is_numeric_uid_gid {
+        true => uid.to_string(),
+        false => match (user::get_by_uid(uid), &options.compat) {
+            (Ok(user_name), _) if !user_name.is_empty() => user_name,
+            (_, Compat::Gnu) => uid.to_string(),
+            (Ok(user_name), Compat::None) => user_name,
+            (Err(_), Compat::None) => "?".to_string(),
+        },
+    }
+}
+
+// The group's name, or the gid with -n or when it has no name
+fn get_group_display(gid: u32, options: &Options) -> String {
+    match options.is_numeric_uid_gid {
+        true => gid.to_string(),
+        false => match (user::group_by_gid(gid), &options.compat) {
+            (Ok(group_name), _) if !group_name.is_empty() => group_name,
+            (_, Compat::Gnu) => gid.to_string(),
+            (Ok(group_name), Compat::None) => group_name,
+            (Err(_), Compat::None) => "?".to_string(),
+        },
+    }
+}
+
+// Compares entries by a key, ties broken by name. Entries without a key
+// sort after all the others (before -r is applied), ordered by name among
+// themselves.
+fn cmp_by_key<K: Ord>(a: &RSEntry, b: &RSEntry, key: impl Fn(&RSEntry) -> Option<K>) -> Ordering {
+    match (key(a), key(b)) {
+        (Some(key_a), Some(key_b)) => key_a.cmp(&key_b).then_with(|| a.cmp(b)),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => a.cmp(b),
+    }
+}
+
+// Compares entries by a key read from their metadata, which entries whose
+// metadata couldn't be read don't have
+fn cmp_by_metadata<K: Ord>(a: &RSEntry, b: &RSEntry, key: impl Fn(&Metadata) -> K) -> Ordering {
+    cmp_by_key(a, b, |entry| entry.metadata.as_ref().map(&key))
+}
+
+// Orders two entries by the sort key
+fn cmp_entries(a: &RSEntry, b: &RSEntry, kind: &RSSort, options: &Options) -> Ordering {
+    // Directories only have a size to sort by when it is shown in bytes
+    let is_dir_size_zero = !matches!(options.dir_size, DirSize::Bytes);
+    match kind {
+        RSSort::Time => cmp_by_metadata(a, b, |meta| Reverse(meta.st_mtime())),
+        RSSort::AccessTime => cmp_by_metadata(a, b, |meta| Reverse(meta.st_atime())),
+        RSSort::Size => cmp_by_key(a, b, |entry| {
+            let meta = entry.metadata.as_ref()?;
+            Some(
+                match (entry.total_size, meta.is_dir() && is_dir_size_zero) {
+                    (Some(total_size), _) => Reverse(total_size.0),
+                    (None, true) => Reverse(0),
+                    (None, false) => Reverse(meta.len()),
+                },
+            )
+        }),
+        RSSort::Blocks => cmp_by_metadata(a, b, |meta| Reverse(meta.st_blocks())),
+        RSSort::Extension => match (a.path.extension(), b.path.extension()) {
+            (Some(ext_a), Some(ext_b)) => ext_a.cmp(ext_b).then_with(|| a.cmp(b)),
+            (Some(_), None) => Ordering::Greater,
+            (None, Some(_)) => Ordering::Less,
+            (None, None) => a.cmp(b),
+        },
+        RSSort::Version => version_sort::compare(&a.name, &b.name),
+        // Untracked entries go last, like those without metadata
+        #[cfg(feature = "git")]
+        RSSort::GitTime => cmp_by_key(a, b, |entry| {
+            entry
+                .last_commit
+                .as_ref()
+                .map(|commit| Reverse(commit.time))
+        }),
+        RSSort::None | RSSort::Default => a.cmp(b),
+    }
 }
 
 impl RSEntries {
-    fn sort_by(&mut self, kind: RSSort) {
-        self.entries.sort_by(|a, b| {
-            if let (Some(meta_a), Some(meta_b)) = (&a.metadata, &b.metadata) {
-                return match kind {
-                    RSSort::Directory => meta_b.is_dir().cmp(&meta_a.is_dir()),
-                    RSSort::Time => meta_b.st_mtime().cmp(&meta_a.st_mtime()),
-                    RSSort::AccessTime => meta_b.st_atime().cmp(&meta_a.st_atime()),
-                    RSSort::Size => meta_b.len().cmp(&meta_a.len()),
-                    RSSort::Extension => {
-                        return match (a.path.extension(), b.path.extension()) {
-                            (Some(ext_a), Some(ext_b)) => ext_a.cmp(ext_b),
-                            (Some(_), None) => Ordering::Greater,
-                            (None, Some(_)) => Ordering::Less,
-                            (None, None) => a.cmp(b),
-                        };
-                    }
-                    RSSort::Default => a.cmp(b),
-                };
+    fn sort_by(&mut self, kind: &RSSort, options: &Options) {
+        self.entries
+            .sort_by(|a, b| cmp_entries(a, b, kind, options))
+    }
+
+    // Keeps the first n entries by the sort key, in order. Only those are
+    // sorted, the rest are just partitioned off.
+    fn select_first(&mut self, n: usize, kind: &RSSort, options: &Options) {
+        if n < self.entries.len() {
+            self.entries
+                .select_nth_unstable_by(n, |a, b| cmp_entries(a, b, kind, options));
+            self.truncate(n);
+        }
+        self.sort_by(kind, options);
+    }
+
+    // Sorts, cuts and groups the entries as the options ask
+    fn order(&mut self, options: &Options) {
+        // --recent picks from the newest down by the time shown, whatever the
+        // sort flags. --largest picks from the biggest down, by apparent size
+        // unless blocks were asked for.
+        let sort_key = match (options.recent, options.largest, &options.sort_key) {
+            (Some(_), _, _) if options.is_access_time => &RSSort::AccessTime,
+            (Some(_), _, _) => &RSSort::Time,
+            (None, Some(_), RSSort::Blocks) | (None, None, _) => &options.sort_key,
+            (None, Some(_), _) => &RSSort::Size,
+        };
+
+        // Without a sort key entries stay in directory order, so there is nothing to reverse
+        if !matches!(sort_key, RSSort::None) {
+            match options.recent.or(options.largest) {
+                Some(n) => self.select_first(n, sort_key, options),
+                None => self.sort_by(sort_key, options),
             }
-            a.cmp(b)
-        })
+            if options.is_sort_reverse {
+                self.reverse();
+            }
+        }
+
+        if options.is_group_directories_first {
+            self.group_directories_first();
+        }
+    }
+
+    // Stable, so each group keeps the order of the active sort
+    fn group_directories_first(&mut self) {
+        self.entries
+            .sort_by_key(|entry| Reverse(entry.kind == EntryKind::Dir))
     }
 
     fn reverse(&mut self) {
         self.entries.reverse();
     }
 
+    // Sums up what is below each directory, for --total-size
+    fn read_total_sizes(&mut self, options: &Options) {
+        for entry in self.entries.iter_mut() {
+            let Some(meta) = &entry.metadata else {
+                continue;
+            };
+            if entry.kind == EntryKind::Dir && !entry.path.is_symlink() {
+                entry.total_size = Some(total_size::total_size(
+                    &entry.path,
+                    meta,
+                    options.cache_trust,
+                ));
+            }
+        }
+    }
+
+    // Looks up the last commit to touch each entry, from the log of the
+    // repository the entries are in
+    #[cfg(feature = "git")]
+    fn read_last_commits(&mut self) {
+        for entry in self.entries.iter_mut() {
+            entry.last_commit = entry
+                .path
+                .parent()
+                .and_then(git::repo_log)
+                .and_then(|log| log.last_commit(&entry.path).cloned());
+        }
+    }
+
+    // Looks up each entry's status in the work tree it is in, returning
+    // whether any of them is in one
+    #[cfg(feature = "git")]
+    fn read_git_statuses(&mut self) -> bool {
+        for entry in self.entries.iter_mut() {
+            entry.git_status = entry
+                .path
+                .parent()
+                .and_then(git::repo_status)
+                .and_then(|status| status.status(&entry.path));
+        }
+        self.entries.iter().any(|entry| entry.git_status.is_some())
+    }
+
+    // Gathers files with identical content next to each other, where the first
+    // one sits in the active sort, numbering each group. Entries that aren't
+    // duplicates are dropped unless they are kept with --show-all.
+    #[cfg(feature = "hash")]
+    fn group_duplicates(&mut self, is_show_unique: bool) {
+        let candidates: Vec<(usize, &Path, &Metadata)> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.kind == EntryKind::File && !entry.path.is_symlink())
+            .filter_map(|(index, entry)| {
+                let meta = entry.metadata.as_ref()?;
+                Some((index, entry.path.as_path(), meta))
+            })
+            .collect();
+        let files: Vec<(&Path, &Metadata)> = candidates
+            .iter()
+            .map(|(_, path, meta)| (*path, *meta))
+            .collect();
+        let groups: Vec<Vec<usize>> = duplicates::find_groups(&files)
+            .into_iter()
+            .map(|group| group.iter().map(|index| candidates[*index].0).collect())
+            .collect();
+
+        let mut group_of = vec![None; self.entries.len()];
+        for (group_index, group) in groups.iter().enumerate() {
+            for index in group {
+                group_of[*index] = Some(group_index);
+            }
+        }
+
+        let mut entries: Vec<Option<RSEntry>> = self.entries.drain(..).map(Some).collect();
+        let mut is_placed = vec![false; groups.len()];
+        for index in 0..entries.len() {
+            match group_of[index] {
+                Some(group_index) if !is_placed[group_index] => {
+                    is_placed[group_index] = true;
+                    let number = is_placed.iter().filter(|placed| **placed).count();
+                    for member in &groups[group_index] {
+                        if let Some(mut entry) = entries[*member].take() {
+                            entry.duplicate_group = Some(number);
+                            self.entries.push(entry);
+                        }
+                    }
+                }
+                Some(_) => continue,
+                None if is_show_unique => self.entries.extend(entries[index].take()),
+                None => continue,
+            }
+        }
+
+        self.count_blocks();
+    }
+
+    // Takes out the entries --regex, --perm and the like leave out, returning them
+    fn take_filtered_out(&mut self, options: &Options) -> RSEntries {
+        let (shown, filtered_out) = self
+            .entries
+            .drain(..)
+            .partition(|entry| is_filtered_in(&entry.name, &entry.path, options));
+        self.entries = shown;
+        self.count_blocks();
+        RSEntries {
+            entries: filtered_out,
+            block_size: BlockCount(0),
+            is_directory_listing: self.is_directory_listing,
+        }
+    }
+
+    // Keeps the first n entries, so only they add up in the total
+    fn truncate(&mut self, n: usize) {
+        self.entries.truncate(n);
+        self.count_blocks();
+    }
+
+    // The directories -R goes on to list, in listing order. Symlinks to
+    // directories aren't followed, so a link cycle can't recurse forever.
+    fn subdirectories(&self) -> Vec<PathBuf> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.kind == EntryKind::Dir && !entry.path.is_symlink())
+            .filter(|entry| entry.name != CURRENT_DIR && entry.name != PARENT_DIR)
+            .map(|entry| entry.path.clone())
+            .collect()
+    }
+
+    // Marks the entries whose names only differ in case or accent
+    // composition, warning about each group. Returns how many groups there are.
+    fn mark_case_collisions(&mut self) -> usize {
+        let groups = collisions::find_groups(self.entries.iter().map(|entry| entry.name.as_str()));
+        for group in &groups {
+            let paths: Vec<String> = group
+                .iter()
+                .map(|index| self.entries[*index].path.display().to_string())
+                .collect();
+            eprintln!("rs: names differ only in case: {}", paths.join(", "));
+            for index in group {
+                self.entries[*index].is_case_collision = true;
+            }
+        }
+        groups.len()
+    }
+
+    // The entries as they'll be listed, their blocks as in the total line
+    fn totals(&self) -> ListingTotals {
+        let mut totals = ListingTotals {
+            entries: self.entries.len(),
+            blocks: self.block_size,
+            bytes: ByteSize(0),
+            case_collisions: 0,
+            errors: 0,
+        };
+        for meta in self
+            .entries
+            .iter()
+            .filter_map(|entry| entry.metadata.as_ref())
+        {
+            totals.bytes += ByteSize(meta.len());
+        }
+        totals
+    }
+
+    // Describes symlinks by their targets, keeping the link for one that is
+    // dangling or loops
+    fn dereference(&mut self) {
+        for entry in self.entries.iter_mut() {
+            if entry.kind != EntryKind::Symlink {
+                continue;
+            }
+            if let Ok(meta) = fs::metadata(&entry.path) {
+                entry.kind = Mode(meta.st_mode()).kind();
+                entry.metadata = Some(meta);
+            }
+        }
+        self.count_blocks();
+    }
+
+    fn count_blocks(&mut self) {
+        self.block_size = BlockCount(
+            self.entries
+                .iter()
+                .filter_map(|entry| entry.metadata.as_ref())
+                .map(|meta| meta.st_blocks())
+                .sum(),
+        );
+    }
+
+    // Entries that couldn't be stat'ed have no row outside GNU output, they
+    // were reported already
     fn to_tabular(&self, options: &Options) -> Vec<Vec<String>> {
         let mut output: Vec<Vec<String>> = vec![];
         for entry in &self.entries {
-            let row = entry.get_table_row(options);
-            output.push(row);
+            let row = RSEntry::pick_long_cells(entry.get_table_row(options), options);
+            if !row.is_empty() {
+                output.push(row);
+            }
         }
         output
     }
 }
 
+// What --hyperlink needs to turn an entry's path into a file:// URI, read
+// once for the run
+struct HyperlinkBase {
+    host: String,
+    current_dir: PathBuf,
+    // The last directory canonicalized and what it came to. A directory's
+    // entries are shown together, so each is only canonicalized once.
+    last_dir: RefCell<Option<(PathBuf, PathBuf)>>,
+}
+
+impl HyperlinkBase {
+    fn new() -> HyperlinkBase {
+        HyperlinkBase {
+            host: fs::read_to_string(HOSTNAME_PATH)
+                .map(|host| host.trim().to_string())
+                .unwrap_or_default(),
+            current_dir: env::current_dir().unwrap_or_default(),
+            last_dir: RefCell::new(None),
+        }
+    }
+
+    // The directory made absolute with its links and dots resolved, or just
+    // absolute when it can't be
+    fn canonical_dir(&self, dir: &Path) -> PathBuf {
+        let mut last_dir = self.last_dir.borrow_mut();
+        if let Some((given, canonical)) = &*last_dir {
+            if given == dir {
+                return canonical.clone();
+            }
+        }
+        let canonical = fs::canonicalize(dir).unwrap_or_else(|_| self.current_dir.join(dir));
+        *last_dir = Some((dir.to_path_buf(), canonical.clone()));
+        canonical
+    }
+
+    // The entry itself isn't resolved, so a link's URI is the link's
+    fn uri(&self, path: &Path) -> String {
+        let absolute = match (path.parent(), path.file_name()) {
+            (Some(parent), Some(name)) if parent.as_os_str().is_empty() => {
+                self.canonical_dir(Path::new(".")).join(name)
+            }
+            (Some(parent), Some(name)) => self.canonical_dir(parent).join(name),
+            _ => self.canonical_dir(path),
+        };
+        format::file_uri(&self.host, absolute.as_os_str().as_bytes())
+    }
+}
+
 struct Options {
     is_show_all: bool,
     is_show_almost_all: bool,
-    is_one_line: bool,
-    is_long_output: bool,
+    output_format: OutputFormat,
     is_numeric_uid_gid: bool,
     is_human_readable: bool,
+    size_precision: usize,
     is_group_directories_first: bool,
     is_ignore_backups: bool,
-    is_sort_by_time: bool,
-    is_sort_by_size: bool,
-    is_sort_by_extension: bool,
+    // Globs from every -I given, names matching any are never listed
+    ignore_patterns: Vec<String>,
+    // Globs from every --hide given, which -a and -A override
+    hide_patterns: Vec<String>,
+    // Entries kept by type, with --only-dirs or --only-files
+    is_only_dirs: bool,
+    is_only_files: bool,
+    sort_key: RSSort,
     is_sort_reverse: bool,
     is_show_size_blocks: bool,
     is_access_time: bool,
     is_show_inode: bool,
     is_kibibytes: bool,
-    is_comma_separated: bool,
+    compat: Compat,
+    #[cfg_attr(not(feature = "icons"), allow(dead_code))]
+    is_emoji: bool,
+    // Nerd Font glyphs instead of emoji, with --icons
+    #[cfg_attr(not(feature = "icons"), allow(dead_code))]
+    is_nerd_font_icons: bool,
+    is_fs_info: bool,
+    is_show_device: bool,
+    is_duplicates: bool,
+    #[cfg_attr(not(feature = "hash"), allow(dead_code))]
+    is_show_unique: bool,
+    is_broken_links: bool,
+    largest: Option<usize>,
+    recent: Option<usize>,
+    is_tree: bool,
+    // How many levels a tree goes down, every level without --level or with 0
+    tree_level: Option<usize>,
+    regex: Option<regex::Regex>,
+    perm: Option<PermSpec>,
+    // (dev, ino) of the --samefile reference
+    samefile: Option<(u64, u64)>,
+    is_prune: bool,
+    is_flat: bool,
+    is_recursive: bool,
+    is_directory: bool,
+    is_dereference: bool,
+    is_dereference_command_line: bool,
+    is_classify: bool,
+    is_indicator_slash: bool,
+    is_context: bool,
+    quoting_style: QuotingStyle,
+    // Set by -q and on a terminal, so a name's stray bytes show as ? too
+    is_hide_control_chars: bool,
+    // Set by -N, so names are written as stored, control characters and all
+    is_literal: bool,
+    time_style: TimeStyle,
+    summary_by: Option<SummaryBy>,
+    dir_size: DirSize,
+    group_by: Option<GroupBy>,
+    is_count_only: bool,
+    is_no_dot_hidden: bool,
+    is_show_tags: bool,
+    // A row naming the columns above -l output, with --header
+    is_header: bool,
+    // Columns left out of long output
+    is_hide_permissions: bool,
+    is_hide_user: bool,
+    is_hide_time: bool,
+    is_hide_filesize: bool,
+    is_git_time: bool,
+    is_git_author: bool,
+    // --git, a status column in long listings inside a work tree
+    is_git_status: bool,
+    // Whether the listing being printed is in a work tree, so the column is
+    // shown. Set for each listing as it is read.
+    is_git_status_shown: Cell<bool>,
+    // Leaves out what git ignores, with --git-ignore
+    #[cfg_attr(not(feature = "git"), allow(dead_code))]
+    is_git_ignore: bool,
+    is_json_tree: bool,
+    fields: Vec<Field>,
+    // --fields given with -l, picking its columns and their order
+    long_fields: Option<Vec<Field>>,
+    is_dot: bool,
+    is_stat: bool,
+    is_grand_total: bool,
+    is_apparent_size: bool,
+    is_check_case_collisions: bool,
+    is_total_size: bool,
+    // How far cached directory sizes are trusted, none when not caching
+    cache_trust: Option<total_size::CacheTrust>,
+    normalization: Option<Normalization>,
+    // Text to pick out in names, from every --highlight given
+    highlights: Vec<String>,
+    is_highlight_case: bool,
+    palette: Palette,
+    is_colored: bool,
+    // Set with --hyperlink, names then link to their files
+    hyperlink_base: Option<HyperlinkBase>,
+    // Width long output is fitted to, none when it isn't
+    responsive_width: Option<usize>,
+    // Columns the grid and -m fill, from -w or the terminal
+    grid_width: Option<usize>,
+    // The objects --json has gathered so far, from every listing
+    json_entries: RefCell<Vec<json::Value>>,
+    // The key and size of everything --summary-by has counted so far
+    summary_items: RefCell<Vec<(String, ByteSize)>>,
+}
+
+impl Options {
+    // Whether a symlink given as an operand stands for its target, so a link
+    // to a directory is listed as that directory. As in ls, that's the case
+    // outside long output, and with -L or --dereference-command-line, but -d
+    // lists the links themselves.
+    fn is_following_operand_links(&self) -> bool {
+        match (self.is_dereference, self.is_directory) {
+            (true, _) => true,
+            (false, true) => false,
+            (false, false) => {
+                self.is_dereference_command_line
+                    || !matches!(self.output_format, OutputFormat::Long)
+            }
+        }
+    }
+
+    // --json, --csv, --tsv and --summary-by make one document of every
+    // listing, which 'dir:' headers and --group-by sections would break up
+    fn is_single_document(&self) -> bool {
+        matches!(
+            self.output_format,
+            OutputFormat::Json | OutputFormat::Delimited(_)
+        ) || self.summary_by.is_some()
+    }
+
+    // With -R, --duplicates compares files and --largest and --recent pick
+    // entries across the whole tree, so the tree is listed as one set like
+    // with --flat
+    fn is_listing_flat(&self) -> bool {
+        self.is_flat
+            || (self.is_recursive
+                && (self.is_duplicates || self.largest.is_some() || self.recent.is_some()))
+    }
+
+    // Commits are only looked up for the JSON fields when they are asked for
+    #[cfg(feature = "git")]
+    fn is_reading_last_commits(&self) -> bool {
+        (self.is_json_tree
+            || matches!(
+                self.output_format,
+                OutputFormat::Json | OutputFormat::Delimited(_)
+            ))
+            && self.fields.iter().any(|field| field.is_git())
+    }
+
+    // Styles text with the first of the classes the palette has a style for,
+    // when output is colored at all
+    fn paint(&self, classes: &[StyleClass], str: &str) -> String {
+        match self.is_colored {
+            true => self.palette.paint(classes, str),
+            false => str.to_string(),
+        }
+    }
+
+    // The quotes around a name and the name's text between them, in the
+    // quoting style
+    fn quote_parts(&self, name: &[u8]) -> (&'static str, String) {
+        if self.is_literal {
+            return ("", format::with_raw_bytes(name));
+        }
+        self.quoting_style.quote(name, self.is_hide_control_chars)
+    }
+
+    fn quote_name(&self, name: &[u8]) -> String {
+        let (quote, text) = self.quote_parts(name);
+        format!("{}{}{}", quote, text, quote)
+    }
+}
+
+// The cells of a row of long output
+#[derive(Clone, Copy, PartialEq)]
+enum LongCell {
+    Blocks,
+    Inode,
+    Device,
+    Permissions,
+    Links,
+    Owner,
+    Group,
+    Context,
+    Size,
+    Time,
+    Commit,
+    Author,
+    GitStatus,
+    Tags,
+    Name,
+    // The rest of a time that takes two cells
+    Continued,
+}
+
+impl LongCell {
+    fn header(self) -> &'static str {
+        match self {
+            LongCell::Blocks => "Blocks",
+            LongCell::Inode => "Inode",
+            LongCell::Device => "Device",
+            LongCell::Permissions => "Permissions",
+            LongCell::Links => "Links",
+            LongCell::Owner => "Owner",
+            LongCell::Group => "Group",
+            LongCell::Context => "Context",
+            LongCell::Size => "Size",
+            LongCell::Time => "Date",
+            LongCell::Commit => "Commit",
+            LongCell::Author => "Author",
+            LongCell::GitStatus => "Git",
+            LongCell::Tags => "Tags",
+            LongCell::Name => "Name",
+            LongCell::Continued => "",
+        }
+    }
+
+    // The column of -l a --fields field picks, if it has one
+    fn of_field(field: Field) -> Option<LongCell> {
+        match field {
+            Field::Size => Some(LongCell::Size),
+            Field::Blocks => Some(LongCell::Blocks),
+            Field::Inode => Some(LongCell::Inode),
+            Field::Links => Some(LongCell::Links),
+            Field::User => Some(LongCell::Owner),
+            Field::Group => Some(LongCell::Group),
+            Field::Mode | Field::Permissions => Some(LongCell::Permissions),
+            Field::Mtime => Some(LongCell::Time),
+            Field::GitTime => Some(LongCell::Commit),
+            Field::GitAuthor => Some(LongCell::Author),
+            _ => None,
+        }
+    }
 }
 
 struct RSEntry {
     name: String,
+    // The name as read from the directory, before --normalize, the operand
+    // as given or with --flat the path below the operand
+    raw_name: OsString,
     path: PathBuf,
     metadata: Option<Metadata>,
+    kind: EntryKind,
+    duplicate_group: Option<usize>,
+    is_case_collision: bool,
+    #[cfg(feature = "git")]
+    last_commit: Option<git::Commit>,
+    #[cfg(feature = "git")]
+    git_status: Option<git::Status>,
+    // Everything below a directory, with --total-size
+    total_size: Option<ByteSize>,
 }
 
 impl RSEntry {
-    fn get_permission_string(&self) -> String {
-        let mut permission_string = String::new();
-        if let Some(file_metadata) = &self.metadata {
-            let mut permission_string_prefix = String::new();
-            if file_metadata.is_dir() {
-                permission_string_prefix.push('d');
-            } else if file_metadata.is_file() {
-                permission_string_prefix.push('-');
-            } else if file_metadata.is_symlink() {
-                permission_string_prefix.push('l');
-            } else {
-                permission_string_prefix.push('?');
-            }
+    fn mode(&self) -> Option<Mode> {
+        self.metadata.as_ref().map(|meta| Mode(meta.st_mode()))
+    }
 
-            let mode = file_metadata.permissions().mode();
-            let mode_string = format!("{:o}", mode);
-            let permission_bits = mode_string[mode_string.len() - 3..].to_string();
-            permission_string = permission_string_prefix;
-            for bit in permission_bits.chars() {
-                match bit {
-                    '4' => permission_string.push_str("r--"),
-                    '5' => permission_string.push_str("r-x"),
-                    '6' => permission_string.push_str("rw-"),
-                    '7' => permission_string.push_str("rwx"),
-                    _ => continue,
-                }
-            }
+    // The name's own bytes. One that isn't UTF-8 was read lossily, so its
+    // bytes come from the path.
+    fn name_bytes(&self) -> &[u8] {
+        match self.path.file_name() {
+            Some(file_name) if file_name.to_str().is_none() => file_name.as_bytes(),
+            _ => self.name.as_bytes(),
         }
-        permission_string
     }
 
-    fn get_file_size(&self) -> u64 {
+    fn raw_name(&self) -> &[u8] {
+        self.raw_name.as_encoded_bytes()
+    }
+
+    fn get_file_size(&self) -> ByteSize {
+        if let Some(total_size) = self.total_size {
+            return total_size;
+        }
         if let Some(file_metadata) = &self.metadata {
-            return file_metadata.len();
+            return ByteSize(file_metadata.len());
+        }
+        ByteSize(0)
+    }
+
+    // Comma separated tag names in their Finder colors, "-" without any
+    // and "?" when they can't be decoded
+    fn get_tags_display(&self, options: &Options) -> String {
+        let Some(tags) = tags::read(&self.path) else {
+            return String::from("?");
+        };
+        if tags.is_empty() {
+            return String::from("-");
+        }
+        tags.iter()
+            .map(|tag| {
+                let escape = tag
+                    .color_spec()
+                    .and_then(|spec| format::parse_style_spec(spec).ok());
+                match escape {
+                    Some(escape) if options.is_colored => format::styled(&escape, &tag.name),
+                    _ => tag.name.clone(),
+                }
+            })
+            .collect::<Vec<String>>()
+            .join(",")
+    }
+
+    // How many entries a directory holds, or "?" when it can't be read
+    fn get_entry_count_display(&self) -> String {
+        match fs::read_dir(&self.path) {
+            Ok(read_dir) => read_dir.count().to_string(),
+            Err(_) => String::from("?"),
         }
-        0
     }
 
-    fn get_file_size_human(&self) -> String {
+    fn get_file_size_human(&self, options: &Options) -> String {
         let mut human_readable_string = String::new();
-        if let Some(file_metadata) = &self.metadata {
-            human_readable_string = format::bytes_to_human_readable(file_metadata.len())
+        if self.metadata.is_some() {
+            human_readable_string = self.get_file_size().human_readable(options.size_precision)
         }
         human_readable_string
     }
@@ -193,152 +1087,555 @@ impl RSEntry {
         if let Some(ref file_metadata) = &self.metadata {
             // size blocks
             if options.is_show_size_blocks {
-                let mut blocks = file_metadata.st_blocks();
-                if options.is_kibibytes {
-                    blocks /= 2;
-                }
-                string_builder.push((blocks).to_string())
+                string_builder.push(blocks_display(
+                    BlockCount(file_metadata.st_blocks()),
+                    options,
+                ))
             }
 
             // index node
             if options.is_show_inode {
-                // TODO: handle other platforms
-                if cfg!(target_os = "macos") {
-                    string_builder.push(file_metadata.st_ino().to_string())
-                }
+                string_builder.push(file_metadata.st_ino().to_string())
             }
 
-            if options.is_long_output || options.is_numeric_uid_gid {
-                // permission string
-                let permission_string = self.get_permission_string();
-                string_builder.push(permission_string);
+            if let OutputFormat::Long = options.output_format {
+                // device holding the entry
+                if options.is_show_device {
+                    string_builder.push(device::device_display(file_metadata.st_dev()));
+                }
+
+                // permission string, marked when there's an ACL or xattrs
+                if !options.is_hide_permissions {
+                    string_builder.push(format!(
+                        "{}{}",
+                        Mode(file_metadata.st_mode()).symbolic(),
+                        xattr::mode_mark(&self.path)
+                    ));
+                }
 
                 // number of hardlinks
                 string_builder.push(file_metadata.st_nlink().to_string());
 
-                // owner
-                let uid_string = match options.is_numeric_uid_gid {
-                    true => file_metadata.st_uid().to_string(),
-                    false => {
-                        if let Ok(user_name) = user::get_by_uid(file_metadata.st_uid()) {
-                            user_name
-                        } else {
-                            "?".to_string()
-                        }
-                    }
-                };
-                string_builder.push(uid_string);
-
-                // group
-                let gid_string = match options.is_numeric_uid_gid {
-                    true => file_metadata.st_gid().to_string(),
-                    false => {
-                        if let Ok(group_name) = user::group_by_gid(file_metadata.st_gid()) {
-                            group_name
-                        } else {
-                            "?".to_string()
-                        }
-                    }
-                };
-                string_builder.push(gid_string);
+                if !options.is_hide_user {
+                    // owner
+                    let uid_string = get_owner_display(file_metadata.st_uid(), options);
+                    string_builder.push(options.paint(&[StyleClass::User], &uid_string));
 
-                // file size
-                let file_size_string = match options.is_human_readable {
-                    true => self.get_file_size_human(),
-                    false => self.get_file_size().to_string(),
-                };
-                string_builder.push(file_size_string);
+                    // group
+                    let gid_string = get_group_display(file_metadata.st_gid(), options);
+                    string_builder.push(options.paint(&[StyleClass::Group], &gid_string));
+                }
+
+                // SELinux context
+                #[cfg(feature = "selinux")]
+                if options.is_context {
+                    string_builder.push(
+                        xattr::security_context(&self.path).unwrap_or_else(|| String::from("?")),
+                    );
+                }
 
-                let mut time_to_parse = file_metadata.modified();
-                if options.is_access_time {
-                    time_to_parse = file_metadata.accessed();
+                // file size
+                if !options.is_hide_filesize {
+                    string_builder.push(self.get_size_cell(file_metadata, options));
                 }
 
                 // last modified time
-                if let Ok(system_time) = time_to_parse {
-                    let duration = system_time.duration_since(SystemTime::UNIX_EPOCH).unwrap();
-                    let days = duration.as_secs() / SECS_PER_DAY;
-                    let date = time::SimpleDate::from_days(days);
-                    string_builder.push(date.month_display(time::DateFormat::ShortMonth));
-                    string_builder.push(date.day());
-                } else {
-                    string_builder.push(String::from(" "));
+                if !options.is_hide_time {
+                    // Negative before 1970
+                    let (secs, nanos) = match options.is_access_time {
+                        true => (file_metadata.st_atime(), file_metadata.st_atime_nsec()),
+                        false => (file_metadata.st_mtime(), file_metadata.st_mtime_nsec()),
+                    };
+                    string_builder.extend(time_cells(secs, nanos as u32, options));
+                }
+
+                // last commit to touch the entry
+                #[cfg(feature = "git")]
+                if options.is_git_time {
+                    match &self.last_commit {
+                        Some(commit) => {
+                            string_builder.extend(time_cells(commit.time as i64, 0, options))
+                        }
+                        None => {
+                            string_builder.push(String::from(GIT_UNTRACKED));
+                            // Filling out the cells a time takes
+                            if let (Compat::None, TimeStyle::Default) =
+                                (&options.compat, options.time_style)
+                            {
+                                string_builder.push(String::new());
+                            }
+                        }
+                    }
+                }
+                #[cfg(feature = "git")]
+                if options.is_git_author {
+                    string_builder.push(match &self.last_commit {
+                        Some(commit) => commit.author.clone(),
+                        None => String::from(GIT_UNTRACKED),
+                    });
+                }
+                #[cfg(feature = "git")]
+                if options.is_git_status_shown.get() {
+                    string_builder.push(match self.git_status {
+                        Some(status) => status.display(),
+                        None => String::from(GIT_UNTRACKED),
+                    });
+                }
+
+                // Finder tags
+                if options.is_show_tags {
+                    string_builder.push(self.get_tags_display(options));
                 }
             }
 
-            if file_metadata.is_dir() && std::io::stdout().is_terminal() {
-                string_builder.push(format::blue_bold(&self.name))
-            } else {
-                string_builder.push(self.name.to_string());
+            string_builder.push(self.get_display_name(options));
+        } else if let Compat::Gnu = options.compat {
+            // GNU fills every field it couldn't stat with a question mark
+            if options.is_show_size_blocks {
+                string_builder.push(String::from("?"));
+            }
+            if options.is_show_inode {
+                string_builder.push(String::from("?"));
+            }
+            if let OutputFormat::Long = options.output_format {
+                if options.is_show_device {
+                    string_builder.push(String::from("?"));
+                }
+                if !options.is_hide_permissions {
+                    string_builder.push(String::from("??????????"));
+                }
+                string_builder.push(String::from("?"));
+                if !options.is_hide_user {
+                    string_builder.extend(["?", "?"].map(String::from));
+                }
+                if cfg!(feature = "selinux") && options.is_context {
+                    string_builder.push(String::from("?"));
+                }
+                if !options.is_hide_filesize {
+                    string_builder.push(String::from("?"));
+                }
+                if !options.is_hide_time {
+                    string_builder.push(String::from("?"));
+                }
+                if cfg!(feature = "git") && options.is_git_time {
+                    string_builder.push(String::from("?"));
+                }
+                if cfg!(feature = "git") && options.is_git_author {
+                    string_builder.push(String::from("?"));
+                }
+                if options.is_git_status_shown.get() {
+                    string_builder.push(String::from("?"));
+                }
+                if options.is_show_tags {
+                    string_builder.push(String::from("?"));
+                }
             }
+            string_builder.push(options.quote_name(self.name_bytes()));
         }
         string_builder
     }
-}
 
-impl Ord for RSEntry {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.name.cmp(&other.name)
+    // The size column of long output, painted
+    fn get_size_cell(&self, file_metadata: &Metadata, options: &Options) -> String {
+        let file_size_string = match (self.kind, &options.dir_size) {
+            // device nodes have no size, only the device they stand for
+            (EntryKind::CharDevice | EntryKind::BlockDevice, _) => {
+                device::size_display(file_metadata.st_rdev())
+            }
+            // --total-size shows what is below instead
+            (EntryKind::Dir, DirSize::Dash) if self.total_size.is_none() => {
+                String::from(DIR_SIZE_DASH)
+            }
+            (EntryKind::Dir, DirSize::Entries) if self.total_size.is_none() => {
+                self.get_entry_count_display()
+            }
+            _ if options.is_human_readable => self.get_file_size_human(options),
+            _ => self.get_file_size().to_string(),
+        };
+        options.paint(&[StyleClass::Size], &file_size_string)
     }
-}
 
-impl PartialOrd for RSEntry {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
-}
+    // The name with its indicator prefix, colored by what kind of entry it
+    // is, with the -F or -p suffix outside the color, and with the target of a
+    // link when asked for
+    fn get_display_name(&self, options: &Options) -> String {
+        let prefix = self.get_indicator_prefix(options);
+        // With -Q the escaped name is painted, the quotes around it aren't
+        let (quote, text) = options.quote_parts(self.name_bytes());
+        let painted = match options.is_colored {
+            true if !options.highlights.is_empty() => {
+                let ranges =
+                    format::find_highlights(&text, &options.highlights, options.is_highlight_case);
+                options
+                    .palette
+                    .paint_highlighted(&self.get_style_classes(), &text, &ranges)
+            }
+            true => options.paint(&self.get_style_classes(), &text),
+            false => text,
+        };
+        // The link covers the quotes, not the prefix or any suffix
+        let quoted = format!("{}{}{}", quote, painted, quote);
+        let mut name = match &options.hyperlink_base {
+            Some(base) => format!(
+                "{}{}",
+                prefix,
+                format::hyperlink(&base.uri(&self.path), &quoted)
+            ),
+            None => format!("{}{}", prefix, quoted),
+        };
 
-impl Eq for RSEntry {}
+        // where a link points, in long output as ls shows it, and what a
+        // broken link used to point at. Like ls -lF, the target is classified
+        // instead of the link.
+        let is_long = matches!(options.output_format, OutputFormat::Long);
+        if (is_long || options.is_broken_links) && self.kind == EntryKind::Symlink {
+            let target = links::target_display(&self.path);
+            let (quote, text) = options.quote_parts(target.as_bytes());
+            // A target that isn't there is painted as missing, or like the
+            // broken link when that has no style of its own
+            let text = match options.is_colored && links::is_broken(&self.path) {
+                true => options.paint(&[StyleClass::Missing, StyleClass::Broken], &text),
+                false => text,
+            };
+            name.push_str(&format!(" -> {}{}{}", quote, text, quote));
+            if options.is_classify {
+                let target_mode = fs::metadata(&self.path).map(|meta| Mode(meta.st_mode()));
+                name.push_str(target_mode.map_or("", |mode| mode.indicator()));
+            }
+        } else {
+            name.push_str(self.get_classify_suffix(options));
+        }
+        name
+    }
 
-impl PartialEq for RSEntry {
-    fn eq(&self, other: &Self) -> bool {
-        self.path == other.path
+    // The type indicator -F appends, or the slash -p puts after directories
+    fn get_classify_suffix(&self, options: &Options) -> &'static str {
+        match (options.is_classify, options.is_indicator_slash) {
+            (true, _) => self.mode().map_or("", |mode| mode.indicator()),
+            (false, true) if self.kind == EntryKind::Dir => "/",
+            _ => "",
+        }
     }
-}
 
-impl fmt::Display for RSEntry {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        if let Some(meta) = &self.metadata {
-            if meta.is_dir() && std::io::stdout().is_terminal() {
-                write!(f, "{}", format::blue_bold(&self.name))?;
-            } else {
-                write!(f, "{}", self.name)?;
+    // Every style class the entry belongs to, most specific first
+    fn get_style_classes(&self) -> Vec<StyleClass> {
+        let mut classes = vec![];
+        if self.is_case_collision {
+            classes.push(StyleClass::Collision);
+        }
+        if links::is_broken(&self.path) {
+            classes.push(StyleClass::Broken);
+        }
+        if self.path.is_symlink() {
+            classes.push(StyleClass::Symlink);
+        }
+        let mode = self.mode();
+        let has = |is_set: fn(Mode) -> bool| mode.is_some_and(is_set);
+        match self.kind {
+            // Like ls, a directory anyone can write to stands out, more so
+            // with the sticky bit than without
+            EntryKind::Dir => {
+                let is_other_writable = has(|mode| mode.other().write);
+                if is_other_writable && has(Mode::is_sticky) {
+                    classes.push(StyleClass::StickyOtherWritable);
+                }
+                if is_other_writable {
+                    classes.push(StyleClass::OtherWritable);
+                }
+                if has(Mode::is_sticky) {
+                    classes.push(StyleClass::Sticky);
+                }
+                classes.push(StyleClass::Directory);
             }
-        } else {
-            write!(f, "{}", self.name)?;
+            EntryKind::File => {
+                if has(Mode::is_setuid) {
+                    classes.push(StyleClass::Setuid);
+                }
+                if has(Mode::is_setgid) {
+                    classes.push(StyleClass::Setgid);
+                }
+                match has(Mode::is_executable) {
+                    true => classes.push(StyleClass::Executable),
+                    false => classes.push(StyleClass::File),
+                }
+            }
+            EntryKind::Fifo => classes.push(StyleClass::Fifo),
+            EntryKind::Socket => classes.push(StyleClass::Socket),
+            EntryKind::BlockDevice => classes.push(StyleClass::BlockDevice),
+            EntryKind::CharDevice => classes.push(StyleClass::CharDevice),
+            EntryKind::Symlink | EntryKind::Unknown => {}
         }
+        classes
+    }
 
-        Ok(())
+    // Duplicate group marker and icon shown before the name, empty unless
+    // --duplicates, --emoji or --icons is set
+    fn get_indicator_prefix(&self, options: &Options) -> String {
+        let marker = match self.duplicate_group {
+            Some(group) => format!("[{}] ", group),
+            None => String::new(),
+        };
+        format!("{}{}", marker, self.get_icon_prefix(options))
     }
-}
 
-impl Borrow<str> for RSEntry {
-    fn borrow(&self) -> &str {
-        &self.name
+    #[cfg(feature = "icons")]
+    fn get_icon_prefix(&self, options: &Options) -> String {
+        if !options.is_emoji && !options.is_nerd_font_icons {
+            return String::new();
+        }
+        let class = icons::classify(&self.path, self.kind, self.mode());
+        let icon = match options.is_emoji {
+            true => icons::emoji(&class),
+            false => icons::nerd_font(&self.path, &class),
+        };
+        format!("{} ", icon)
     }
-}
 
-fn get_entries(dir_entries: Vec<String>, base_path: &Path) -> RSEntries {
-    let mut block_size = 0;
+    // --emoji and --icons can't be given without the icons feature
+    #[cfg(not(feature = "icons"))]
+    fn get_icon_prefix(&self, _options: &Options) -> String {
+        String::new()
+    }
+
+    fn get_description(&self, options: &Options) -> describe::Description {
+        let kind = match (fs::read_link(&self.path), self.kind) {
+            (Ok(target), _) => DescribedKind::Symlink(target.display().to_string()),
+            (Err(_), EntryKind::Dir) => DescribedKind::Directory,
+            (Err(_), EntryKind::File) => DescribedKind::File,
+            (Err(_), EntryKind::Unknown) => DescribedKind::Unknown,
+            (Err(_), _) => DescribedKind::Special,
+        };
+
+        // Only regular files get a size, it's noise for everything else
+        let size = match kind {
+            DescribedKind::File => Some(self.get_file_size()),
+            _ => None,
+        };
+
+        let time = self.metadata.as_ref().map(|meta| {
+            let (verb, secs) = match options.is_access_time {
+                true => ("accessed", meta.st_atime()),
+                false => ("modified", meta.st_mtime()),
+            };
+            (verb, time::SimpleDate::from_secs(secs))
+        });
+
+        let owner = self
+            .metadata
+            .as_ref()
+            .map(|meta| match user::get_by_uid(meta.st_uid()) {
+                Ok(user_name) if !user_name.is_empty() && !options.is_numeric_uid_gid => user_name,
+                _ => meta.st_uid().to_string(),
+            });
+
+        describe::Description {
+            name: options.quote_name(self.name_bytes()),
+            kind,
+            size,
+            time,
+            owner,
+        }
+    }
+
+    // The cells get_table_row fills in long output, in the same order
+    fn long_cells(options: &Options) -> Vec<LongCell> {
+        let cells = RSEntry::read_long_cells(options);
+        match RSEntry::picked_cell_indices(options) {
+            Some(indices) => indices.into_iter().map(|index| cells[index]).collect(),
+            None => cells,
+        }
+    }
+
+    // With --fields, where each column it picks is among the cells read, in
+    // the order given. The name stays last.
+    fn picked_cell_indices(options: &Options) -> Option<Vec<usize>> {
+        let fields = options.long_fields.as_ref()?;
+        let cells = RSEntry::read_long_cells(options);
+        let mut indices = vec![];
+        for cell in fields.iter().filter_map(|field| LongCell::of_field(*field)) {
+            let Some(index) = cells.iter().position(|read| *read == cell) else {
+                continue;
+            };
+            if indices.contains(&index) {
+                continue;
+            }
+            indices.push(index);
+            // A time split over two cells takes both
+            if cells.get(index + 1) == Some(&LongCell::Continued) {
+                indices.push(index + 1);
+            }
+        }
+        indices.push(cells.len() - 1);
+        Some(indices)
+    }
+
+    // A row of long output as --fields orders it. One short of cells, like
+    // that of an entry without metadata, is kept as it is.
+    fn pick_long_cells(row: Vec<String>, options: &Options) -> Vec<String> {
+        match RSEntry::picked_cell_indices(options) {
+            Some(indices) if row.len() == RSEntry::read_long_cells(options).len() => indices
+                .into_iter()
+                .map(|index| row[index].clone())
+                .collect(),
+            _ => row,
+        }
+    }
+
+    // The cells get_table_row reads for long output, in ls's order
+    fn read_long_cells(options: &Options) -> Vec<LongCell> {
+        let time_cells = match (&options.compat, options.time_style) {
+            (Compat::None, TimeStyle::Default) => vec![LongCell::Time, LongCell::Continued],
+            _ => vec![LongCell::Time],
+        };
+        let mut cells = vec![];
+        if options.is_show_size_blocks {
+            cells.push(LongCell::Blocks);
+        }
+        if options.is_show_inode {
+            cells.push(LongCell::Inode);
+        }
+        if options.is_show_device {
+            cells.push(LongCell::Device);
+        }
+        if !options.is_hide_permissions {
+            cells.push(LongCell::Permissions);
+        }
+        cells.push(LongCell::Links);
+        if !options.is_hide_user {
+            cells.extend([LongCell::Owner, LongCell::Group]);
+        }
+        if cfg!(feature = "selinux") && options.is_context {
+            cells.push(LongCell::Context);
+        }
+        if !options.is_hide_filesize {
+            cells.push(LongCell::Size);
+        }
+        if !options.is_hide_time {
+            cells.extend(&time_cells);
+        }
+        if cfg!(feature = "git") && options.is_git_time {
+            cells.push(LongCell::Commit);
+            cells.extend(&time_cells[1..]);
+        }
+        if cfg!(feature = "git") && options.is_git_author {
+            cells.push(LongCell::Author);
+        }
+        if options.is_git_status && options.is_git_status_shown.get() {
+            cells.push(LongCell::GitStatus);
+        }
+        if options.is_show_tags {
+            cells.push(LongCell::Tags);
+        }
+        cells.push(LongCell::Name);
+        cells
+    }
+
+    // Where a cell is in long output, if it's shown
+    fn long_cell_index(options: &Options, cell: LongCell) -> Option<usize> {
+        RSEntry::long_cells(options)
+            .iter()
+            .position(|shown| *shown == cell)
+    }
+
+    // Names for the cells of long output, for --header. A time split over
+    // two cells is named in the first.
+    fn header_row(options: &Options) -> Vec<String> {
+        RSEntry::long_cells(options)
+            .into_iter()
+            .map(|cell| match cell.header() {
+                "" => String::new(),
+                name => options.paint(&[StyleClass::TableHeader], name),
+            })
+            .collect()
+    }
+}
+
+impl Ord for RSEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.name.cmp(&other.name)
+    }
+}
+
+impl PartialOrd for RSEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Eq for RSEntry {}
+
+impl PartialEq for RSEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.path == other.path
+    }
+}
+
+impl fmt::Display for RSEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Plain, the colors go on in get_display_name where Options says
+        // whether to use them
+        write!(f, "{}", self.name)
+    }
+}
+
+impl Borrow<str> for RSEntry {
+    fn borrow(&self) -> &str {
+        &self.name
+    }
+}
+
+// Entries for the names in the directory. With a normalization the names are
+// shown, sorted and matched normalized, the paths keep the names as read. A
+// name that isn't UTF-8 is shown lossily, -b shows its bytes.
+fn get_entries(dir_entries: Vec<OsString>, base_path: &Path, options: &Options) -> RSEntries {
+    let mut block_size = BlockCount(0);
     let mut rs_entries: Vec<RSEntry> = vec![];
     for dir_entry in dir_entries {
         let local_path = base_path.join(&dir_entry);
-        let metadata = fs::metadata(&local_path);
+        let name =
+            normalize::normalize(&dir_entry.to_string_lossy(), options.normalization).into_owned();
+        // A symlink describes itself, as in ls, not what it points at. With -L
+        // it describes its target, unless it's dangling or loops.
+        let metadata = match options.is_dereference {
+            true => fs::metadata(&local_path).or_else(|err| {
+                eprintln!("rs: cannot access '{}': {}", local_path.display(), err);
+                fs::symlink_metadata(&local_path)
+            }),
+            false => fs::symlink_metadata(&local_path),
+        };
         match metadata {
             Ok(meta) => {
-                block_size += meta.st_blocks();
+                block_size += BlockCount(meta.st_blocks());
                 rs_entries.push(RSEntry {
-                    name: dir_entry,
+                    name,
+                    raw_name: dir_entry,
                     path: local_path,
+                    kind: Mode(meta.st_mode()).kind(),
                     metadata: Some(meta),
+                    duplicate_group: None,
+                    is_case_collision: false,
+                    #[cfg(feature = "git")]
+                    last_commit: None,
+                    #[cfg(feature = "git")]
+                    git_status: None,
+                    total_size: None,
                 })
             }
             Err(err) => {
-                eprintln!("{}", err);
+                eprintln!("rs: cannot access '{}': {}", local_path.display(), err);
                 rs_entries.push(RSEntry {
-                    name: dir_entry,
+                    name,
+                    raw_name: dir_entry,
                     path: local_path,
+                    kind: EntryKind::Unknown,
                     metadata: None,
+                    duplicate_group: None,
+                    is_case_collision: false,
+                    #[cfg(feature = "git")]
+                    last_commit: None,
+                    #[cfg(feature = "git")]
+                    git_status: None,
+                    total_size: None,
                 });
             }
         }
@@ -346,96 +1643,1254 @@ fn get_entries(dir_entries: Vec<String>, base_path: &Path) -> RSEntries {
     RSEntries {
         entries: rs_entries,
         block_size,
+        is_directory_listing: true,
+    }
+}
+
+// Names listed in the directory's .hidden file, one per line, which file
+// managers hide like dotfiles. A missing or unreadable file hides nothing.
+fn get_dot_hidden_names(base_path: &Path) -> HashSet<String> {
+    match fs::read_to_string(base_path.join(DOT_HIDDEN_FILE_NAME)) {
+        Ok(contents) => contents.lines().map(String::from).collect(),
+        Err(_) => HashSet::new(),
+    }
+}
+
+// Whether any of the -I or --hide patterns matches the name. Like the shell,
+// a wildcard doesn't match a leading dot.
+fn is_matched_by(patterns: &[String], name: &str) -> bool {
+    patterns.iter().any(|pattern| glob::is_match(pattern, name))
+}
+
+fn get_dir_entries(dir: ReadDir, base_path: &Path, options: &Options) -> Vec<OsString> {
+    let is_show_hidden = options.is_show_all || options.is_show_almost_all;
+    let dot_hidden_names = match is_show_hidden || options.is_no_dot_hidden {
+        true => HashSet::new(),
+        false => get_dot_hidden_names(base_path),
+    };
+    #[cfg_attr(not(feature = "git"), allow(unused_mut))]
+    let mut names: Vec<OsString> = dir
+        .into_iter()
+        .filter_map(|d| d.ok())
+        .map(|d| d.file_name())
+        .filter(|o| is_show_hidden || !o.as_bytes().starts_with(CURRENT_DIR.as_bytes()))
+        .filter(|o| !o.to_str().is_some_and(|s| dot_hidden_names.contains(s)))
+        .filter(|o| !(options.is_ignore_backups && o.as_bytes().ends_with(b"~")))
+        // -I applies even with -a, --hide only without -a or -A. Both match
+        // the name as --normalize shows it.
+        .filter(|o| {
+            let lossy = o.to_string_lossy();
+            let name = normalize::normalize(&lossy, options.normalization);
+            !is_matched_by(&options.ignore_patterns, &name)
+                && (is_show_hidden || !is_matched_by(&options.hide_patterns, &name))
+        })
+        .collect();
+    // Ignored directories go too, so nothing walks into them
+    #[cfg(feature = "git")]
+    if options.is_git_ignore {
+        let ignored = git::ignored(base_path);
+        names.retain(|name| !ignored.contains(name));
+    }
+    names
+}
+
+// fn get_tabular_entries(rs_entries: RSEntries, options: &Options) -> Vec<Vec<String>> {
+//     let mut output: Vec<Vec<String>> = vec![];
+//     for entry in rs_entries.entries {
+//         let row = entry.get_table_row(options);
+//         output.push(row);
+//     }
+//     output
+// }
+
+// One line for each filesystem the operands are on, in the order given
+fn print_fs_infos(operands: &[PathBuf], options: &Options) {
+    let mut devices = HashSet::new();
+    for operand in operands {
+        // Missing operands were reported when they were listed
+        let Ok(meta) = fs::metadata(operand) else {
+            continue;
+        };
+        if devices.insert(meta.st_dev()) {
+            print_fs_info(operand, options);
+        }
+    }
+}
+
+fn print_fs_info(path: &Path, options: &Options) {
+    match fs_info::FsStats::of(path) {
+        Ok(stats) => {
+            let mount = fs_info::find_mount(path);
+            println!(
+                "{}",
+                fs_info::summary(
+                    mount.as_ref(),
+                    &stats,
+                    options.is_human_readable.then_some(options.size_precision)
+                )
+            );
+        }
+        Err(err) => eprintln!(
+            "rs: cannot read filesystem of '{}': {}",
+            path.display(),
+            err
+        ),
+    }
+}
+
+// Reads a directory into tree nodes, descending into subdirectories as far
+// as --level allows
+fn get_tree_nodes(dir: ReadDir, base_path: &Path, options: &Options) -> Vec<tree::Node<RSEntry>> {
+    let ancestors = fs::metadata(base_path)
+        .map(|meta| vec![(meta.st_dev(), meta.st_ino())])
+        .unwrap_or_default();
+    read_tree_level(dir, base_path, 1, &ancestors, options)
+}
+
+// Symlinks to directories are only followed with -L, and then not into a
+// directory that is already one of the ancestors, so a link cycle can't
+// recurse forever
+fn read_tree_level(
+    dir: ReadDir,
+    base_path: &Path,
+    depth: usize,
+    ancestors: &[(u64, u64)],
+    options: &Options,
+) -> Vec<tree::Node<RSEntry>> {
+    let mut rs_entries = get_entries(get_dir_entries(dir, base_path, options), base_path, options);
+    #[cfg(feature = "git")]
+    if options.is_reading_last_commits() {
+        rs_entries.read_last_commits();
+    }
+
+    // Subdirectories are read before ordering, so --total-size can sum them
+    // from below in the one walk and sort by the sums
+    let mut subtrees = HashMap::new();
+    let mut cut_names = HashSet::new();
+    for entry in rs_entries.entries.iter_mut() {
+        let Some(meta) = &entry.metadata else {
+            continue;
+        };
+        let key = (meta.st_dev(), meta.st_ino());
+        if entry.kind != EntryKind::Dir
+            || (entry.path.is_symlink() && !options.is_dereference)
+            || ancestors.contains(&key)
+        {
+            continue;
+        }
+        // A directory past the last level is a leaf, still with its whole
+        // size for --total-size and marked when that hides any entries
+        if options.tree_level.is_some_and(|level| depth >= level) {
+            if options.is_total_size {
+                if fs::read_dir(&entry.path).is_ok_and(|mut read_dir| read_dir.next().is_some()) {
+                    cut_names.insert(entry.name.clone());
+                }
+                entry.total_size = Some(total_size::total_size(
+                    &entry.path,
+                    meta,
+                    options.cache_trust,
+                ));
+            }
+            continue;
+        }
+        let subtree = match fs::read_dir(&entry.path) {
+            Ok(read_dir) => {
+                let ancestors = [ancestors, &[key]].concat();
+                let nodes = read_tree_level(read_dir, &entry.path, depth + 1, &ancestors, options);
+                (nodes, None)
+            }
+            Err(err) => (vec![], Some(err.to_string())),
+        };
+        if options.is_total_size {
+            let total_size = subtree.0.iter().fold(ByteSize(0), |mut total, node| {
+                total += node.item.get_file_size();
+                total
+            });
+            entry.total_size = Some(total_size);
+        }
+        subtrees.insert(entry.name.clone(), subtree);
+    }
+    rs_entries.order(options);
+
+    let mut nodes = vec![];
+    for entry in rs_entries.entries {
+        let subtree = subtrees.remove(&entry.name);
+        let is_dir = subtree.is_some();
+        let (children, error) = subtree.unwrap_or_default();
+        nodes.push(tree::Node {
+            is_dir,
+            is_match: options
+                .regex
+                .as_ref()
+                .is_none_or(|regex| regex.is_match(&entry.name)),
+            is_truncated: cut_names.contains(&entry.name),
+            item: entry,
+            error,
+            children,
+        });
+    }
+    nodes
+}
+
+// Cuts the tree down to the matches when filtering
+fn prune_tree(nodes: &mut Vec<tree::Node<RSEntry>>, options: &Options) {
+    // Matches are marked while reading, so whole branches can be dropped after
+    if options.regex.is_some() || options.is_prune {
+        tree::prune(nodes, options.is_prune);
+    }
+}
+
+// Reports the directories that couldn't be opened, in tree order
+fn print_tree_errors(nodes: &[tree::Node<RSEntry>]) {
+    for node in nodes {
+        if let Some(err) = &node.error {
+            eprintln!(
+                "rs: cannot open directory '{}': {}",
+                node.item.path.display(),
+                err
+            );
+        }
+        print_tree_errors(&node.children);
+    }
+}
+
+fn print_tree(dir: ReadDir, base_path: &Path, options: &Options) {
+    let mut nodes = get_tree_nodes(dir, base_path, options);
+    print_tree_errors(&nodes);
+    prune_tree(&mut nodes, options);
+
+    let size_width = match options.is_total_size {
+        true => get_tree_size_width(&nodes, options),
+        false => 0,
+    };
+    let label = |node: &tree::Node<RSEntry>| {
+        let name = node.item.get_display_name(options);
+        match options.is_total_size {
+            true => format!(
+                "[{:>width$}]  {}{}",
+                get_tree_size_display(&node.item, options),
+                name,
+                if node.is_truncated {
+                    TREE_TRUNCATED_MARKER
+                } else {
+                    ""
+                },
+                width = size_width
+            ),
+            false => name,
+        }
+    };
+
+    println!("{}", base_path.display());
+    for line in tree::render(&nodes, &label) {
+        print_line(&line, options);
+    }
+}
+
+// A tree node's size, a directory's being everything the tree holds below it
+fn get_tree_size_display(entry: &RSEntry, options: &Options) -> String {
+    match options.is_human_readable {
+        true => entry.get_file_size().human_readable(options.size_precision),
+        false => entry.get_file_size().to_string(),
+    }
+}
+
+// The widest size in the tree, so the sizes line up on the right
+fn get_tree_size_width(nodes: &[tree::Node<RSEntry>], options: &Options) -> usize {
+    nodes
+        .iter()
+        .map(|node| {
+            get_tree_size_display(&node.item, options)
+                .len()
+                .max(get_tree_size_width(&node.children, options))
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+// A DOT node label: the name, and the size below it with -H
+fn get_dot_label(name: &str, size: ByteSize, options: &Options) -> String {
+    match options.is_human_readable {
+        true => format!("{}\n{}", name, size.human_readable(options.size_precision)),
+        false => name.to_string(),
+    }
+}
+
+fn add_dot_nodes(
+    graph: &mut dot::Digraph,
+    parent: usize,
+    nodes: &[tree::Node<RSEntry>],
+    options: &Options,
+) {
+    for node in nodes {
+        let label = get_dot_label(&node.item.name, node.item.get_file_size(), options);
+        // Directories past --level have no children, but are still boxes
+        let index = graph.add_node(label, node.item.kind == EntryKind::Dir);
+        graph.add_edge(parent, index);
+        add_dot_nodes(graph, index, &node.children, options);
+    }
+}
+
+// Prints the operands as one Graphviz digraph, with an edge from each
+// directory to each of its entries as far down as --tree would go
+fn print_dot(operands: &[PathBuf], options: &Options) -> Result<(), String> {
+    let mut graph = dot::Digraph::default();
+    let mut result = Ok(());
+    for base_path in operands {
+        let metadata = match fs::metadata(base_path) {
+            Ok(metadata) => metadata,
+            Err(err) => {
+                result = result.and(Err(format!(
+                    "rs: cannot access '{}': {}",
+                    base_path.display(),
+                    err
+                )));
+                continue;
+            }
+        };
+        let label = get_dot_label(
+            &base_path.display().to_string(),
+            ByteSize(metadata.len()),
+            options,
+        );
+        let root = graph.add_node(label, metadata.is_dir());
+        if let Ok(read_dir) = fs::read_dir(base_path) {
+            let mut nodes = get_tree_nodes(read_dir, base_path, options);
+            print_tree_errors(&nodes);
+            prune_tree(&mut nodes, options);
+            add_dot_nodes(&mut graph, root, &nodes, options);
+        }
+    }
+    println!("{}", graph.render());
+    result
+}
+
+// One field of an entry, null when the entry has no value for it. Names
+// that aren't UTF-8 are given lossily.
+fn get_json_field(entry: &RSEntry, field: Field) -> json::Value {
+    let meta = entry.metadata.as_ref();
+    match field {
+        Field::Name => json::Value::String(entry.name.clone()),
+        Field::Path => json::Value::String(entry.path.display().to_string()),
+        Field::Type => json::Value::String(entry.kind.name().to_string()),
+        Field::Size => meta.map(|meta| meta.len()).into(),
+        Field::Blocks => meta.map(|meta| meta.st_blocks()).into(),
+        Field::Inode => meta.map(|meta| meta.st_ino()).into(),
+        Field::Links => meta.map(|meta| meta.st_nlink()).into(),
+        Field::Uid => meta.map(|meta| u64::from(meta.st_uid())).into(),
+        Field::Gid => meta.map(|meta| u64::from(meta.st_gid())).into(),
+        Field::User => meta
+            .and_then(|meta| user::get_by_uid(meta.st_uid()).ok())
+            .filter(|name| !name.is_empty())
+            .into(),
+        Field::Group => meta
+            .and_then(|meta| user::group_by_gid(meta.st_gid()).ok())
+            .filter(|name| !name.is_empty())
+            .into(),
+        Field::Mode => meta.map(|meta| Mode(meta.st_mode()).octal()).into(),
+        Field::Permissions => meta.map(|meta| Mode(meta.st_mode()).symbolic()).into(),
+        Field::Mtime => get_json_time(meta.map(|meta| meta.st_mtime())),
+        Field::Atime => get_json_time(meta.map(|meta| meta.st_atime())),
+        Field::Ctime => get_json_time(meta.map(|meta| meta.st_ctime())),
+        Field::Target => match entry.path.is_symlink() {
+            true => fs::read_link(&entry.path)
+                .ok()
+                .map(|target| target.display().to_string())
+                .into(),
+            false => json::Value::Null,
+        },
+        #[cfg(feature = "git")]
+        Field::GitTime => entry.last_commit.as_ref().map(|commit| commit.time).into(),
+        #[cfg(feature = "git")]
+        Field::GitAuthor => entry
+            .last_commit
+            .as_ref()
+            .map(|commit| commit.author.clone())
+            .into(),
+        // Refused by --fields without the feature
+        #[cfg(not(feature = "git"))]
+        Field::GitTime | Field::GitAuthor => json::Value::Null,
+    }
+}
+
+// Seconds since the epoch as ISO 8601, null without them
+fn get_json_time(secs: Option<i64>) -> json::Value {
+    secs.map(time::iso8601_timestamp).into()
+}
+
+// An entry as --csv and --tsv list it, the --fields with the same values as
+// --json. Fields an entry has no value for are empty.
+fn get_record(entry: &RSEntry, options: &Options) -> Vec<String> {
+    options
+        .fields
+        .iter()
+        .map(|field| match get_json_field(entry, *field) {
+            json::Value::Number(number) => number.to_string(),
+            json::Value::String(text) => text,
+            _ => String::new(),
+        })
+        .collect()
+}
+
+// An entry as a JSON object of the --fields, directories holding their
+// children, or the error that kept them from being read
+fn get_json_object(
+    entry: &RSEntry,
+    error: Option<&String>,
+    children: Option<Vec<json::Value>>,
+    options: &Options,
+) -> json::Value {
+    let mut members: Vec<(&'static str, json::Value)> = options
+        .fields
+        .iter()
+        .map(|field| (field.name(), get_json_field(entry, *field)))
+        .collect();
+    if let Some(err) = error {
+        members.push(("error", json::Value::String(err.to_string())));
+    }
+    if let Some(children) = children {
+        members.push(("children", json::Value::Array(children)));
+    }
+    json::Value::Object(members)
+}
+
+fn get_json_nodes(nodes: Vec<tree::Node<RSEntry>>, options: &Options) -> Vec<json::Value> {
+    nodes
+        .into_iter()
+        .map(|node| {
+            let children = node.is_dir.then(|| get_json_nodes(node.children, options));
+            get_json_object(&node.item, node.error.as_ref(), children, options)
+        })
+        .collect()
+}
+
+// Prints the operands as one JSON array, each directory nesting its
+// entries under "children" as far down as --tree would go
+fn print_json_tree(operands: &[PathBuf], options: &Options) -> Result<(), String> {
+    let mut roots = vec![];
+    for base_path in operands {
+        let mut rs_entries = get_entries(
+            vec![base_path.clone().into_os_string()],
+            Path::new(""),
+            options,
+        );
+        #[cfg(feature = "git")]
+        if options.is_reading_last_commits() {
+            rs_entries.read_last_commits();
+        }
+        let Some(entry) = rs_entries.entries.pop() else {
+            continue;
+        };
+        let root = match fs::read_dir(base_path) {
+            Ok(read_dir) => {
+                let mut nodes = get_tree_nodes(read_dir, base_path, options);
+                prune_tree(&mut nodes, options);
+                let children = Some(get_json_nodes(nodes, options));
+                get_json_object(&entry, None, children, options)
+            }
+            Err(_) if entry.kind != EntryKind::Dir && entry.metadata.is_some() => {
+                get_json_object(&entry, None, None, options)
+            }
+            Err(err) => get_json_object(&entry, Some(&err.to_string()), None, options),
+        };
+        roots.push(root);
+    }
+    println!("{}", json::Value::Array(roots).render());
+    Ok(())
+}
+
+// Names of the entries a directory listing shows, before any metadata is read
+fn get_listed_names(dir: ReadDir, base_path: &Path, options: &Options) -> Vec<OsString> {
+    let mut dir_entries = get_dir_entries(dir, base_path, options);
+
+    if options.is_show_all {
+        for name in [CURRENT_DIR, PARENT_DIR] {
+            if !is_matched_by(&options.ignore_patterns, name) {
+                dir_entries.push(OsString::from(name));
+            }
+        }
+    }
+
+    dir_entries
+}
+
+// How many entries the listing would show. Only --duplicates needs the
+// entries read and ordered, everything else is counted from names.
+fn count_entries(dir: ReadDir, base_path: &Path, options: &Options) -> usize {
+    #[cfg_attr(not(feature = "hash"), allow(unused_mut))]
+    let mut rs_entries = match options.is_listing_flat() {
+        true => {
+            let mut entries = vec![];
+            get_flat_entries(dir, base_path, Path::new(""), options, &mut entries);
+            RSEntries {
+                entries,
+                block_size: BlockCount(0),
+                is_directory_listing: true,
+            }
+        }
+        false if options.is_duplicates => {
+            let mut rs_entries = get_entries(
+                get_listed_names(dir, base_path, options),
+                base_path,
+                options,
+            );
+            rs_entries.take_filtered_out(options);
+            rs_entries
+        }
+        false => {
+            let names = get_listed_names(dir, base_path, options);
+            let count = names
+                .iter()
+                .filter(|name| {
+                    is_filtered_in(&name.to_string_lossy(), &base_path.join(name), options)
+                })
+                .count();
+            let mut count = options
+                .recent
+                .or(options.largest)
+                .map_or(count, |n| count.min(n));
+            if !options.is_recursive {
+                return count;
+            }
+            // -R walks every subdirectory, whether the filters show it or not
+            for name in names
+                .iter()
+                .filter(|name| *name != CURRENT_DIR && *name != PARENT_DIR)
+            {
+                let subdirectory = base_path.join(name);
+                if !fs::symlink_metadata(&subdirectory).is_ok_and(|meta| meta.is_dir()) {
+                    continue;
+                }
+                match fs::read_dir(&subdirectory) {
+                    Ok(read_dir) => count += count_entries(read_dir, &subdirectory, options),
+                    Err(err) => eprintln!(
+                        "rs: cannot open directory '{}': {}",
+                        subdirectory.display(),
+                        err
+                    ),
+                }
+            }
+            return count;
+        }
+    };
+
+    #[cfg(feature = "hash")]
+    if options.is_duplicates {
+        rs_entries.order(options);
+        rs_entries.group_duplicates(options.is_show_unique);
+        return rs_entries.entries.len();
+    }
+    let count = rs_entries.entries.len();
+    options
+        .recent
+        .or(options.largest)
+        .map_or(count, |n| count.min(n))
+}
+
+// A bare count for one operand, otherwise a count per operand and a total
+fn print_counts(operands: &[PathBuf], options: &Options) -> Result<(), String> {
+    let mut result = Ok(());
+    let mut total = 0;
+    for base_path in operands {
+        let count = match fs::read_dir(base_path) {
+            Ok(read_dir) => count_entries(read_dir, base_path, options),
+            Err(_) if fs::metadata(base_path).is_ok_and(|meta| meta.is_file()) => 1,
+            Err(err) => {
+                let error = format!("rs: cannot access '{}': {}", base_path.display(), err);
+                result = result.and(Err(error));
+                continue;
+            }
+        };
+        if operands.len() > 1 {
+            println!("{} {}", count, base_path.display());
+        }
+        total += count;
+    }
+
+    match operands.len() > 1 {
+        true => println!("{} total", total),
+        false => println!("{}", total),
+    }
+    result
+}
+
+fn process_entries(
+    dir: ReadDir,
+    base_path: &Path,
+    options: &Options,
+) -> Result<ListingTotals, String> {
+    let mut rs_entries = get_entries(
+        get_listed_names(dir, base_path, options),
+        base_path,
+        options,
+    );
+    // Filters only decide what is shown, so -R still walks the directories
+    // they leave out, after the listed ones
+    let mut filtered_out = rs_entries.take_filtered_out(options);
+    let (mut totals, mut subdirectories) = print_entries(rs_entries, options);
+    if options.is_recursive {
+        filtered_out.order(options);
+        subdirectories.extend(filtered_out.subdirectories());
+    }
+
+    // With -R each subdirectory follows as its own section, like GNU ls.
+    // One that can't be opened is reported and the rest still listed.
+    for subdirectory in subdirectories {
+        if !options.is_single_document() {
+            println!();
+            println!("{}:", subdirectory.display());
+        }
+        match fs::read_dir(&subdirectory) {
+            Ok(read_dir) => totals += process_entries(read_dir, &subdirectory, options)?,
+            Err(err) => {
+                eprintln!(
+                    "rs: cannot open directory '{}': {}",
+                    subdirectory.display(),
+                    err
+                );
+                totals.errors += 1;
+            }
+        }
+    }
+
+    Ok(totals)
+}
+
+// Lists operands that aren't listed as directories, every file operand and
+// with -d every operand, as the entries of one listing
+fn print_operand_entries(names: Vec<OsString>, options: &Options) -> ListingTotals {
+    let mut rs_entries = get_entries(names, Path::new(""), options);
+    rs_entries.is_directory_listing = false;
+    if options.is_following_operand_links() {
+        rs_entries.dereference();
+    }
+    let (totals, _) = print_entries(rs_entries, options);
+    totals
+}
+
+// Lists everything below the directory in one set, each entry named by its
+// path relative to the operand, so sorting applies across the whole tree
+fn process_flat_entries(
+    dir: ReadDir,
+    base_path: &Path,
+    options: &Options,
+) -> Result<ListingTotals, String> {
+    let mut entries = vec![];
+    get_flat_entries(dir, base_path, Path::new(""), options, &mut entries);
+
+    let mut rs_entries = RSEntries {
+        entries,
+        block_size: BlockCount(0),
+        is_directory_listing: true,
+    };
+    rs_entries.count_blocks();
+    let (totals, _) = print_entries(rs_entries, options);
+    Ok(totals)
+}
+
+// Filters only decide what is shown, every subdirectory is still walked.
+// Symlinks to directories aren't followed, so a link cycle can't recurse forever.
+fn get_flat_entries(
+    dir: ReadDir,
+    dir_path: &Path,
+    relative_path: &Path,
+    options: &Options,
+    flat_entries: &mut Vec<RSEntry>,
+) {
+    for mut entry in get_entries(get_dir_entries(dir, dir_path, options), dir_path, options).entries
+    {
+        let is_dir = entry.kind == EntryKind::Dir && !entry.path.is_symlink();
+        let relative_entry_path = relative_path.join(&entry.name);
+        if is_dir {
+            match fs::read_dir(&entry.path) {
+                Ok(read_dir) => get_flat_entries(
+                    read_dir,
+                    &entry.path,
+                    &relative_entry_path,
+                    options,
+                    flat_entries,
+                ),
+                Err(err) => eprintln!(
+                    "rs: cannot open directory '{}': {}",
+                    entry.path.display(),
+                    err
+                ),
+            }
+        }
+
+        if is_filtered_in(&entry.name, &entry.path, options) {
+            entry.name = relative_entry_path.display().to_string();
+            entry.raw_name = relative_path.join(&entry.raw_name).into_os_string();
+            flat_entries.push(entry);
+        }
+    }
+
+    // Only the newest N are shown, so once there are twice that many the
+    // rest are dropped, and the walk never holds more than that of the tree
+    if let Some(n) = options.recent {
+        if flat_entries.len() > 2 * n {
+            let mut newest = RSEntries {
+                entries: mem::take(flat_entries),
+                block_size: BlockCount(0),
+                is_directory_listing: true,
+            };
+            newest.order(options);
+            *flat_entries = newest.entries;
+        }
+    }
+}
+
+// Whether an entry passes --broken-links, --only-dirs, --only-files and
+// --regex
+fn is_filtered_in(name: &str, path: &Path, options: &Options) -> bool {
+    if !is_type_shown(path, options) {
+        return false;
+    }
+    if options.is_broken_links && !links::is_broken(path) {
+        return false;
+    }
+    if let Some(samefile) = options.samefile {
+        // Hard links share the inode, a symlink to the file doesn't
+        let inode = fs::symlink_metadata(path).map(|meta| (meta.st_dev(), meta.st_ino()));
+        if !inode.is_ok_and(|inode| inode == samefile) {
+            return false;
+        }
+    }
+    if let Some(perm) = &options.perm {
+        // The mode of the link itself, as listed, unless -L follows it.
+        // Entries that can't be stat'ed have no mode to test.
+        let metadata = match options.is_dereference {
+            true => fs::metadata(path),
+            false => fs::symlink_metadata(path),
+        };
+        let mode = metadata.map(|meta| Mode(meta.st_mode()));
+        if !mode.is_ok_and(|mode| perm.matches(mode)) {
+            return false;
+        }
+    }
+    options
+        .regex
+        .as_ref()
+        .is_none_or(|regex| regex.is_match(&normalize::normalize(name, options.normalization)))
+}
+
+// Whether --only-dirs or --only-files keeps the entry. With -L a symlink is
+// the type of its target. An entry whose type can't be read is reported and
+// left out of both.
+fn is_type_shown(path: &Path, options: &Options) -> bool {
+    if !options.is_only_dirs && !options.is_only_files {
+        return true;
+    }
+    let metadata = match options.is_dereference {
+        true => fs::metadata(path).or_else(|_| fs::symlink_metadata(path)),
+        false => fs::symlink_metadata(path),
+    };
+    match metadata {
+        Ok(meta) if options.is_only_dirs => meta.is_dir(),
+        Ok(meta) => meta.is_file(),
+        Err(err) => {
+            eprintln!("rs: cannot determine type of '{}': {}", path.display(), err);
+            false
+        }
+    }
+}
+
+// e.g. "grand total: 812 entries, 3.4G", allocated space unless
+// --apparent-size, shown like the total line
+fn print_grand_total(totals: &ListingTotals, options: &Options) {
+    let size = match (options.is_apparent_size, options.is_human_readable) {
+        (true, true) => totals.bytes.human_readable(options.size_precision),
+        (true, false) => totals.bytes.to_string(),
+        (false, _) => blocks_display(totals.blocks, options),
+    };
+    let noun = match totals.entries {
+        1 => "entry",
+        _ => "entries",
+    };
+    println!("grand total: {} {}, {}", totals.entries, noun, size);
+}
+
+// Counts the entries in the summary in place of listing them
+fn add_to_summary(rs_entries: &RSEntries, summary_by: &SummaryBy, options: &Options) {
+    let items = rs_entries.entries.iter().map(|entry| {
+        let key = match summary_by {
+            SummaryBy::Extension => match (entry.kind, entry.path.extension()) {
+                (EntryKind::Dir, _) => String::from(DIRECTORY_SUMMARY_KEY),
+                (_, Some(ext)) => ext.to_string_lossy().to_string(),
+                (_, None) => String::from(NO_EXTENSION_SUMMARY_KEY),
+            },
+            // An id without a name is counted under the id, not a blank
+            SummaryBy::Owner => match &entry.metadata {
+                Some(meta) => match get_owner_display(meta.st_uid(), options) {
+                    name if name.is_empty() => meta.st_uid().to_string(),
+                    name => name,
+                },
+                None => String::from(UNKNOWN_SUMMARY_KEY),
+            },
+            SummaryBy::Group => match &entry.metadata {
+                Some(meta) => match get_group_display(meta.st_gid(), options) {
+                    name if name.is_empty() => meta.st_gid().to_string(),
+                    name => name,
+                },
+                None => String::from(UNKNOWN_SUMMARY_KEY),
+            },
+        };
+        (key, entry.get_file_size())
+    });
+    options.summary_items.borrow_mut().extend(items);
+}
+
+// A count and total size per group, over everything listed, under a header
+// row. Nothing is printed when nothing was listed.
+fn print_summary(summary_by: &SummaryBy, options: &Options) {
+    let groups = summary::group(options.summary_items.take().into_iter());
+    if groups.is_empty() {
+        return;
+    }
+    let key_title = match summary_by {
+        SummaryBy::Extension => "Extension",
+        SummaryBy::Owner => "Owner",
+        SummaryBy::Group => "Group",
+    };
+    let mut rows = vec![["Count", "Size", key_title].map(String::from).to_vec()];
+    rows.extend(groups.into_iter().map(|group| {
+        let size = match options.is_human_readable {
+            true => group.total_size.human_readable(options.size_precision),
+            false => group.total_size.to_string(),
+        };
+        vec![group.count.to_string(), size, group.key]
+    }));
+    println!("{}", table(rows, TableAlignment::RightLastLeft).unwrap());
+}
+
+// Indices of the long output cells in the order they are given up to fit a
+// narrow terminal: the SELinux context, which is the widest by far, then
+// group, nlink, owner, and blocks and inode last
+fn responsive_drop_order(cells: &[LongCell]) -> Vec<usize> {
+    [
+        LongCell::Context,
+        LongCell::Group,
+        LongCell::Links,
+        LongCell::Owner,
+        LongCell::Blocks,
+        LongCell::Inode,
+    ]
+    .into_iter()
+    .filter_map(|cell| cells.iter().position(|shown| *shown == cell))
+    .collect()
+}
+
+// Makes every row of a long listing fit on one line of the given width,
+// dropping columns in priority order and then cutting names short. What to
+// drop is decided once from the widest cell of each column.
+fn fit_long_rows(
+    rows: &mut [Vec<String>],
+    left_columns: &mut Vec<usize>,
+    width: usize,
+    cells: &[LongCell],
+) {
+    let mut widths = format::column_widths(rows);
+    if widths.is_empty() {
+        return;
+    }
+    // Cells are joined by a single space
+    let table_width = |widths: &[usize]| widths.iter().sum::<usize>() + widths.len() - 1;
+
+    let mut dropped = vec![];
+    for index in responsive_drop_order(cells) {
+        if table_width(&widths) <= width {
+            break;
+        }
+        dropped.push(index);
+        widths[index] = 0;
+    }
+    // Later columns shift down, so take the rightmost out first
+    dropped.sort_unstable_by(|a, b| b.cmp(a));
+    for index in &dropped {
+        widths.remove(*index);
+        // A row can be short of the cell, like one for an entry without metadata
+        for row in rows.iter_mut().filter(|row| row.len() > *index) {
+            row.remove(*index);
+        }
+        left_columns.retain(|column| column != index);
+        for column in left_columns.iter_mut() {
+            if *column > *index {
+                *column -= 1;
+            }
+        }
+    }
+
+    // Every other column takes its width and a separator
+    let name_width = widths.pop().unwrap_or(0);
+    let name_budget = width.saturating_sub(widths.iter().map(|width| width + 1).sum());
+    if name_width > name_budget {
+        for row in rows.iter_mut() {
+            if let Some(name) = row.last_mut() {
+                *name = format::truncate_with_ellipsis(name, name_budget);
+            }
+        }
+    }
+}
+
+// Lists the entries, returning their totals and, with -R, the directories
+// among them to list next
+fn print_entries(mut rs_entries: RSEntries, options: &Options) -> (ListingTotals, Vec<PathBuf>) {
+    if let Some(summary_by) = &options.summary_by {
+        add_to_summary(&rs_entries, summary_by, options);
+        // The table comes at the end, so the order -R walks in doesn't matter
+        let subdirectories = match options.is_recursive {
+            true => rs_entries.subdirectories(),
+            false => vec![],
+        };
+        return (rs_entries.totals(), subdirectories);
+    }
+
+    if options.is_total_size {
+        rs_entries.read_total_sizes(options);
+    }
+    #[cfg(feature = "git")]
+    if options.is_git_time
+        || matches!(options.sort_key, RSSort::GitTime)
+        || options.is_reading_last_commits()
+    {
+        rs_entries.read_last_commits();
+    }
+    #[cfg(feature = "git")]
+    if options.is_git_status && matches!(options.output_format, OutputFormat::Long) {
+        options
+            .is_git_status_shown
+            .set(rs_entries.read_git_statuses());
+    }
+
+    rs_entries.order(options);
+
+    #[cfg(feature = "hash")]
+    if options.is_duplicates {
+        rs_entries.group_duplicates(options.is_show_unique);
+    }
+
+    let mut totals = rs_entries.totals();
+    if options.is_check_case_collisions {
+        totals.case_collisions = rs_entries.mark_case_collisions();
+    }
+    let subdirectories = match options.is_recursive {
+        true => rs_entries.subdirectories(),
+        false => vec![],
+    };
+    // A single document has no sections
+    let group_by = match &options.group_by {
+        Some(group_by) if !options.is_single_document() => group_by,
+        _ => {
+            print_listing(&rs_entries, options);
+            return (totals, subdirectories);
+        }
+    };
+
+    for (index, (header, section)) in get_sections(rs_entries, group_by).iter().enumerate() {
+        if index > 0 {
+            println!();
+        }
+        println!(
+            "{}",
+            options.paint(&[StyleClass::Header], &format!("{}:", header))
+        );
+        print_listing(section, options);
+    }
+    (totals, subdirectories)
+}
+
+// The section an entry goes in with --group-by, sections sorting by the
+// first part of the key and showing the second
+fn get_section_key(entry: &RSEntry, group_by: &GroupBy, today: i64) -> (u8, String) {
+    match group_by {
+        GroupBy::Type => match entry.kind {
+            _ if entry.path.is_symlink() => (2, String::from("Symlinks")),
+            EntryKind::Dir => (0, String::from("Directories")),
+            EntryKind::File => (1, String::from("Files")),
+            _ => (3, String::from("Other")),
+        },
+        GroupBy::Extension => match (entry.kind, entry.path.extension()) {
+            (EntryKind::Dir, _) => (0, String::from("Directories")),
+            (_, Some(ext)) => (1, format!("*.{}", ext.to_string_lossy())),
+            (_, None) => (2, String::from("No extension")),
+        },
+        GroupBy::Date => {
+            let modified = entry.metadata.as_ref().map(|meta| meta.st_mtime());
+            // Days back from today, so anything in the future counts as today
+            match modified.map(|secs| (today - time::local_days(secs)).max(0)) {
+                Some(0) => (0, String::from("Today")),
+                Some(1..=6) => (1, String::from("This week")),
+                Some(7..=30) => (2, String::from("This month")),
+                _ => (3, String::from("Older")),
+            }
+        }
     }
 }
 
-fn get_dir_entries(dir: ReadDir, options: &Options) -> Vec<String> {
-    dir.into_iter()
-        .filter_map(|d| d.ok())
-        .map(|d| d.file_name())
-        .filter_map(|o| o.into_string().ok())
-        .filter(|s| {
-            (options.is_show_all || options.is_show_almost_all) || !s.starts_with(CURRENT_DIR)
+// Splits the entries into labeled sections, each keeping the active order
+fn get_sections(rs_entries: RSEntries, group_by: &GroupBy) -> Vec<(String, RSEntries)> {
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |now| now.as_secs() as i64);
+    let today = time::local_days(now);
+
+    let is_directory_listing = rs_entries.is_directory_listing;
+    let mut sections: Vec<((u8, String), RSEntries)> = vec![];
+    for entry in rs_entries.entries {
+        let key = get_section_key(&entry, group_by, today);
+        match sections
+            .iter_mut()
+            .find(|(section_key, _)| *section_key == key)
+        {
+            Some((_, section)) => section.entries.push(entry),
+            None => sections.push((
+                key,
+                RSEntries {
+                    entries: vec![entry],
+                    block_size: BlockCount(0),
+                    is_directory_listing,
+                },
+            )),
+        }
+    }
+
+    // Stable, so each section keeps its entries' order
+    sections.sort_by(|(a, _), (b, _)| a.cmp(b));
+    sections
+        .into_iter()
+        .map(|((_, header), mut section)| {
+            section.count_blocks();
+            (header, section)
         })
-        .filter(|s| !(options.is_ignore_backups && s.ends_with('~')))
         .collect()
 }
 
-// fn get_tabular_entries(rs_entries: RSEntries, options: &Options) -> Vec<Vec<String>> {
-//     let mut output: Vec<Vec<String>> = vec![];
-//     for entry in rs_entries.entries {
-//         let row = entry.get_table_row(options);
-//         output.push(row);
-//     }
-//     output
-// }
+// Everything stat(1) shows about an entry, in the same layout
+fn get_stat_block(entry: &RSEntry, meta: &Metadata, options: &Options) -> Vec<String> {
+    let mode = Mode(meta.st_mode());
+    let size = ByteSize(meta.len());
+    let timestamp = |secs: i64, nanos: i64| time::full_timestamp(secs, nanos as u32);
+    let birth = meta
+        .created()
+        .ok()
+        .and_then(|time| time.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map_or(String::from("-"), |since_epoch| {
+            time::full_timestamp(since_epoch.as_secs() as i64, since_epoch.subsec_nanos())
+        });
+    vec![
+        format!("  File: {}", entry.name),
+        format!(
+            "  Size: {:<15} Blocks: {:<10} IO Block: {:<6} {}",
+            format!("{} ({})", size, size.human_readable(options.size_precision)),
+            meta.st_blocks(),
+            meta.st_blksize(),
+            entry.kind.description()
+        ),
+        format!(
+            "Device: {:<15} Inode: {:<11} Links: {}",
+            device::device_display(meta.st_dev()),
+            meta.st_ino(),
+            meta.st_nlink()
+        ),
+        format!(
+            "Access: ({}/{})  Uid: ({:>5}/{:>8})   Gid: ({:>5}/{:>8})",
+            mode.octal(),
+            mode.symbolic(),
+            meta.st_uid(),
+            get_owner_display(meta.st_uid(), options),
+            meta.st_gid(),
+            get_group_display(meta.st_gid(), options)
+        ),
+        format!(
+            "Access: {}",
+            timestamp(meta.st_atime(), meta.st_atime_nsec())
+        ),
+        format!(
+            "Modify: {}",
+            timestamp(meta.st_mtime(), meta.st_mtime_nsec())
+        ),
+        format!(
+            "Change: {}",
+            timestamp(meta.st_ctime(), meta.st_ctime_nsec())
+        ),
+        format!(" Birth: {}", birth),
+    ]
+}
 
-fn process_entries(dir: ReadDir, base_path: &Path, options: Options) -> Result<(), String> {
-    let mut dir_entries = get_dir_entries(dir, &options);
+// Prints a line of the listing. Under -N the names' stray bytes are written
+// as they were read.
+fn print_line(line: &str, options: &Options) {
+    match options.is_literal {
+        true => {
+            let mut bytes = format::raw_bytes(line);
+            bytes.push(b'\n');
+            let _ = std::io::stdout().lock().write_all(&bytes);
+        }
+        false => println!("{}", line),
+    }
+}
 
-    if options.is_show_all {
-        dir_entries.push(String::from(CURRENT_DIR));
-        dir_entries.push(String::from(PARENT_DIR));
-    }
-
-    let mut rs_entries = get_entries(dir_entries, base_path);
-
-    let sort_type = match options {
-        Options {
-            is_group_directories_first: true,
-            ..
-        } => RSSort::Directory,
-        Options {
-            is_sort_by_size: true,
-            ..
-        } => RSSort::Size,
-        Options {
-            is_access_time: true,
-            is_long_output: true,
-            is_sort_by_time: true,
-            ..
-        } => RSSort::AccessTime,
-        Options {
-            is_sort_by_time: true,
-            ..
-        } => RSSort::Time,
-        Options {
-            is_sort_by_extension: true,
-            ..
-        } => RSSort::Extension,
-        _ => RSSort::Default,
-    };
+// A stat block per entry, blank lines between them. Entries that couldn't
+// be stat'ed were already reported.
+fn print_stat_blocks(entries: &[RSEntry], options: &Options) {
+    let blocks = entries.iter().filter_map(|entry| {
+        let meta = entry.metadata.as_ref()?;
+        Some(get_stat_block(entry, meta, options).join("\n"))
+    });
+    print_line(&blocks.collect::<Vec<String>>().join("\n\n"), options);
+}
 
-    rs_entries.sort_by(sort_type);
-    if options.is_sort_reverse {
-        rs_entries.reverse();
+fn print_listing(rs_entries: &RSEntries, options: &Options) {
+    // --stat replaces the layout altogether
+    if options.is_stat {
+        print_stat_blocks(&rs_entries.entries, options);
+        return;
     }
 
-    if options.is_one_line || options.is_long_output || options.is_numeric_uid_gid {
-        let table = table(
-            rs_entries.to_tabular(&options),
-            TableAlignment::RightLastLeft,
-        )
-        .unwrap();
-        if !options.is_one_line {
-            println!("total {}", rs_entries.block_size);
+    match options.output_format {
+        OutputFormat::Long | OutputFormat::OneLine => {
+            let mut rows = rs_entries.to_tabular(options);
+            // An empty listing has nothing to name
+            if options.is_header
+                && matches!(options.output_format, OutputFormat::Long)
+                && !rows.is_empty()
+            {
+                rows.insert(0, RSEntry::header_row(options));
+            }
+            // The mode is left aligned, so a marked one sticks out on the right
+            let left_cells = match (&options.compat, &options.output_format) {
+                (Compat::Gnu, OutputFormat::Long) => {
+                    vec![
+                        LongCell::Permissions,
+                        LongCell::Owner,
+                        LongCell::Group,
+                        LongCell::Context,
+                        LongCell::GitStatus,
+                    ]
+                }
+                (Compat::None, OutputFormat::Long) => {
+                    vec![
+                        LongCell::Permissions,
+                        LongCell::Context,
+                        LongCell::GitStatus,
+                    ]
+                }
+                _ => vec![],
+            };
+            let mut left_columns: Vec<usize> = left_cells
+                .into_iter()
+                .filter_map(|cell| RSEntry::long_cell_index(options, cell))
+                .collect();
+            if let (OutputFormat::Long, Some(width)) =
+                (&options.output_format, options.responsive_width)
+            {
+                fit_long_rows(
+                    &mut rows,
+                    &mut left_columns,
+                    width,
+                    &RSEntry::long_cells(options),
+                );
+            }
+            let alignment = match left_columns.is_empty() {
+                true => TableAlignment::RightLastLeft,
+                false => TableAlignment::LeftTextColumns(left_columns),
+            };
+            let table = table(rows, alignment).unwrap();
+            // Like ls, operands listed together aren't a directory to total
+            if matches!(options.output_format, OutputFormat::Long)
+                && rs_entries.is_directory_listing
+            {
+                println!("total {}", blocks_display(rs_entries.block_size, options));
+            }
+            print_line(&table, options);
+        }
+        OutputFormat::CommaSeparated => {
+            // Painted like the names in other layouts, the wrapping measures
+            // them without their escapes
+            let names: Vec<String> = rs_entries
+                .entries
+                .iter()
+                .map(|entry| entry.get_display_name(options))
+                .collect();
+            match options.grid_width {
+                Some(width) => print_line(&format::comma_list(&names, width), options),
+                None => print_line(&names.join(", "), options),
+            }
+        }
+        OutputFormat::Zero => {
+            let mut stdout = std::io::stdout().lock();
+            for entry in &rs_entries.entries {
+                let _ = stdout.write_all(entry.raw_name());
+                let _ = stdout.write_all(b"\0");
+            }
+        }
+        OutputFormat::Describe => {
+            for entry in &rs_entries.entries {
+                print_line(
+                    &describe::sentence(&entry.get_description(options)),
+                    options,
+                );
+            }
+        }
+        OutputFormat::Delimited(delimiter) => {
+            for entry in &rs_entries.entries {
+                let record = get_record(entry, options);
+                println!("{}", records::record(&record, delimiter));
+            }
+        }
+        OutputFormat::Json => {
+            options.json_entries.borrow_mut().extend(
+                rs_entries
+                    .entries
+                    .iter()
+                    .map(|entry| get_json_object(entry, None, None, options)),
+            );
+        }
+        OutputFormat::Columns | OutputFormat::Across | OutputFormat::Default => {
+            let rows = rs_entries.to_tabular(options);
+            let Some(width) = options.grid_width else {
+                print_line(&rows.concat().join(ENTRY_SPACE), options);
+                return;
+            };
+            // An entry's cells, like its inode and name, stay together
+            let cells: Vec<String> = rows.iter().map(|row| row.join(" ")).collect();
+            if !cells.is_empty() {
+                let fill = match options.output_format {
+                    OutputFormat::Across => GridFill::Across,
+                    _ => GridFill::Down,
+                };
+                print_line(&format::grid(&cells, width, ENTRY_SPACE, fill), options);
+            }
         }
-        println!("{}", table);
-    } else if options.is_comma_separated {
-        // TODO: figure out how to handle coloured folders
-        println!("{}", rs_entries.entries.join(", "))
-    } else {
-        println!(
-            "{}",
-            rs_entries.to_tabular(&options).concat().join(ENTRY_SPACE)
-        );
     }
+}
 
-    Ok(())
+// The width to lay out for when -w doesn't give one: the terminal's, then a
+// $COLUMNS that is a positive number, then 80
+fn terminal_width() -> usize {
+    terminal::width()
+        .or_else(|| {
+            env::var(COLUMNS_ENV_NAME)
+                .ok()
+                .and_then(|columns| columns.parse().ok())
+                .filter(|&columns| columns > 0)
+        })
+        .unwrap_or(DEFAULT_GRID_WIDTH)
+}
+
+// Exits for a flag given to a build without the feature it needs
+fn feature_error(flag: &str, feature: &str) -> ! {
+    args::usage_error(
+        APP_NAME,
+        &format!(
+            "'--{}' is not available, rs was compiled without the '{}' feature",
+            flag, feature
+        ),
+    )
 }
 
 fn run() -> Result<(), String> {
-    let app = App::new("rs")
+    let app = App::new(APP_NAME)
         .about("An ls clone in rust")
         .author("Harrison Grieve")
         .version(env!("CARGO_PKG_VERSION"))
@@ -450,11 +2905,200 @@ fn run() -> Result<(), String> {
             Arg::with_name(GROUP_DIRECTORIES_FIRST_ARG_NAME).long(GROUP_DIRECTORIES_FIRST_ARG_NAME),
         )
         .arg(Arg::with_name(IGNORE_BACKUPS_ARG_NAME).short("B"))
+        .arg(
+            Arg::with_name(IGNORE_ARG_NAME)
+                .short("I")
+                .long(IGNORE_ARG_NAME)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(HIDE_ARG_NAME)
+                .long(HIDE_ARG_NAME)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(ONLY_DIRS_ARG_NAME)
+                .short("D")
+                .long(ONLY_DIRS_ARG_NAME),
+        )
+        .arg(Arg::with_name(ONLY_FILES_ARG_NAME).long(ONLY_FILES_ARG_NAME))
         .arg(Arg::with_name(TIME_SORT_ARG_NAME).short("t"))
         .arg(Arg::with_name(SIZE_ARG_NAME).short("s"))
         .arg(Arg::with_name(SIZE_SORT_ARG_NAME).short("S"))
         .arg(Arg::with_name(EXT_SORT_ARG_NAME).short("X"))
+        .arg(Arg::with_name(VERSION_SORT_ARG_NAME).short("v"))
+        .arg(Arg::with_name(UNSORTED_ARG_NAME).short("U"))
+        .arg(Arg::with_name(DIRECTORY_ORDER_ARG_NAME).short("f"))
+        .arg(
+            Arg::with_name(SORT_ARG_NAME)
+                .long(SORT_ARG_NAME)
+                .takes_value(true),
+        )
+        .arg(Arg::with_name(DESCRIBE_ARG_NAME).long(DESCRIBE_ARG_NAME))
+        .arg(Arg::with_name(EMOJI_ARG_NAME).long(EMOJI_ARG_NAME))
+        .arg(Arg::with_name(HEADER_ARG_NAME).long(HEADER_ARG_NAME))
+        .arg(Arg::with_name(NO_PERMISSIONS_ARG_NAME).long(NO_PERMISSIONS_ARG_NAME))
+        .arg(Arg::with_name(NO_USER_ARG_NAME).long(NO_USER_ARG_NAME))
+        .arg(Arg::with_name(NO_TIME_ARG_NAME).long(NO_TIME_ARG_NAME))
+        .arg(Arg::with_name(NO_FILESIZE_ARG_NAME).long(NO_FILESIZE_ARG_NAME))
+        .arg(
+            Arg::with_name(ICONS_ARG_NAME)
+                .long(ICONS_ARG_NAME)
+                .optional_value(true),
+        )
+        .arg(Arg::with_name(FS_INFO_ARG_NAME).long(FS_INFO_ARG_NAME))
+        .arg(Arg::with_name(DEVICE_ARG_NAME).long(DEVICE_ARG_NAME))
+        .arg(Arg::with_name(GLOB_ARG_NAME).long(GLOB_ARG_NAME))
+        .arg(Arg::with_name(GLOB_EMPTY_OK_ARG_NAME).long(GLOB_EMPTY_OK_ARG_NAME))
+        .arg(Arg::with_name(DUPLICATES_ARG_NAME).long(DUPLICATES_ARG_NAME))
+        .arg(Arg::with_name(SHOW_ALL_ARG_NAME).long(SHOW_ALL_ARG_NAME))
+        .arg(Arg::with_name(BROKEN_LINKS_ARG_NAME).long(BROKEN_LINKS_ARG_NAME))
+        .arg(Arg::with_name(TREE_ARG_NAME).long(TREE_ARG_NAME))
+        .arg(
+            Arg::with_name(LEVEL_ARG_NAME)
+                .long(LEVEL_ARG_NAME)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(REGEX_ARG_NAME)
+                .long(REGEX_ARG_NAME)
+                .takes_value(true),
+        )
+        .arg(Arg::with_name(PRUNE_ARG_NAME).long(PRUNE_ARG_NAME))
+        .arg(Arg::with_name(FLAT_ARG_NAME).long(FLAT_ARG_NAME))
+        .arg(Arg::with_name(NO_RESPONSIVE_ARG_NAME).long(NO_RESPONSIVE_ARG_NAME))
+        .arg(Arg::with_name(COUNT_ONLY_ARG_NAME).long(COUNT_ONLY_ARG_NAME))
+        .arg(Arg::with_name(NO_DOT_HIDDEN_ARG_NAME).long(NO_DOT_HIDDEN_ARG_NAME))
+        .arg(Arg::with_name(TAGS_ARG_NAME).long(TAGS_ARG_NAME))
+        .arg(Arg::with_name(GIT_TIME_ARG_NAME).long(GIT_TIME_ARG_NAME))
+        .arg(Arg::with_name(GIT_AUTHOR_ARG_NAME).long(GIT_AUTHOR_ARG_NAME))
+        .arg(Arg::with_name(GIT_ARG_NAME).long(GIT_ARG_NAME))
+        .arg(Arg::with_name(GIT_IGNORE_ARG_NAME).long(GIT_IGNORE_ARG_NAME))
+        .arg(Arg::with_name(JSON_TREE_ARG_NAME).long(JSON_TREE_ARG_NAME))
+        .arg(Arg::with_name(DOT_ARG_NAME).long(DOT_ARG_NAME))
+        .arg(Arg::with_name(STAT_ARG_NAME).long(STAT_ARG_NAME))
+        .arg(Arg::with_name(GRAND_TOTAL_ARG_NAME).long(GRAND_TOTAL_ARG_NAME))
+        .arg(Arg::with_name(APPARENT_SIZE_ARG_NAME).long(APPARENT_SIZE_ARG_NAME))
+        .arg(Arg::with_name(CHECK_CASE_COLLISIONS_ARG_NAME).long(CHECK_CASE_COLLISIONS_ARG_NAME))
+        .arg(
+            Arg::with_name(SAMEFILE_ARG_NAME)
+                .long(SAMEFILE_ARG_NAME)
+                .takes_value(true),
+        )
+        .arg(Arg::with_name(NO_DEREFERENCE_REF_ARG_NAME).long(NO_DEREFERENCE_REF_ARG_NAME))
+        .arg(
+            Arg::with_name(PERM_ARG_NAME)
+                .long(PERM_ARG_NAME)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(HIGHLIGHT_ARG_NAME)
+                .long(HIGHLIGHT_ARG_NAME)
+                .takes_value(true),
+        )
+        .arg(Arg::with_name(HIGHLIGHT_CASE_ARG_NAME).long(HIGHLIGHT_CASE_ARG_NAME))
+        .arg(Arg::with_name(TOTAL_SIZE_ARG_NAME).long(TOTAL_SIZE_ARG_NAME))
+        .arg(Arg::with_name(CACHE_ARG_NAME).long(CACHE_ARG_NAME))
+        .arg(Arg::with_name(NO_CACHE_ARG_NAME).long(NO_CACHE_ARG_NAME))
+        .arg(Arg::with_name(CLEAR_CACHE_ARG_NAME).long(CLEAR_CACHE_ARG_NAME))
+        .arg(
+            Arg::with_name(TIME_STYLE_ARG_NAME)
+                .long(TIME_STYLE_ARG_NAME)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(FIELDS_ARG_NAME)
+                .long(FIELDS_ARG_NAME)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(NORMALIZE_ARG_NAME)
+                .long(NORMALIZE_ARG_NAME)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(CACHE_TRUST_ARG_NAME)
+                .long(CACHE_TRUST_ARG_NAME)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(GROUP_BY_ARG_NAME)
+                .long(GROUP_BY_ARG_NAME)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(DIR_SIZE_ARG_NAME)
+                .long(DIR_SIZE_ARG_NAME)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(COLOR_ARG_NAME)
+                .long(COLOR_ARG_NAME)
+                .optional_value(true),
+        )
+        .arg(
+            Arg::with_name(HYPERLINK_ARG_NAME)
+                .long(HYPERLINK_ARG_NAME)
+                .optional_value(true),
+        )
+        .arg(
+            Arg::with_name(COLOR_OVERRIDE_ARG_NAME)
+                .long(COLOR_OVERRIDE_ARG_NAME)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(SUMMARY_BY_ARG_NAME)
+                .long(SUMMARY_BY_ARG_NAME)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(LARGEST_ARG_NAME)
+                .long(LARGEST_ARG_NAME)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(RECENT_ARG_NAME)
+                .long(RECENT_ARG_NAME)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(SIZE_PRECISION_ARG_NAME)
+                .long(SIZE_PRECISION_ARG_NAME)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(COMPAT_ARG_NAME)
+                .long(COMPAT_ARG_NAME)
+                .takes_value(true),
+        )
         .arg(Arg::with_name(REVERSE_ARG_NAME).short("r"))
+        .arg(Arg::with_name(RECURSIVE_ARG_NAME).short("R"))
+        .arg(Arg::with_name(DIRECTORY_ARG_NAME).short("d"))
+        .arg(Arg::with_name(DEREFERENCE_ARG_NAME).short("L"))
+        .arg(Arg::with_name(CLASSIFY_ARG_NAME).short("F"))
+        .arg(Arg::with_name(INDICATOR_SLASH_ARG_NAME).short("p"))
+        .arg(Arg::with_name(CONTEXT_ARG_NAME).short("Z"))
+        .arg(Arg::with_name(QUOTE_NAME_ARG_NAME).short("Q"))
+        .arg(Arg::with_name(ESCAPE_ARG_NAME).short("b"))
+        .arg(
+            Arg::with_name(HIDE_CONTROL_CHARS_ARG_NAME)
+                .short("q")
+                .long(HIDE_CONTROL_CHARS_ARG_NAME),
+        )
+        .arg(
+            Arg::with_name(LITERAL_ARG_NAME)
+                .short("N")
+                .long(LITERAL_ARG_NAME),
+        )
+        .arg(
+            Arg::with_name(QUOTING_STYLE_ARG_NAME)
+                .long(QUOTING_STYLE_ARG_NAME)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(DEREFERENCE_COMMAND_LINE_ARG_NAME)
+                .long(DEREFERENCE_COMMAND_LINE_ARG_NAME),
+        )
         .arg(Arg::with_name(ACCESS_TIME_ARG_NAME).short("u"))
         .arg(
             Arg::with_name(INODE_ARG_NAME)
@@ -466,48 +3110,703 @@ fn run() -> Result<(), String> {
                 .short("k")
                 .long(KIBIBYTES_ARG_NAME),
         )
-        .arg(Arg::with_name(COMMA_SEPARATED_ARG_NAME).short("m"));
+        .arg(Arg::with_name(COMMA_SEPARATED_ARG_NAME).short("m"))
+        .arg(Arg::with_name(COLUMNS_ARG_NAME).short("C"))
+        .arg(Arg::with_name(ACROSS_ARG_NAME).short("x"))
+        .arg(
+            Arg::with_name(WIDTH_ARG_NAME)
+                .short("w")
+                .long(WIDTH_ARG_NAME)
+                .takes_value(true),
+        )
+        .arg(Arg::with_name(ZERO_ARG_NAME).long(ZERO_ARG_NAME))
+        .arg(Arg::with_name(JSON_ARG_NAME).long(JSON_ARG_NAME))
+        .arg(Arg::with_name(CSV_ARG_NAME).long(CSV_ARG_NAME))
+        .arg(Arg::with_name(TSV_ARG_NAME).long(TSV_ARG_NAME));
 
     let matches = app.get_matches();
 
+    for (flag, feature, is_enabled) in FEATURE_FLAGS {
+        if !is_enabled && matches.is_present(flag) {
+            feature_error(flag, feature);
+        }
+    }
+
+    // --zero leaves no room for another layout's spacing, so those are refused
+    if matches.is_present(ZERO_ARG_NAME) {
+        for layout in [
+            LONG_ARG_NAME,
+            NUMERIC_UID_GID_ARG_NAME,
+            COMMA_SEPARATED_ARG_NAME,
+            DESCRIBE_ARG_NAME,
+            COLUMNS_ARG_NAME,
+            ACROSS_ARG_NAME,
+        ] {
+            if matches.is_present(layout) {
+                args::usage_error(
+                    APP_NAME,
+                    &format!(
+                        "'--{}' and '--{}' can't be used together",
+                        ZERO_ARG_NAME, layout
+                    ),
+                );
+            }
+        }
+    }
+
+    // --json, --csv and --tsv replace the layout altogether, so the other
+    // layouts are refused, and so is more than one of them
+    for format in [JSON_ARG_NAME, CSV_ARG_NAME, TSV_ARG_NAME] {
+        if !matches.is_present(format) {
+            continue;
+        }
+        for layout in [
+            LONG_ARG_NAME,
+            NUMERIC_UID_GID_ARG_NAME,
+            COMMA_SEPARATED_ARG_NAME,
+            DESCRIBE_ARG_NAME,
+            COLUMNS_ARG_NAME,
+            ACROSS_ARG_NAME,
+            ZERO_ARG_NAME,
+            STAT_ARG_NAME,
+            TREE_ARG_NAME,
+            JSON_ARG_NAME,
+            CSV_ARG_NAME,
+            TSV_ARG_NAME,
+        ] {
+            if layout != format && matches.is_present(layout) {
+                args::usage_error(
+                    APP_NAME,
+                    &format!("'--{}' and '--{}' can't be used together", format, layout),
+                );
+            }
+        }
+    }
+
+    // Output formats are mutually exclusive, the last one given wins
+    let output_format = match matches.last_of(&[
+        ONE_LINE_ARG_NAME,
+        LONG_ARG_NAME,
+        NUMERIC_UID_GID_ARG_NAME,
+        COMMA_SEPARATED_ARG_NAME,
+        DESCRIBE_ARG_NAME,
+        COLUMNS_ARG_NAME,
+        ACROSS_ARG_NAME,
+    ]) {
+        // Only -1 can be left with it, and names are one per "line" anyway
+        _ if matches.is_present(ZERO_ARG_NAME) => OutputFormat::Zero,
+        _ if matches.is_present(JSON_ARG_NAME) => OutputFormat::Json,
+        _ if matches.is_present(CSV_ARG_NAME) => OutputFormat::Delimited(records::Delimiter::Comma),
+        _ if matches.is_present(TSV_ARG_NAME) => OutputFormat::Delimited(records::Delimiter::Tab),
+        Some(ONE_LINE_ARG_NAME) => OutputFormat::OneLine,
+        Some(LONG_ARG_NAME) | Some(NUMERIC_UID_GID_ARG_NAME) => OutputFormat::Long,
+        Some(COMMA_SEPARATED_ARG_NAME) => OutputFormat::CommaSeparated,
+        Some(DESCRIBE_ARG_NAME) => OutputFormat::Describe,
+        Some(COLUMNS_ARG_NAME) => OutputFormat::Columns,
+        Some(ACROSS_ARG_NAME) => OutputFormat::Across,
+        // Flattened paths are long, so they go one per line
+        _ if matches.is_present(FLAT_ARG_NAME) => OutputFormat::OneLine,
+        _ => OutputFormat::Default,
+    };
+
+    // Sort flags override each other the same way
+    let sort_key = match matches.last_of(&[
+        TIME_SORT_ARG_NAME,
+        SIZE_SORT_ARG_NAME,
+        EXT_SORT_ARG_NAME,
+        VERSION_SORT_ARG_NAME,
+        UNSORTED_ARG_NAME,
+        DIRECTORY_ORDER_ARG_NAME,
+        SORT_ARG_NAME,
+    ]) {
+        Some(TIME_SORT_ARG_NAME) => RSSort::Time,
+        Some(SIZE_SORT_ARG_NAME) => RSSort::Size,
+        Some(EXT_SORT_ARG_NAME) => RSSort::Extension,
+        Some(VERSION_SORT_ARG_NAME) => RSSort::Version,
+        Some(UNSORTED_ARG_NAME) | Some(DIRECTORY_ORDER_ARG_NAME) => RSSort::None,
+        Some(SORT_ARG_NAME) => match matches.value_of(SORT_ARG_NAME) {
+            Some("time") => RSSort::Time,
+            Some("size") => RSSort::Size,
+            Some("blocks") => RSSort::Blocks,
+            Some("extension") => RSSort::Extension,
+            Some("version") => RSSort::Version,
+            #[cfg(feature = "git")]
+            Some("git-time") => RSSort::GitTime,
+            #[cfg(not(feature = "git"))]
+            Some("git-time") => feature_error(&format!("{}=git-time", SORT_ARG_NAME), "git"),
+            Some("none") => RSSort::None,
+            Some("name") => RSSort::Default,
+            Some(other) => args::usage_error(
+                APP_NAME,
+                &format!("invalid argument '{}' for '--{}'", other, SORT_ARG_NAME),
+            ),
+            None => RSSort::Default,
+        },
+        _ => RSSort::Default,
+    };
+
+    // -u only switches sorting to access time in long output
+    let sort_key = match (sort_key, &output_format) {
+        (RSSort::Time, OutputFormat::Long) if matches.is_present(ACCESS_TIME_ARG_NAME) => {
+            RSSort::AccessTime
+        }
+        (sort_key, _) => sort_key,
+    };
+
+    let compat = match matches.value_of(COMPAT_ARG_NAME) {
+        Some("gnu") => Compat::Gnu,
+        Some(other) => args::usage_error(
+            APP_NAME,
+            &format!("invalid argument '{}' for '--{}'", other, COMPAT_ARG_NAME),
+        ),
+        None => Compat::None,
+    };
+
+    let size_precision = match matches.value_of(SIZE_PRECISION_ARG_NAME) {
+        Some(value) => match value.parse::<usize>() {
+            Ok(precision) if precision <= MAX_SIZE_PRECISION => precision,
+            _ => args::usage_error(
+                APP_NAME,
+                &format!(
+                    "invalid argument '{}' for '--{}', expected 0 to {}",
+                    value, SIZE_PRECISION_ARG_NAME, MAX_SIZE_PRECISION
+                ),
+            ),
+        },
+        None => DEFAULT_SIZE_PRECISION,
+    };
+
+    // Both take a count of entries to keep
+    let parse_count = |name: &str| {
+        matches.value_of(name).map(|value| {
+            value.parse::<usize>().unwrap_or_else(|_| {
+                args::usage_error(
+                    APP_NAME,
+                    &format!("invalid argument '{}' for '--{}'", value, name),
+                )
+            })
+        })
+    };
+    // Emoji or Nerd Font glyphs, whichever was asked for last
+    let icon_flag = matches.last_of(&[EMOJI_ARG_NAME, ICONS_ARG_NAME]);
+    if matches.is_present(ONLY_DIRS_ARG_NAME) && matches.is_present(ONLY_FILES_ARG_NAME) {
+        args::usage_error(
+            APP_NAME,
+            &format!(
+                "'--{}' and '--{}' can't be used together",
+                ONLY_DIRS_ARG_NAME, ONLY_FILES_ARG_NAME
+            ),
+        );
+    }
+    let largest = parse_count(LARGEST_ARG_NAME);
+    let recent = parse_count(RECENT_ARG_NAME);
+    if largest.is_some() && recent.is_some() {
+        args::usage_error(
+            APP_NAME,
+            &format!(
+                "'--{}' and '--{}' can't be used together",
+                LARGEST_ARG_NAME, RECENT_ARG_NAME
+            ),
+        );
+    }
+
+    let perm = matches.value_of(PERM_ARG_NAME).map(|spec| {
+        PermSpec::parse(spec).unwrap_or_else(|spec| {
+            args::usage_error(
+                APP_NAME,
+                &format!("invalid argument '{}' for '--{}'", spec, PERM_ARG_NAME),
+            )
+        })
+    });
+
+    // The reference is stat'ed once, following it if it is a symlink unless asked not to
+    let samefile = matches.value_of(SAMEFILE_ARG_NAME).map(|reference| {
+        let metadata = match matches.is_present(NO_DEREFERENCE_REF_ARG_NAME) {
+            true => fs::symlink_metadata(reference),
+            false => fs::metadata(reference),
+        };
+        match metadata {
+            Ok(meta) => (meta.st_dev(), meta.st_ino()),
+            Err(err) => {
+                eprintln!("rs: cannot access '{}': {}", reference, err);
+                exit(MISSING_REFERENCE_EXIT_CODE);
+            }
+        }
+    });
+
+    let regex = matches.value_of(REGEX_ARG_NAME).map(|pattern| {
+        regex::Regex::new(pattern).unwrap_or_else(|err| {
+            args::usage_error(
+                APP_NAME,
+                &format!(
+                    "invalid regex '{}' for '--{}': {}",
+                    pattern, REGEX_ARG_NAME, err
+                ),
+            )
+        })
+    });
+
+    let group_by = match matches.value_of(GROUP_BY_ARG_NAME) {
+        Some("type") => Some(GroupBy::Type),
+        Some("extension") => Some(GroupBy::Extension),
+        Some("date") => Some(GroupBy::Date),
+        Some(other) => args::usage_error(
+            APP_NAME,
+            &format!("invalid argument '{}' for '--{}'", other, GROUP_BY_ARG_NAME),
+        ),
+        None => None,
+    };
+
+    let dir_size = match matches.value_of(DIR_SIZE_ARG_NAME) {
+        Some("bytes") | None => DirSize::Bytes,
+        Some("dash") => DirSize::Dash,
+        Some("entries") => DirSize::Entries,
+        Some(other) => args::usage_error(
+            APP_NAME,
+            &format!("invalid argument '{}' for '--{}'", other, DIR_SIZE_ARG_NAME),
+        ),
+    };
+
+    let summary_by = match matches.value_of(SUMMARY_BY_ARG_NAME) {
+        Some("extension") => Some(SummaryBy::Extension),
+        Some("owner") => Some(SummaryBy::Owner),
+        Some("group") => Some(SummaryBy::Group),
+        Some(other) => args::usage_error(
+            APP_NAME,
+            &format!(
+                "invalid argument '{}' for '--{}'",
+                other, SUMMARY_BY_ARG_NAME
+            ),
+        ),
+        None => None,
+    };
+
+    let mut palette = Palette::from_colorfgbg(env::var(COLORFGBG_ENV_NAME).ok().as_deref());
+    if let Ok(ls_colors) = env::var(LS_COLORS_ENV_NAME) {
+        palette.apply_ls_colors(&ls_colors);
+    }
+    // Overrides come last so they win over everything else
+    for color_override in matches.values_of(COLOR_OVERRIDE_ARG_NAME) {
+        let (class_name, spec) = color_override.split_once('=').unwrap_or_else(|| {
+            args::usage_error(
+                APP_NAME,
+                &format!(
+                    "invalid argument '{}' for '--{}', expected CLASS=SPEC",
+                    color_override, COLOR_OVERRIDE_ARG_NAME
+                ),
+            )
+        });
+        let class = StyleClass::from_name(class_name).unwrap_or_else(|| {
+            args::usage_error(
+                APP_NAME,
+                &format!(
+                    "unknown style class '{}' for '--{}'",
+                    class_name, COLOR_OVERRIDE_ARG_NAME
+                ),
+            )
+        });
+        let escape = format::parse_style_spec(spec).unwrap_or_else(|token| {
+            args::usage_error(
+                APP_NAME,
+                &format!(
+                    "invalid color '{}' for '--{}'",
+                    token, COLOR_OVERRIDE_ARG_NAME
+                ),
+            )
+        });
+        palette.set(class, escape);
+    }
+
+    let is_git_author = matches.is_present(GIT_AUTHOR_ARG_NAME);
+    let fields = match matches.value_of(FIELDS_ARG_NAME).map(fields::parse_list) {
+        Some(Ok(fields)) => fields,
+        Some(Err(err)) => args::usage_error(APP_NAME, &err),
+        None if matches!(output_format, OutputFormat::Json) => fields::JSON_FIELDS.to_vec(),
+        None if matches!(output_format, OutputFormat::Delimited(_)) => {
+            fields::RECORD_FIELDS.to_vec()
+        }
+        None => fields::DEFAULT_FIELDS.to_vec(),
+    };
+    let long_fields = match (&output_format, matches.is_present(FIELDS_ARG_NAME)) {
+        (OutputFormat::Long, true) => Some(fields.clone()),
+        _ => None,
+    };
+    // With them a column is only read when picked, whatever the flags say
+    let is_picked = |picked: &[Field], is_flag: bool| match &long_fields {
+        Some(fields) => picked.iter().any(|field| fields.contains(field)),
+        None => is_flag,
+    };
+    // The flag wins over TIME_STYLE, which is ignored when it isn't a style
+    // rs knows, as a shell may export it for other tools
+    let time_style = match matches.value_of(TIME_STYLE_ARG_NAME) {
+        Some(name) => TimeStyle::parse(name).unwrap_or_else(|| {
+            args::usage_error(
+                APP_NAME,
+                &format!(
+                    "invalid argument '{}' for '--{}'",
+                    name, TIME_STYLE_ARG_NAME
+                ),
+            )
+        }),
+        None => env::var(TIME_STYLE_ENV_NAME)
+            .ok()
+            .and_then(|name| TimeStyle::parse(&name))
+            .unwrap_or(TimeStyle::Default),
+    };
+    let normalization = match matches.value_of(NORMALIZE_ARG_NAME) {
+        Some("nfc") => Some(Normalization::Nfc),
+        Some("nfd") => Some(Normalization::Nfd),
+        Some("none") | None => None,
+        Some(other) => args::usage_error(
+            APP_NAME,
+            &format!(
+                "invalid argument '{}' for '--{}'",
+                other, NORMALIZE_ARG_NAME
+            ),
+        ),
+    };
+    let trust = match matches.value_of(CACHE_TRUST_ARG_NAME) {
+        Some("mtime") => total_size::CacheTrust::Mtime,
+        Some("shallow") | None => total_size::CacheTrust::Shallow,
+        Some(other) => args::usage_error(
+            APP_NAME,
+            &format!(
+                "invalid argument '{}' for '--{}'",
+                other, CACHE_TRUST_ARG_NAME
+            ),
+        ),
+    };
+    // --no-cache wins, so it can undo a --cache kept in an alias
+    let cache_trust = match (
+        matches.is_present(CACHE_ARG_NAME),
+        matches.is_present(NO_CACHE_ARG_NAME),
+    ) {
+        (true, false) => Some(trust),
+        _ => None,
+    };
+
+    // Looked up once, so every section of the listing agrees on it
+    let width = terminal_width();
+    let is_terminal = std::io::stdout().is_terminal();
+    // --color, --hyperlink and --icons take always, never or auto, and are
+    // always on their own. None leaves it to auto.
+    let parse_when = |name: &str| match matches.value_of(name) {
+        _ if !matches.is_present(name) => None,
+        None | Some("always" | "yes" | "force") => Some(true),
+        Some("never" | "no" | "none") => Some(false),
+        Some("auto" | "tty" | "if-tty") => None,
+        Some(other) => args::usage_error(
+            APP_NAME,
+            &format!("invalid argument '{}' for '--{}'", other, name),
+        ),
+    };
+    // -f is the fast path for huge directories and, as in GNU ls, is never
+    // colored, whatever --color says. Otherwise, left to itself, only the
+    // default style on a terminal that isn't dumb is colored.
+    let is_directory_order = matches.is_present(DIRECTORY_ORDER_ARG_NAME);
+    let is_colored = !is_directory_order
+        && parse_when(COLOR_ARG_NAME).unwrap_or_else(|| {
+            matches!(compat, Compat::None)
+                && is_terminal
+                && env::var(TERM_ENV_NAME).map_or(true, |term| term != "dumb")
+        });
+    // -w wins over the terminal. -C, -x and -m still need a width when piped.
+    let grid_width = match (parse_count(WIDTH_ARG_NAME), &output_format) {
+        // No limit, so everything goes on one line
+        (Some(0), _) => Some(usize::MAX),
+        (Some(width), _) => Some(width),
+        (None, OutputFormat::Columns | OutputFormat::Across | OutputFormat::CommaSeparated) => {
+            Some(width)
+        }
+        (None, _) => is_terminal.then_some(width),
+    };
+
+    // -N writes names as they're stored, whatever else is given. Otherwise
+    // the last of -Q, -b and --quoting-style wins over QUOTING_STYLE, which is
+    // ignored when it isn't a style. Like GNU ls, names on a terminal are
+    // quoted to paste into a shell by default.
+    let is_literal = matches.is_present(LITERAL_ARG_NAME);
+    let quoting_style =
+        match matches.last_of(&[QUOTE_NAME_ARG_NAME, ESCAPE_ARG_NAME, QUOTING_STYLE_ARG_NAME]) {
+            _ if is_literal => QuotingStyle::Literal,
+            Some(QUOTE_NAME_ARG_NAME) => QuotingStyle::C,
+            Some(ESCAPE_ARG_NAME) => QuotingStyle::Escape,
+            Some(_) => {
+                let name = matches.value_of(QUOTING_STYLE_ARG_NAME).unwrap_or_default();
+                QuotingStyle::parse(name).unwrap_or_else(|| {
+                    args::usage_error(
+                        APP_NAME,
+                        &format!(
+                            "invalid argument '{}' for '--{}'",
+                            name, QUOTING_STYLE_ARG_NAME
+                        ),
+                    )
+                })
+            }
+            None => env::var(QUOTING_STYLE_ENV_NAME)
+                .ok()
+                .and_then(|name| QuotingStyle::parse(&name))
+                .unwrap_or(match is_terminal {
+                    true => QuotingStyle::ShellEscape,
+                    false => QuotingStyle::Literal,
+                }),
+        };
+
     let options = Options {
-        is_show_all: matches.is_present(ALL_ARG_NAME),
+        // -f shows everything, like -a
+        is_show_all: matches.is_present(ALL_ARG_NAME) || is_directory_order,
         is_show_almost_all: matches.is_present(ALMOST_ALL_ARG_NAME),
-        is_one_line: matches.is_present(ONE_LINE_ARG_NAME),
-        is_long_output: matches.is_present(LONG_ARG_NAME),
+        output_format,
         is_numeric_uid_gid: matches.is_present(NUMERIC_UID_GID_ARG_NAME),
         is_human_readable: matches.is_present(HUMAN_READABLE_ARG_NAME),
+        size_precision,
         is_group_directories_first: matches.is_present(GROUP_DIRECTORIES_FIRST_ARG_NAME),
         is_ignore_backups: matches.is_present(IGNORE_BACKUPS_ARG_NAME),
-        is_sort_by_time: matches.is_present(TIME_SORT_ARG_NAME),
-        is_sort_by_size: matches.is_present(SIZE_SORT_ARG_NAME),
-        is_sort_by_extension: matches.is_present(EXT_SORT_ARG_NAME),
+        ignore_patterns: matches
+            .values_of(IGNORE_ARG_NAME)
+            .into_iter()
+            .map(|pattern| normalize::normalize(pattern, normalization).into_owned())
+            .collect(),
+        hide_patterns: matches
+            .values_of(HIDE_ARG_NAME)
+            .into_iter()
+            .map(|pattern| normalize::normalize(pattern, normalization).into_owned())
+            .collect(),
+        is_only_dirs: matches.is_present(ONLY_DIRS_ARG_NAME),
+        is_only_files: matches.is_present(ONLY_FILES_ARG_NAME),
+        sort_key,
         is_sort_reverse: matches.is_present(REVERSE_ARG_NAME),
-        is_show_size_blocks: matches.is_present(SIZE_ARG_NAME),
+        is_show_size_blocks: is_picked(&[Field::Blocks], matches.is_present(SIZE_ARG_NAME)),
         is_access_time: matches.is_present(ACCESS_TIME_ARG_NAME),
-        is_show_inode: matches.is_present(INODE_ARG_NAME),
+        is_show_inode: is_picked(&[Field::Inode], matches.is_present(INODE_ARG_NAME)),
         is_kibibytes: matches.is_present(KIBIBYTES_ARG_NAME),
-        is_comma_separated: matches.is_present(COMMA_SEPARATED_ARG_NAME),
+        compat,
+        is_emoji: icon_flag == Some(EMOJI_ARG_NAME),
+        is_header: matches.is_present(HEADER_ARG_NAME),
+        is_hide_permissions: !is_picked(
+            &[Field::Mode, Field::Permissions],
+            !matches.is_present(NO_PERMISSIONS_ARG_NAME),
+        ),
+        is_hide_user: !is_picked(
+            &[Field::User, Field::Group],
+            !matches.is_present(NO_USER_ARG_NAME),
+        ),
+        is_hide_time: !is_picked(&[Field::Mtime], !matches.is_present(NO_TIME_ARG_NAME)),
+        is_hide_filesize: !is_picked(&[Field::Size], !matches.is_present(NO_FILESIZE_ARG_NAME)),
+        // Like --hyperlink, off unless asked for
+        is_nerd_font_icons: icon_flag == Some(ICONS_ARG_NAME)
+            && parse_when(ICONS_ARG_NAME).unwrap_or(is_terminal),
+        is_fs_info: matches.is_present(FS_INFO_ARG_NAME),
+        is_show_device: is_picked(&[], matches.is_present(DEVICE_ARG_NAME)),
+        is_duplicates: matches.is_present(DUPLICATES_ARG_NAME),
+        is_show_unique: matches.is_present(SHOW_ALL_ARG_NAME),
+        is_broken_links: matches.is_present(BROKEN_LINKS_ARG_NAME),
+        largest,
+        recent,
+        is_tree: matches.is_present(TREE_ARG_NAME),
+        tree_level: parse_count(LEVEL_ARG_NAME).filter(|level| *level > 0),
+        regex,
+        perm,
+        samefile,
+        is_prune: matches.is_present(PRUNE_ARG_NAME),
+        is_flat: matches.is_present(FLAT_ARG_NAME),
+        is_recursive: matches.is_present(RECURSIVE_ARG_NAME),
+        is_directory: matches.is_present(DIRECTORY_ARG_NAME),
+        is_dereference: matches.is_present(DEREFERENCE_ARG_NAME),
+        is_dereference_command_line: matches.is_present(DEREFERENCE_COMMAND_LINE_ARG_NAME),
+        is_classify: matches.is_present(CLASSIFY_ARG_NAME),
+        is_indicator_slash: matches.is_present(INDICATOR_SLASH_ARG_NAME),
+        is_context: is_picked(&[], matches.is_present(CONTEXT_ARG_NAME)),
+        quoting_style,
+        is_hide_control_chars: !is_literal
+            && (matches.is_present(HIDE_CONTROL_CHARS_ARG_NAME) || is_terminal),
+        is_literal,
+        time_style,
+        summary_by,
+        dir_size,
+        group_by,
+        is_count_only: matches.is_present(COUNT_ONLY_ARG_NAME),
+        is_no_dot_hidden: matches.is_present(NO_DOT_HIDDEN_ARG_NAME),
+        is_show_tags: is_picked(&[], matches.is_present(TAGS_ARG_NAME)),
+        // --git-author adds the author beside the commit date
+        is_git_time: is_picked(
+            &[Field::GitTime, Field::GitAuthor],
+            matches.is_present(GIT_TIME_ARG_NAME) || is_git_author,
+        ),
+        is_git_author: is_picked(&[Field::GitAuthor], is_git_author),
+        is_git_status: is_picked(&[], matches.is_present(GIT_ARG_NAME)),
+        is_git_status_shown: Cell::new(false),
+        is_git_ignore: matches.is_present(GIT_IGNORE_ARG_NAME),
+        is_json_tree: matches.is_present(JSON_TREE_ARG_NAME),
+        fields,
+        long_fields,
+        is_dot: matches.is_present(DOT_ARG_NAME),
+        is_stat: matches.is_present(STAT_ARG_NAME),
+        is_grand_total: matches.is_present(GRAND_TOTAL_ARG_NAME),
+        is_apparent_size: matches.is_present(APPARENT_SIZE_ARG_NAME),
+        is_check_case_collisions: matches.is_present(CHECK_CASE_COLLISIONS_ARG_NAME),
+        highlights: matches
+            .values_of(HIGHLIGHT_ARG_NAME)
+            .into_iter()
+            .map(String::from)
+            .collect(),
+        is_highlight_case: matches.is_present(HIGHLIGHT_CASE_ARG_NAME),
+        is_total_size: matches.is_present(TOTAL_SIZE_ARG_NAME),
+        cache_trust,
+        normalization,
+        palette,
+        is_colored,
+        // Off unless asked for, like ls
+        hyperlink_base: (matches.is_present(HYPERLINK_ARG_NAME)
+            && parse_when(HYPERLINK_ARG_NAME).unwrap_or(is_terminal))
+        .then(HyperlinkBase::new),
+        responsive_width: match matches.is_present(NO_RESPONSIVE_ARG_NAME) {
+            true => None,
+            false => is_terminal.then_some(width),
+        },
+        grid_width,
+        json_entries: RefCell::new(vec![]),
+        summary_items: RefCell::new(vec![]),
     };
 
-    let base_path = match matches.value_of(PATH_ARG_NAME) {
-        Some(path) => Path::new(path),
-        None => Path::new(DEFAULT_PATH),
-    };
+    let mut operands: Vec<PathBuf> = vec![];
+    for operand in matches.operands() {
+        // The matcher works on text, so a pattern that isn't UTF-8 is taken literally
+        let pattern = match operand.to_str() {
+            Some(pattern) if matches.is_present(GLOB_ARG_NAME) => pattern,
+            _ => {
+                operands.push(PathBuf::from(operand));
+                continue;
+            }
+        };
 
-    if let Ok(metadata) = fs::metadata(base_path) {
-        if metadata.is_file() {
-            println!("{}", base_path.display());
-            exit(0);
+        let expanded = glob::expand(pattern);
+        if expanded.is_empty() && !matches.is_present(GLOB_EMPTY_OK_ARG_NAME) {
+            eprintln!("rs: {}: no matches", pattern);
+            exit(NO_MATCHES_EXIT_CODE);
         }
+        operands.extend(expanded);
     }
 
-    match fs::read_dir(base_path) {
-        Ok(read_dir) => process_entries(read_dir, base_path, options),
-        Err(err) => {
-            Err(format!("rs: cannot access '{}': {}", base_path.display(), err).to_string())
+    if matches.operands().is_empty() {
+        operands.push(PathBuf::from(DEFAULT_PATH));
+    }
+
+    // Unlike ls, an operand given more than once is only listed once
+    let mut seen_operands = HashSet::new();
+    operands.retain(|operand| seen_operands.insert(operand.clone()));
+
+    if matches.is_present(CLEAR_CACHE_ARG_NAME) {
+        return total_size::clear_cache();
+    }
+    if options.is_count_only {
+        return print_counts(&operands, &options);
+    }
+    if options.is_json_tree {
+        return print_json_tree(&operands, &options);
+    }
+    if options.is_dot {
+        return print_dot(&operands, &options);
+    }
+
+    // A tree listed as one set has no subdirectory sections to tell apart
+    let is_show_headers = (operands.len() > 1
+        || (options.is_recursive && !options.is_listing_flat()))
+        && !options.is_single_document();
+    let mut result = Ok(());
+    // Added up here, from what each listing reports, so it agrees with their totals
+    let mut grand_totals = ListingTotals::default();
+    // Like ls, missing operands are reported first, then the rest that
+    // aren't directories are listed together, then each directory
+    let mut operand_entries = vec![];
+    let mut dirs = vec![];
+    for operand in &operands {
+        // A dangling link is still an entry to list
+        let metadata = match options.is_following_operand_links() {
+            true => fs::metadata(operand).or_else(|_| fs::symlink_metadata(operand)),
+            false => fs::symlink_metadata(operand),
+        };
+        match metadata {
+            Ok(meta) if meta.is_dir() && !options.is_directory => dirs.push(operand),
+            Ok(_) => operand_entries.push(operand.clone().into_os_string()),
+            Err(err) => {
+                eprintln!("rs: cannot access '{}': {}", operand.display(), err);
+                grand_totals.errors += 1;
+            }
+        }
+    }
+
+    if let OutputFormat::Delimited(delimiter) = options.output_format {
+        let header: Vec<&str> = options.fields.iter().map(|field| field.name()).collect();
+        println!("{}", records::record(&header, delimiter));
+    }
+    let has_operand_entries = !operand_entries.is_empty();
+    if has_operand_entries {
+        grand_totals += print_operand_entries(operand_entries, &options);
+    }
+
+    for (index, base_path) in dirs.into_iter().enumerate() {
+        if is_show_headers {
+            if index > 0 || has_operand_entries {
+                println!();
+            }
+            println!("{}:", base_path.display());
+        }
+
+        let listed = match fs::read_dir(base_path) {
+            Ok(read_dir) if options.is_tree => {
+                print_tree(read_dir, base_path, &options);
+                Ok(ListingTotals::default())
+            }
+            Ok(read_dir) if options.is_listing_flat() => {
+                process_flat_entries(read_dir, base_path, &options)
+            }
+            Ok(read_dir) => process_entries(read_dir, base_path, &options),
+            Err(err) => Err(format!(
+                "rs: cannot open directory '{}': {}",
+                base_path.display(),
+                err
+            )),
+        };
+        match listed {
+            Ok(totals) => grand_totals += totals,
+            Err(err) => {
+                eprintln!("{}", err);
+                grand_totals.errors += 1;
+            }
         }
     }
+
+    if matches!(options.output_format, OutputFormat::Json) {
+        println!(
+            "{}",
+            json::Value::Array(options.json_entries.take()).render()
+        );
+    }
+    if let Some(summary_by) = &options.summary_by {
+        print_summary(summary_by, &options);
+    }
+    if options.is_grand_total {
+        print_grand_total(&grand_totals, &options);
+    }
+    if options.is_fs_info {
+        print_fs_infos(&operands, &options);
+    }
+    if grand_totals.errors > 0 {
+        result = result.and(Err(format!(
+            "rs: {} path(s) could not be read",
+            grand_totals.errors
+        )));
+    }
+    // Failing lets scripts and CI stop on collisions
+    if grand_totals.case_collisions > 0 {
+        result = result.and(Err(format!(
+            "rs: {} group(s) of names differ only in case",
+            grand_totals.case_collisions
+        )));
+    }
+
+    result.and(total_size::save_cache())
 }
 
 fn main() {
@@ -519,3 +3818,233 @@ fn main() {
         }
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{File, FileTimes};
+    use std::time::Duration;
+
+    // A scratch directory for one test, emptied first
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("rs-unit-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    // An entry as get_entries makes it, without metadata when the path
+    // can't be stat'ed
+    fn entry(path: PathBuf) -> RSEntry {
+        let metadata = fs::symlink_metadata(&path).ok();
+        RSEntry {
+            name: path.file_name().unwrap().to_string_lossy().into_owned(),
+            raw_name: path.file_name().unwrap().to_os_string(),
+            kind: metadata
+                .as_ref()
+                .map_or(EntryKind::Unknown, |meta| Mode(meta.st_mode()).kind()),
+            path,
+            metadata,
+            duplicate_group: None,
+            is_case_collision: false,
+            #[cfg(feature = "git")]
+            last_commit: None,
+            #[cfg(feature = "git")]
+            git_status: None,
+            total_size: None,
+        }
+    }
+
+    fn names(entries: &[RSEntry]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.name.as_str()).collect()
+    }
+
+    #[test]
+    fn entries_without_metadata_sort_last_by_name() {
+        let dir = scratch_dir("metadata-order");
+        for (name, secs) in [("a", 3), ("c", 1), ("e", 2)] {
+            let time = SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+            File::create(dir.join(name))
+                .unwrap()
+                .set_times(FileTimes::new().set_modified(time))
+                .unwrap();
+        }
+        // d and b were never created, so they can't be stat'ed
+        let mut entries: Vec<RSEntry> = ["d", "c", "b", "a", "e"]
+            .into_iter()
+            .map(|name| entry(dir.join(name)))
+            .collect();
+
+        entries.sort_by(|a, b| cmp_by_metadata(a, b, |meta| Reverse(meta.st_mtime())));
+        assert_eq!(names(&entries), ["a", "e", "c", "b", "d"]);
+
+        entries.sort_by(|a, b| cmp_by_metadata(a, b, |meta| Reverse(meta.len())));
+        assert_eq!(names(&entries), ["a", "c", "e", "b", "d"]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    const LONG_CELLS: [LongCell; 8] = [
+        LongCell::Permissions,
+        LongCell::Links,
+        LongCell::Owner,
+        LongCell::Group,
+        LongCell::Size,
+        LongCell::Time,
+        LongCell::Continued,
+        LongCell::Name,
+    ];
+
+    // 86 columns wide, the name taking 40 of them
+    fn long_rows() -> Vec<Vec<String>> {
+        let row = [
+            "-rw-r--r--",
+            "1",
+            "harrison",
+            "staff",
+            "1234",
+            "Oct 15",
+            "12:00",
+            "a-fairly-long-file-name-for-a-test.txt",
+        ];
+        vec![row.map(String::from).to_vec()]
+    }
+
+    // The rows fitted to the width, and the columns left aligned after
+    fn fit(
+        mut rows: Vec<Vec<String>>,
+        cells: &[LongCell],
+        width: usize,
+    ) -> (Vec<Vec<String>>, Vec<usize>) {
+        let mut left_columns = vec![0];
+        fit_long_rows(&mut rows, &mut left_columns, width, cells);
+        (rows, left_columns)
+    }
+
+    #[test]
+    fn wide_terminals_keep_every_column() {
+        let (rows, _) = fit(long_rows(), &LONG_CELLS, 200);
+        assert_eq!(rows, long_rows());
+    }
+
+    #[test]
+    fn group_goes_first() {
+        let (rows, left_columns) = fit(long_rows(), &LONG_CELLS, 80);
+        assert_eq!(
+            rows[0],
+            [
+                "-rw-r--r--",
+                "1",
+                "harrison",
+                "1234",
+                "Oct 15",
+                "12:00",
+                "a-fairly-long-file-name-for-a-test.txt",
+            ]
+        );
+        assert_eq!(left_columns, [0]);
+    }
+
+    #[test]
+    fn names_are_cut_once_nothing_else_can_go() {
+        let (rows, _) = fit(long_rows(), &LONG_CELLS, 60);
+        assert_eq!(rows[0][..4], ["-rw-r--r--", "1234", "Oct 15", "12:00"]);
+        // What is left of the 60 columns after the others and their separators
+        assert_eq!(
+            rows[0][4],
+            format::truncate_with_ellipsis(&long_rows()[0][7], 31)
+        );
+        assert_eq!(rows[0].join(" ").chars().count(), 60);
+    }
+
+    #[test]
+    fn context_goes_before_group() {
+        let mut cells = LONG_CELLS.to_vec();
+        cells.insert(4, LongCell::Context);
+        let mut rows = long_rows();
+        rows[0].insert(4, String::from("unconfined_u:object_r:user_home_t:s0"));
+        let (rows, _) = fit(rows, &cells, 90);
+        assert_eq!(rows, long_rows());
+    }
+
+    #[test]
+    fn short_rows_are_left_alone() {
+        let mut rows = long_rows();
+        rows.push(vec![]);
+        let (rows, _) = fit(rows, &LONG_CELLS, 60);
+        assert_eq!(rows[0].len(), 5);
+        assert!(rows[1].is_empty());
+    }
+
+    fn section_headers(sections: &[(String, RSEntries)]) -> Vec<&str> {
+        sections.iter().map(|(header, _)| header.as_str()).collect()
+    }
+
+    #[test]
+    fn sections_by_type_and_extension() {
+        let dir = scratch_dir("sections");
+        fs::create_dir(dir.join("src")).unwrap();
+        for name in ["b.rs", "a.md", "Makefile", "c.rs"] {
+            File::create(dir.join(name)).unwrap();
+        }
+        std::os::unix::fs::symlink("b.rs", dir.join("link.rs")).unwrap();
+        let rs_entries = || RSEntries {
+            entries: ["b.rs", "src", "link.rs", "a.md", "Makefile", "c.rs"]
+                .into_iter()
+                .map(|name| entry(dir.join(name)))
+                .collect(),
+            block_size: BlockCount(0),
+            is_directory_listing: true,
+        };
+
+        let sections = get_sections(rs_entries(), &GroupBy::Type);
+        assert_eq!(
+            section_headers(&sections),
+            ["Directories", "Files", "Symlinks"]
+        );
+        // Each section keeps the order the entries came in
+        assert_eq!(
+            names(&sections[1].1.entries),
+            ["b.rs", "a.md", "Makefile", "c.rs"]
+        );
+
+        let sections = get_sections(rs_entries(), &GroupBy::Extension);
+        assert_eq!(
+            section_headers(&sections),
+            ["Directories", "*.md", "*.rs", "No extension"]
+        );
+        assert_eq!(names(&sections[2].1.entries), ["b.rs", "link.rs", "c.rs"]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn date_sections_count_days_back_from_today() {
+        let dir = scratch_dir("date-sections");
+        let path = dir.join("file");
+        let secs = 1_700_000_000;
+        File::create(&path)
+            .unwrap()
+            .set_times(
+                FileTimes::new().set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(secs)),
+            )
+            .unwrap();
+        let file = entry(path);
+        let day = time::local_days(secs as i64);
+
+        for (days_later, header) in [
+            (-3, "Today"),
+            (0, "Today"),
+            (1, "This week"),
+            (6, "This week"),
+            (7, "This month"),
+            (30, "This month"),
+            (31, "Older"),
+        ] {
+            let (_, key) = get_section_key(&file, &GroupBy::Date, day + days_later);
+            assert_eq!(key, header, "{} days later", days_later);
+        }
+        // Without metadata there's no date to go by
+        let missing = entry(dir.join("missing"));
+        assert_eq!(get_section_key(&missing, &GroupBy::Date, day).1, "Older");
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,460 @@
+// File type bits of st_mode, see inode(7)
+const TYPE_MASK: u32 = 0o170000;
+const TYPE_SOCKET: u32 = 0o140000;
+const TYPE_SYMLINK: u32 = 0o120000;
+const TYPE_REGULAR: u32 = 0o100000;
+const TYPE_BLOCK_DEVICE: u32 = 0o060000;
+const TYPE_DIRECTORY: u32 = 0o040000;
+const TYPE_CHAR_DEVICE: u32 = 0o020000;
+const TYPE_FIFO: u32 = 0o010000;
+
+// Special permission bits
+const SETUID: u32 = 0o4000;
+const SETGID: u32 = 0o2000;
+const STICKY: u32 = 0o1000;
+
+// Any of the user, group or other execute bits
+const EXECUTE_BITS: u32 = 0o111;
+
+// The permission and special bits, everything but the file type
+const PERMISSION_MASK: u32 = 0o7777;
+
+// Shifts of the user, group and other rwx triples
+const USER_SHIFT: u32 = 6;
+const GROUP_SHIFT: u32 = 3;
+const OTHER_SHIFT: u32 = 0;
+
+// What an entry is, read once from its file type bits
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    File,
+    Dir,
+    Symlink,
+    Fifo,
+    Socket,
+    CharDevice,
+    BlockDevice,
+    Unknown,
+}
+
+impl EntryKind {
+    // The leading character of a symbolic mode, as ls prints it
+    pub fn type_char(self) -> char {
+        match self {
+            EntryKind::File => '-',
+            EntryKind::Dir => 'd',
+            EntryKind::Symlink => 'l',
+            EntryKind::Fifo => 'p',
+            EntryKind::Socket => 's',
+            EntryKind::CharDevice => 'c',
+            EntryKind::BlockDevice => 'b',
+            EntryKind::Unknown => '?',
+        }
+    }
+
+    // What stat(1) calls the kind
+    pub fn description(self) -> &'static str {
+        match self {
+            EntryKind::File => "regular file",
+            EntryKind::Dir => "directory",
+            EntryKind::Symlink => "symbolic link",
+            EntryKind::Fifo => "fifo",
+            EntryKind::Socket => "socket",
+            EntryKind::CharDevice => "character special file",
+            EntryKind::BlockDevice => "block special file",
+            EntryKind::Unknown => "unknown",
+        }
+    }
+
+    // What the kind is called in structured output
+    pub fn name(self) -> &'static str {
+        match self {
+            EntryKind::File => "file",
+            EntryKind::Dir => "dir",
+            EntryKind::Symlink => "symlink",
+            EntryKind::Fifo => "fifo",
+            EntryKind::Socket => "socket",
+            EntryKind::CharDevice => "char-device",
+            EntryKind::BlockDevice => "block-device",
+            EntryKind::Unknown => "unknown",
+        }
+    }
+}
+
+// Read, write and execute permission for one class of user
+pub struct Permissions {
+    pub read: bool,
+    pub write: bool,
+    pub execute: bool,
+}
+
+// A full st_mode: file type, special bits and permissions
+#[derive(Clone, Copy)]
+pub struct Mode(pub u32);
+
+impl Mode {
+    pub fn kind(self) -> EntryKind {
+        match self.0 & TYPE_MASK {
+            TYPE_REGULAR => EntryKind::File,
+            TYPE_DIRECTORY => EntryKind::Dir,
+            TYPE_SYMLINK => EntryKind::Symlink,
+            TYPE_BLOCK_DEVICE => EntryKind::BlockDevice,
+            TYPE_CHAR_DEVICE => EntryKind::CharDevice,
+            TYPE_FIFO => EntryKind::Fifo,
+            TYPE_SOCKET => EntryKind::Socket,
+            _ => EntryKind::Unknown,
+        }
+    }
+
+    pub fn is_setuid(self) -> bool {
+        self.0 & SETUID != 0
+    }
+
+    pub fn is_setgid(self) -> bool {
+        self.0 & SETGID != 0
+    }
+
+    pub fn is_sticky(self) -> bool {
+        self.0 & STICKY != 0
+    }
+
+    // Executable by anyone at all
+    pub fn is_executable(self) -> bool {
+        self.0 & EXECUTE_BITS != 0
+    }
+
+    fn permissions(self, shift: u32) -> Permissions {
+        let bits = (self.0 >> shift) & 0o7;
+        Permissions {
+            read: bits & 0o4 != 0,
+            write: bits & 0o2 != 0,
+            execute: bits & 0o1 != 0,
+        }
+    }
+
+    pub fn user(self) -> Permissions {
+        self.permissions(USER_SHIFT)
+    }
+
+    pub fn group(self) -> Permissions {
+        self.permissions(GROUP_SHIFT)
+    }
+
+    pub fn other(self) -> Permissions {
+        self.permissions(OTHER_SHIFT)
+    }
+
+    // What -F appends to the name, e.g. "/" for a directory, as ls does
+    pub fn indicator(self) -> &'static str {
+        match self.kind() {
+            EntryKind::Dir => "/",
+            EntryKind::Symlink => "@",
+            EntryKind::Fifo => "|",
+            EntryKind::Socket => "=",
+            EntryKind::File if self.is_executable() => "*",
+            _ => "",
+        }
+    }
+
+    // e.g. "0644", the permission and special bits
+    pub fn octal(self) -> String {
+        format!("{:04o}", self.0 & PERMISSION_MASK)
+    }
+
+    // e.g. "drwxr-sr-t", the type and nine permission characters, where a
+    // special bit shows in the execute slot as lowercase if execute is also
+    // set and uppercase if it isn't
+    pub fn symbolic(self) -> String {
+        let mut symbolic = String::from(self.kind().type_char());
+        let triples = [
+            (self.user(), self.is_setuid(), 's'),
+            (self.group(), self.is_setgid(), 's'),
+            (self.other(), self.is_sticky(), 't'),
+        ];
+        for (permissions, is_special, special_char) in triples {
+            symbolic.push(if permissions.read { 'r' } else { '-' });
+            symbolic.push(if permissions.write { 'w' } else { '-' });
+            symbolic.push(match (permissions.execute, is_special) {
+                (true, true) => special_char,
+                (false, true) => special_char.to_ascii_uppercase(),
+                (true, false) => 'x',
+                (false, false) => '-',
+            });
+        }
+        symbolic
+    }
+}
+
+// A find(1) style -perm test, made against the permission bits only
+pub enum PermSpec {
+    // MODE, exactly these bits
+    Exact(u32),
+    // -MODE, at least all of these bits
+    All(u32),
+    // /MODE, any of these bits
+    Any(u32),
+}
+
+impl PermSpec {
+    pub fn parse(spec: &str) -> Result<PermSpec, String> {
+        let (variant, octal): (fn(u32) -> PermSpec, &str) = match spec.chars().next() {
+            Some('-') => (PermSpec::All, &spec[1..]),
+            Some('/') => (PermSpec::Any, &spec[1..]),
+            _ => (PermSpec::Exact, spec),
+        };
+        if octal.is_empty() || !octal.chars().all(|ch| ch.is_digit(8)) {
+            return Err(spec.to_string());
+        }
+        match u32::from_str_radix(octal, 8) {
+            Ok(bits) if bits <= PERMISSION_MASK => Ok(variant(bits)),
+            _ => Err(spec.to_string()),
+        }
+    }
+
+    pub fn matches(&self, mode: Mode) -> bool {
+        let bits = mode.0 & PERMISSION_MASK;
+        match *self {
+            PermSpec::Exact(spec) => bits == spec,
+            PermSpec::All(spec) => bits & spec == spec,
+            // As in find, /000 matches everything
+            PermSpec::Any(spec) => spec == 0 || bits & spec != 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        env, fs,
+        os::unix::{fs::symlink, net::UnixListener},
+        path::Path,
+        process::{self, Command},
+    };
+
+    #[cfg(target_os = "linux")]
+    use std::os::linux::fs::MetadataExt;
+    #[cfg(target_os = "macos")]
+    use std::os::macos::fs::MetadataExt;
+    #[cfg(all(unix, not(any(target_os = "linux", target_os = "macos"))))]
+    use std::os::unix::fs::MetadataExt;
+
+    fn lstat_kind(path: &Path) -> EntryKind {
+        Mode(fs::symlink_metadata(path).unwrap().st_mode()).kind()
+    }
+
+    #[test]
+    fn symbolic_modes() {
+        for (mode, symbolic) in [
+            (0, "?---------"),
+            (0o7777, "?rwsrwsrwt"),
+            (0o100644, "-rw-r--r--"),
+            (0o100755, "-rwxr-xr-x"),
+            (0o040755, "drwxr-xr-x"),
+            (0o041777, "drwxrwxrwt"),
+            (0o041776, "drwxrwxrwT"),
+            (0o120777, "lrwxrwxrwx"),
+            (0o104755, "-rwsr-xr-x"),
+            (0o104644, "-rwSr--r--"),
+            (0o102755, "-rwxr-sr-x"),
+            (0o102745, "-rwxr-Sr-x"),
+            (0o060660, "brw-rw----"),
+            (0o020666, "crw-rw-rw-"),
+            (0o010600, "prw-------"),
+            (0o140777, "srwxrwxrwx"),
+        ] {
+            assert_eq!(Mode(mode).symbolic(), symbolic, "{:o}", mode);
+        }
+    }
+
+    #[test]
+    fn every_digit_in_every_position() {
+        let triples = ["---", "--x", "-w-", "-wx", "r--", "r-x", "rw-", "rwx"];
+        for (digit, triple) in triples.iter().enumerate() {
+            let digit = digit as u32;
+            for (shift, position) in [(USER_SHIFT, 0), (GROUP_SHIFT, 1), (OTHER_SHIFT, 2)] {
+                let mut expected = ["---"; 3];
+                expected[position] = triple;
+                let symbolic = Mode(TYPE_REGULAR | digit << shift).symbolic();
+                assert_eq!(symbolic, format!("-{}", expected.concat()), "{}", digit);
+                assert_eq!(symbolic.len(), 10);
+            }
+        }
+        assert_eq!(Mode(0o100604).symbolic(), "-rw----r--");
+        assert_eq!(Mode(0o100311).symbolic(), "--wx--x--x");
+        assert_eq!(Mode(0o100200).symbolic(), "--w-------");
+    }
+
+    // Every combination of the permission and special bits, against
+    // stat(2)'s S_I* masks one by one
+    #[test]
+    fn all_permission_bits() {
+        let slots = [
+            (0o400, 'r', None),
+            (0o200, 'w', None),
+            (0o100, 'x', Some((0o4000, 's'))),
+            (0o040, 'r', None),
+            (0o020, 'w', None),
+            (0o010, 'x', Some((0o2000, 's'))),
+            (0o004, 'r', None),
+            (0o002, 'w', None),
+            (0o001, 'x', Some((0o1000, 't'))),
+        ];
+        for bits in 0..=PERMISSION_MASK {
+            let mut expected = String::from("d");
+            for (mask, set, special) in slots {
+                let is_set = bits & mask != 0;
+                expected.push(match special {
+                    Some((special_mask, ch)) if bits & special_mask != 0 => match is_set {
+                        true => ch,
+                        false => ch.to_ascii_uppercase(),
+                    },
+                    _ if is_set => set,
+                    _ => '-',
+                });
+            }
+            let mode = Mode(TYPE_DIRECTORY | bits);
+            assert_eq!(mode.symbolic(), expected, "{:o}", bits);
+            assert_eq!(mode.octal(), format!("{:04o}", bits));
+            assert!(mode.kind() == EntryKind::Dir);
+        }
+    }
+
+    #[test]
+    fn fabricated_modes_of_each_kind() {
+        for (type_bits, kind, type_char, indicator) in [
+            (TYPE_REGULAR, EntryKind::File, '-', ""),
+            (TYPE_DIRECTORY, EntryKind::Dir, 'd', "/"),
+            (TYPE_SYMLINK, EntryKind::Symlink, 'l', "@"),
+            (TYPE_FIFO, EntryKind::Fifo, 'p', "|"),
+            (TYPE_SOCKET, EntryKind::Socket, 's', "="),
+            (TYPE_CHAR_DEVICE, EntryKind::CharDevice, 'c', ""),
+            (TYPE_BLOCK_DEVICE, EntryKind::BlockDevice, 'b', ""),
+            (0, EntryKind::Unknown, '?', ""),
+            (0o030000, EntryKind::Unknown, '?', ""),
+        ] {
+            let mode = Mode(type_bits | 0o640);
+            assert!(mode.kind() == kind, "{:o}", type_bits);
+            assert_eq!(kind.type_char(), type_char);
+            assert_eq!(mode.symbolic(), format!("{}rw-r-----", type_char));
+            assert_eq!(mode.indicator(), indicator, "{:o}", type_bits);
+        }
+        // Only a regular file is marked executable
+        assert_eq!(Mode(TYPE_REGULAR | 0o755).indicator(), "*");
+        assert_eq!(Mode(TYPE_CHAR_DEVICE | 0o755).indicator(), "");
+    }
+
+    #[test]
+    fn octal_modes_leave_out_the_type() {
+        assert_eq!(Mode(0).octal(), "0000");
+        assert_eq!(Mode(0o100644).octal(), "0644");
+        assert_eq!(Mode(0o041777).octal(), "1777");
+        assert_eq!(Mode(0o7777).octal(), "7777");
+    }
+
+    #[test]
+    fn special_bits() {
+        let mode = Mode(0o106001);
+        assert!(mode.is_setuid());
+        assert!(mode.is_setgid());
+        assert!(!mode.is_sticky());
+        assert!(mode.is_executable());
+        assert!(Mode(0o1000).is_sticky());
+        assert!(!Mode(0o100666).is_executable());
+    }
+
+    #[test]
+    fn permission_triples() {
+        let mode = Mode(0o100754);
+        let triple =
+            |permissions: Permissions| (permissions.read, permissions.write, permissions.execute);
+        assert_eq!(triple(mode.user()), (true, true, true));
+        assert_eq!(triple(mode.group()), (true, false, true));
+        assert_eq!(triple(mode.other()), (true, false, false));
+    }
+
+    #[test]
+    fn perm_specs_match_like_find() {
+        for (mode, spec, expected) in [
+            (0o100644, "644", true),
+            (0o100644, "0644", true),
+            (0o100644, "640", false),
+            (0o104644, "644", false),
+            (0o104644, "4644", true),
+            (0o040755, "755", true),
+            (0o100644, "-644", true),
+            (0o100644, "-600", true),
+            (0o100644, "-664", false),
+            (0o104755, "-4000", true),
+            (0o100755, "-4000", false),
+            (0o100666, "-0002", true),
+            (0o100664, "-0002", false),
+            (0o100644, "-0", true),
+            (0o100644, "/022", false),
+            (0o100646, "/022", true),
+            (0o100600, "/022", false),
+            (0o100620, "/022", true),
+            (0o102755, "/6000", true),
+            (0o101777, "/6000", false),
+            (0o100600, "/000", true),
+            (0o100000, "/000", true),
+            (0o100000, "0", true),
+        ] {
+            let perm = PermSpec::parse(spec).unwrap();
+            assert_eq!(perm.matches(Mode(mode)), expected, "{:o} {}", mode, spec);
+        }
+    }
+
+    #[test]
+    fn invalid_perm_specs() {
+        for spec in [
+            "", "-", "/", "8", "649", "abc", "u+x", "+644", "--644", "/-644", "17777", " 644",
+        ] {
+            assert_eq!(
+                PermSpec::parse(spec).err().as_deref(),
+                Some(spec),
+                "{:?}",
+                spec
+            );
+        }
+    }
+
+    #[test]
+    fn real_files_have_their_kind() {
+        let dir = env::temp_dir().join(format!("rs-unit-{}-kinds", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("dir")).unwrap();
+        fs::write(dir.join("file"), b"").unwrap();
+        symlink("file", dir.join("link")).unwrap();
+        let _listener = UnixListener::bind(dir.join("socket")).unwrap();
+        let status = Command::new("mkfifo").arg(dir.join("fifo")).status();
+        assert!(status.unwrap().success());
+
+        for (name, kind, type_char, indicator) in [
+            ("file", EntryKind::File, '-', ""),
+            ("dir", EntryKind::Dir, 'd', "/"),
+            ("link", EntryKind::Symlink, 'l', "@"),
+            ("socket", EntryKind::Socket, 's', "="),
+            ("fifo", EntryKind::Fifo, 'p', "|"),
+        ] {
+            let path = dir.join(name);
+            assert!(lstat_kind(&path) == kind, "{}", name);
+            assert_eq!(kind.type_char(), type_char);
+            let mode = Mode(fs::symlink_metadata(&path).unwrap().st_mode());
+            assert_eq!(mode.indicator(), indicator, "{}", name);
+        }
+        fs::remove_dir_all(dir).unwrap();
+
+        assert!(lstat_kind(Path::new("/dev/null")) == EntryKind::CharDevice);
+        assert_eq!(EntryKind::CharDevice.type_char(), 'c');
+        // Block devices can't be made without privileges, so look for one
+        let block_device = fs::read_dir("/dev")
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .find(|entry| lstat_kind(&entry.path()) == EntryKind::BlockDevice);
+        if let Some(entry) = block_device {
+            let mode = Mode(fs::symlink_metadata(entry.path()).unwrap().st_mode());
+            assert!(mode.symbolic().starts_with('b'));
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use std::borrow::Cow;
+
+// Precomposed Latin letters and the base letters they decompose to, per
+// combining mark, e.g. 'é' is 'e' followed by U+0301. Enough of Unicode's
+// canonical decomposition to match the names macOS writes in NFD against
+// the same names typed in NFC.
+const DECOMPOSITIONS: [(char, &str, &str); 13] = [
+    ('\u{300}', "ÀÈÌÒÙàèìòù", "AEIOUaeiou"),
+    (
+        '\u{301}',
+        "ÁÉÍÓÚÝáéíóúýĆćĹĺŃńŔŕŚśŹź",
+        "AEIOUYaeiouyCcLlNnRrSsZz",
+    ),
+    (
+        '\u{302}',
+        "ÂÊÎÔÛâêîôûĈĉĜĝĤĥĴĵŜŝŴŵŶŷ",
+        "AEIOUaeiouCcGgHhJjSsWwYy",
+    ),
+    ('\u{303}', "ÃÑÕãñõĨĩŨũ", "ANOanoIiUu"),
+    ('\u{304}', "ĀāĒēĪīŌōŪū", "AaEeIiOoUu"),
+    ('\u{306}', "ĂăĔĕĞğĬĭŎŏŬŭ", "AaEeGgIiOoUu"),
+    ('\u{307}', "ĊċĖėĠġİŻż", "CcEeGgIZz"),
+    ('\u{308}', "ÄËÏÖÜäëïöüÿŸ", "AEIOUaeiouyY"),
+    ('\u{30a}', "ÅåŮů", "AaUu"),
+    ('\u{30b}', "ŐőŰű", "OoUu"),
+    ('\u{30c}', "ČčĎďĚěĽľŇňŘřŠšŤťŽž", "CcDdEeLlNnRrSsTtZz"),
+    ('\u{327}', "ÇçĢģĶķĻļŅņŖŗŞşŢţ", "CcGgKkLlNnRrSsTt"),
+    ('\u{328}', "ĄąĘęĮįŲų", "AaEeIiUu"),
+];
+
+// The Unicode normalization form names are shown in with --normalize
+#[derive(Clone, Copy)]
+pub enum Normalization {
+    // Composed, as Linux and Windows tools usually write names
+    Nfc,
+    // Decomposed, as macOS stores names
+    Nfd,
+}
+
+// The base letter and combining mark a precomposed letter is made of
+fn decompose(ch: char) -> Option<(char, char)> {
+    DECOMPOSITIONS.iter().find_map(|(mark, composed, bases)| {
+        let index = composed.chars().position(|composed| composed == ch)?;
+        Some((bases.chars().nth(index)?, *mark))
+    })
+}
+
+// The precomposed letter for a base letter and combining mark, if the
+// table has one
+fn compose(base: char, mark: char) -> Option<char> {
+    let (_, composed, bases) = DECOMPOSITIONS.iter().find(|(known, _, _)| *known == mark)?;
+    let index = bases.chars().position(|known| known == base)?;
+    composed.chars().nth(index)
+}
+
+pub fn nfd(name: &str) -> String {
+    let mut decomposed = String::with_capacity(name.len());
+    for ch in name.chars() {
+        match decompose(ch) {
+            Some((base, mark)) => {
+                decomposed.push(base);
+                decomposed.push(mark);
+            }
+            None => decomposed.push(ch),
+        }
+    }
+    decomposed
+}
+
+pub fn nfc(name: &str) -> String {
+    let mut composed = String::with_capacity(name.len());
+    let mut chars = name.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match chars.peek().and_then(|mark| compose(ch, *mark)) {
+            Some(letter) => {
+                composed.push(letter);
+                chars.next();
+            }
+            None => composed.push(ch),
+        }
+    }
+    composed
+}
+
+impl Normalization {
+    pub fn apply(self, name: &str) -> String {
+        match self {
+            Normalization::Nfc => nfc(name),
+            Normalization::Nfd => nfd(name),
+        }
+    }
+}
+
+// The name in the given form, untouched without one or when it is plain ASCII
+pub fn normalize(name: &str, normalization: Option<Normalization>) -> Cow<'_, str> {
+    match normalization {
+        Some(normalization) if !name.is_ascii() => Cow::Owned(normalization.apply(name)),
+        _ => Cow::Borrowed(name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn letters_decompose_and_compose_back() {
+        assert_eq!(nfd("caf\u{e9}"), "cafe\u{301}");
+        assert_eq!(nfc("cafe\u{301}"), "caf\u{e9}");
+        assert_eq!(nfd("\u{c5}\u{f1}\u{17e}"), "A\u{30a}n\u{303}z\u{30c}");
+        for composed in DECOMPOSITIONS
+            .iter()
+            .flat_map(|(_, composed, _)| composed.chars())
+        {
+            let text = composed.to_string();
+            assert_eq!(nfc(&nfd(&text)), text, "{}", composed);
+            assert_eq!(nfd(&text).chars().count(), 2, "{}", composed);
+        }
+    }
+
+    #[test]
+    fn text_outside_the_table_is_kept() {
+        assert_eq!(nfd("plain.txt"), "plain.txt");
+        assert_eq!(nfc("plain.txt"), "plain.txt");
+        // Marks without a composed letter stay as they are
+        assert_eq!(nfc("x\u{301}"), "x\u{301}");
+        assert_eq!(nfc("\u{301}e"), "\u{301}e");
+        assert_eq!(nfd("日本"), "日本");
+    }
+
+    #[test]
+    fn ascii_and_no_normalization_borrow() {
+        assert!(matches!(
+            normalize("plain", Some(Normalization::Nfd)),
+            Cow::Borrowed("plain")
+        ));
+        assert!(matches!(normalize("cafe\u{301}", None), Cow::Borrowed(_)));
+        assert_eq!(
+            normalize("cafe\u{301}", Some(Normalization::Nfc)),
+            "caf\u{e9}"
+        );
+    }
+}
@@ -0,0 +1,79 @@
+// Delimited records, one per line, for spreadsheets and awk
+#[derive(Clone, Copy)]
+pub enum Delimiter {
+    // RFC 4180 CSV
+    Comma,
+    // Tab separated, escaping rather than quoting
+    Tab,
+}
+
+// Quotes the field when it holds a comma, quote or line break, doubling the
+// quotes inside
+fn quote_csv(field: &str) -> String {
+    match field.contains([',', '"', '\n', '\r']) {
+        true => format!("\"{}\"", field.replace('"', "\"\"")),
+        false => field.to_string(),
+    }
+}
+
+// TSV has no quoting, so a tab or line break in the field is written as \t,
+// \n or \r, and a backslash is doubled to keep those apart from the text
+fn escape_tsv(field: &str) -> String {
+    let mut escaped = String::with_capacity(field.len());
+    for ch in field.chars() {
+        match ch {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            ch => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+// The fields as one record, without the line ending
+pub fn record<S: AsRef<str>>(fields: &[S], delimiter: Delimiter) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|field| match delimiter {
+            Delimiter::Comma => quote_csv(field.as_ref()),
+            Delimiter::Tab => escape_tsv(field.as_ref()),
+        })
+        .collect();
+    match delimiter {
+        Delimiter::Comma => fields.join(","),
+        Delimiter::Tab => fields.join("\t"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_quotes_only_fields_that_need_it() {
+        assert_eq!(
+            record(
+                &["plain", "a,b", "say \"hi\"", "new\nline", "cr\r"],
+                Delimiter::Comma
+            ),
+            "plain,\"a,b\",\"say \"\"hi\"\"\",\"new\nline\",\"cr\r\""
+        );
+        assert_eq!(record(&["tab\there", ""], Delimiter::Comma), "tab\there,");
+    }
+
+    #[test]
+    fn tsv_escapes_tabs_line_breaks_and_backslashes() {
+        assert_eq!(
+            record(
+                &["tab\there", "new\nline", "cr\r", "back\\slash"],
+                Delimiter::Tab
+            ),
+            "tab\\there\tnew\\nline\tcr\\r\tback\\\\slash"
+        );
+        // A name that reads like an escape stays apart from the escaped one
+        assert_eq!(record(&["a\\tb", "a\tb"], Delimiter::Tab), "a\\\\tb\ta\\tb");
+        assert_eq!(record(&["a,\"b\"", ""], Delimiter::Tab), "a,\"b\"\t");
+    }
+}
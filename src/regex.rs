@@ -0,0 +1,363 @@
+// A small regular expression matcher for filtering names: literals, `.`,
+// bracket classes, the `\d`, `\w` and `\s` shorthands, `*`, `+` and `?`
+// after a single atom, `^` and `$` anchors and top level `|` alternatives.
+// Groups aren't supported, names are short enough to backtrack over.
+
+enum ClassItem {
+    Char(char),
+    Range(char, char),
+    Digit,
+    Word,
+    Space,
+}
+
+impl ClassItem {
+    fn matches(&self, ch: char) -> bool {
+        match self {
+            ClassItem::Char(c) => *c == ch,
+            ClassItem::Range(start, end) => (*start..=*end).contains(&ch),
+            ClassItem::Digit => ch.is_ascii_digit(),
+            ClassItem::Word => ch.is_alphanumeric() || ch == '_',
+            ClassItem::Space => ch.is_whitespace(),
+        }
+    }
+}
+
+enum Atom {
+    Any,
+    Class {
+        is_negated: bool,
+        items: Vec<ClassItem>,
+    },
+}
+
+impl Atom {
+    fn matches(&self, ch: char) -> bool {
+        match self {
+            Atom::Any => true,
+            Atom::Class { is_negated, items } => {
+                items.iter().any(|item| item.matches(ch)) != *is_negated
+            }
+        }
+    }
+}
+
+enum Quantifier {
+    One,
+    ZeroOrOne,
+    ZeroOrMore,
+    OneOrMore,
+}
+
+struct Piece {
+    atom: Atom,
+    quantifier: Quantifier,
+}
+
+struct Branch {
+    is_anchored_start: bool,
+    is_anchored_end: bool,
+    pieces: Vec<Piece>,
+}
+
+pub struct Regex {
+    branches: Vec<Branch>,
+}
+
+fn single(item: ClassItem) -> Atom {
+    Atom::Class {
+        is_negated: false,
+        items: vec![item],
+    }
+}
+
+// The class item for the character after a `\`
+fn escaped_item(ch: char) -> ClassItem {
+    match ch {
+        'd' => ClassItem::Digit,
+        'w' => ClassItem::Word,
+        's' => ClassItem::Space,
+        't' => ClassItem::Char('\t'),
+        'n' => ClassItem::Char('\n'),
+        other => ClassItem::Char(other),
+    }
+}
+
+// Parses a bracket class, `chars` being just after the `[`
+fn parse_class(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<Atom, String> {
+    let is_negated = chars.next_if_eq(&'^').is_some();
+    let mut items = vec![];
+    // A `]` straight after the opening bracket is part of the set
+    let mut is_first = true;
+    loop {
+        let item = match chars.next() {
+            Some(']') if !is_first => return Ok(Atom::Class { is_negated, items }),
+            Some('\\') => match chars.next() {
+                Some(ch) => escaped_item(ch),
+                None => break,
+            },
+            Some(ch) => ClassItem::Char(ch),
+            None => break,
+        };
+        is_first = false;
+
+        let mut lookahead = chars.clone();
+        match (item, lookahead.next(), lookahead.next()) {
+            (ClassItem::Char(start), Some('-'), Some(end)) if end != ']' => {
+                chars.nth(1);
+                items.push(ClassItem::Range(start, end));
+            }
+            (item, _, _) => items.push(item),
+        }
+    }
+    Err(String::from("unterminated ["))
+}
+
+fn parse_branch(pattern: &str) -> Result<Branch, String> {
+    let (is_anchored_start, pattern) = match pattern.strip_prefix('^') {
+        Some(rest) => (true, rest),
+        None => (false, pattern),
+    };
+    // An escaped `$` at the end is a literal dollar, but not one after an
+    // escaped backslash
+    let backslashes = pattern
+        .strip_suffix('$')
+        .map(|rest| rest.chars().rev().take_while(|ch| *ch == '\\').count());
+    let is_anchored_end = backslashes.is_some_and(|count| count % 2 == 0);
+    let pattern = match is_anchored_end {
+        true => &pattern[..pattern.len() - 1],
+        false => pattern,
+    };
+
+    let mut pieces: Vec<Piece> = vec![];
+    let mut chars = pattern.chars().peekable();
+    while let Some(ch) = chars.next() {
+        let quantifier = match ch {
+            '*' => Some(Quantifier::ZeroOrMore),
+            '+' => Some(Quantifier::OneOrMore),
+            '?' => Some(Quantifier::ZeroOrOne),
+            _ => None,
+        };
+        if let Some(quantifier) = quantifier {
+            match pieces.last_mut() {
+                Some(piece) if matches!(piece.quantifier, Quantifier::One) => {
+                    piece.quantifier = quantifier
+                }
+                _ => return Err(format!("nothing to repeat before '{}'", ch)),
+            }
+            continue;
+        }
+
+        let atom = match ch {
+            '.' => Atom::Any,
+            '[' => parse_class(&mut chars)?,
+            '\\' => match chars.next() {
+                Some(escaped) => single(escaped_item(escaped)),
+                None => return Err(String::from("trailing backslash")),
+            },
+            '(' | ')' => return Err(String::from("groups are not supported")),
+            literal => single(ClassItem::Char(literal)),
+        };
+        pieces.push(Piece {
+            atom,
+            quantifier: Quantifier::One,
+        });
+    }
+
+    Ok(Branch {
+        is_anchored_start,
+        is_anchored_end,
+        pieces,
+    })
+}
+
+// Whether the pieces match text from the start, all of it when anchored at the end
+fn match_here(pieces: &[Piece], text: &[char], is_anchored_end: bool) -> bool {
+    let Some((piece, rest)) = pieces.split_first() else {
+        return !is_anchored_end || text.is_empty();
+    };
+
+    let (min, max) = match piece.quantifier {
+        Quantifier::One => (1, 1),
+        Quantifier::ZeroOrOne => (0, 1),
+        Quantifier::ZeroOrMore => (0, usize::MAX),
+        Quantifier::OneOrMore => (1, usize::MAX),
+    };
+    let available = text
+        .iter()
+        .take(max)
+        .take_while(|ch| piece.atom.matches(**ch))
+        .count();
+    // Greedy, so try the longest run first
+    (min..=available)
+        .rev()
+        .any(|count| match_here(rest, &text[count..], is_anchored_end))
+}
+
+// Splits on the `|`s that aren't escaped or inside a bracket class
+fn split_branches(pattern: &str) -> Vec<&str> {
+    let mut branches = vec![];
+    let (mut start, mut is_escaped, mut is_in_class) = (0, false, false);
+    for (index, ch) in pattern.char_indices() {
+        match ch {
+            _ if is_escaped => is_escaped = false,
+            '\\' => is_escaped = true,
+            '[' => is_in_class = true,
+            ']' => is_in_class = false,
+            '|' if !is_in_class => {
+                branches.push(&pattern[start..index]);
+                start = index + 1;
+            }
+            _ => continue,
+        }
+    }
+    branches.push(&pattern[start..]);
+    branches
+}
+
+impl Regex {
+    pub fn new(pattern: &str) -> Result<Regex, String> {
+        let branches = split_branches(pattern)
+            .into_iter()
+            .map(parse_branch)
+            .collect::<Result<Vec<Branch>, String>>()?;
+        Ok(Regex { branches })
+    }
+
+    // Whether the pattern matches anywhere in the text
+    pub fn is_match(&self, text: &str) -> bool {
+        let text: Vec<char> = text.chars().collect();
+        self.branches.iter().any(|branch| {
+            let starts = match branch.is_anchored_start {
+                true => 0..=0,
+                false => 0..=text.len(),
+            };
+            starts
+                .into_iter()
+                .any(|start| match_here(&branch.pieces, &text[start..], branch.is_anchored_end))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Asserts which of the names the pattern matches
+    fn assert_matches(pattern: &str, matched: &[&str], unmatched: &[&str]) {
+        let regex = Regex::new(pattern).unwrap();
+        for name in matched {
+            assert!(regex.is_match(name), "{} should match {}", pattern, name);
+        }
+        for name in unmatched {
+            assert!(
+                !regex.is_match(name),
+                "{} shouldn't match {}",
+                pattern,
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn literals_match_anywhere_unless_anchored() {
+        assert_matches("test", &["test", "test.rs", "my_test"], &["tset", "Test"]);
+        assert_matches("^test", &["test", "test.rs"], &["my_test"]);
+        assert_matches("\\.rs$", &["main.rs"], &["main.rs.bak", "mainrs"]);
+        assert_matches("^main\\.rs$", &["main.rs"], &["main.rs~", "amain.rs"]);
+        assert_matches("^$", &[""], &["a"]);
+        assert_matches("", &["", "anything"], &[]);
+    }
+
+    #[test]
+    fn dot_matches_any_character() {
+        assert_matches("^a.c$", &["abc", "a.c", "a\u{e9}c"], &["ac", "abbc"]);
+    }
+
+    #[test]
+    fn classes_match_one_of_their_characters() {
+        assert_matches("^[abc]$", &["a", "c"], &["d", "ab", ""]);
+        assert_matches("^[a-c0-2_]+$", &["abc012", "_"], &["abcd", "3"]);
+        // A leading `]` and a trailing `-` are part of the set
+        assert_matches("^[]x]$", &["]", "x"], &["["]);
+        assert_matches("^[a-]$", &["a", "-"], &["b"]);
+        assert_matches("^[\\d.]+$", &["1.2.3"], &["1.a"]);
+        // `|` inside a class isn't an alternative
+        assert_matches("^[a|b]$", &["|", "a"], &["ab"]);
+    }
+
+    #[test]
+    fn negated_classes_match_anything_else() {
+        assert_matches("^[^abc]$", &["d", "1", "^"], &["a", "", "dd"]);
+        assert_matches("^[^0-9]+$", &["abc"], &["a1c"]);
+    }
+
+    #[test]
+    fn shorthands_match_digits_words_and_spaces() {
+        assert_matches("^\\d+$", &["2024"], &["20a4"]);
+        assert_matches("^\\w+$", &["snake_case1", "caf\u{e9}"], &["kebab-case"]);
+        assert_matches("\\s", &["a b", "a\tb"], &["ab"]);
+    }
+
+    #[test]
+    fn quantifiers_repeat_the_atom_before_them() {
+        assert_matches("^ab*c$", &["ac", "abc", "abbbc"], &["abdc"]);
+        assert_matches("^ab+c$", &["abc", "abbbc"], &["ac"]);
+        assert_matches("^ab?c$", &["ac", "abc"], &["abbc"]);
+        assert_matches("^.*\\.log$", &[".log", "app.log"], &["app.logs"]);
+        // Greedy runs still give back what the rest needs
+        assert_matches("^a.*b.*c$", &["abc", "axxbyyc"], &["axxcyyb"]);
+        assert_matches("^[0-9]+[a-z]+$", &["123abc"], &["123", "abc"]);
+    }
+
+    #[test]
+    fn alternatives_match_either_branch() {
+        assert_matches("^a$|^b$", &["a", "b"], &["ab", "c"]);
+        assert_matches(
+            "\\.rs$|\\.toml$|^Makefile$",
+            &["main.rs", "Cargo.toml", "Makefile"],
+            &["main.c"],
+        );
+        assert_matches("a\\|b", &["a|b"], &["a", "b"]);
+    }
+
+    #[test]
+    fn escapes_make_special_characters_literal() {
+        assert_matches("^a\\.b$", &["a.b"], &["axb"]);
+        assert_matches("^\\*\\+\\?$", &["*+?"], &["a"]);
+        assert_matches("^\\[x\\]$", &["[x]"], &["x"]);
+        assert_matches("^cost\\$$", &["cost$"], &["cost"]);
+        assert_matches("cost\\$", &["cost$", "cost$s"], &["cost"]);
+        // An escaped backslash before the `$` leaves it an anchor
+        assert_matches("^a\\\\$", &["a\\"], &["a\\b", "a\\$"]);
+        assert_matches("^a\\tb$", &["a\tb"], &["atb"]);
+    }
+
+    #[test]
+    fn invalid_patterns_are_errors() {
+        for pattern in [
+            "*a", "a|+b", "a**", "a+?", "^*", "[abc", "[", "[]", "[^", "a\\", "[a\\", "(ab)", "a)",
+        ] {
+            assert!(Regex::new(pattern).is_err(), "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn no_short_pattern_panics() {
+        let chars = [
+            'a', '.', '[', ']', '^', '$', '\\', '*', '+', '?', '|', '-', '(', '\u{e9}',
+        ];
+        for a in chars {
+            for b in chars {
+                for c in chars {
+                    let pattern: String = [a, b, c].iter().collect();
+                    if let Ok(regex) = Regex::new(&pattern) {
+                        for text in ["", "a", "a.b", "[a]", "\u{e9}$"] {
+                            regex.is_match(text);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use std::{fmt, ops::AddAssign};
+
+use crate::format;
+
+// st_blocks counts 512-byte units whatever the filesystem's block size is
+const BLOCK_SIZE_IN_BYTES: u64 = 512;
+
+// An amount of data in bytes, like a file's apparent size
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ByteSize(pub u64);
+
+// An amount of allocated space in st_blocks' 512-byte units
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct BlockCount(pub u64);
+
+impl ByteSize {
+    // e.g. "4.0K", in powers of 1024
+    pub fn human_readable(self, precision: usize) -> String {
+        format::bytes_to_human_readable(self.0, precision)
+    }
+
+    // e.g. "4.2 kilobytes"
+    pub fn words(self) -> String {
+        format::bytes_to_words(self.0)
+    }
+
+    // How many blocks of the given size it takes, rounded up like du
+    pub fn to_display_blocks(self, block_size: u64) -> u64 {
+        self.0.div_ceil(block_size)
+    }
+}
+
+impl BlockCount {
+    pub fn to_bytes(self) -> ByteSize {
+        ByteSize(self.0 * BLOCK_SIZE_IN_BYTES)
+    }
+}
+
+impl AddAssign for ByteSize {
+    fn add_assign(&mut self, other: ByteSize) {
+        self.0 += other.0;
+    }
+}
+
+impl AddAssign for BlockCount {
+    fn add_assign(&mut self, other: BlockCount) {
+        self.0 += other.0;
+    }
+}
+
+impl fmt::Display for ByteSize {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl fmt::Display for BlockCount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blocks_are_512_bytes() {
+        assert!(BlockCount(0).to_bytes() == ByteSize(0));
+        assert!(BlockCount(1).to_bytes() == ByteSize(512));
+        assert!(BlockCount(8).to_bytes() == ByteSize(4096));
+    }
+
+    #[test]
+    fn display_blocks_round_up() {
+        assert_eq!(ByteSize(0).to_display_blocks(1024), 0);
+        assert_eq!(ByteSize(1).to_display_blocks(1024), 1);
+        assert_eq!(ByteSize(1024).to_display_blocks(1024), 1);
+        assert_eq!(ByteSize(1025).to_display_blocks(1024), 2);
+        // 8 st_blocks units are 4 kibibyte blocks, as -s shows them
+        assert_eq!(BlockCount(8).to_bytes().to_display_blocks(1024), 4);
+        assert_eq!(BlockCount(3).to_bytes().to_display_blocks(1024), 2);
+    }
+
+    #[test]
+    fn formatting() {
+        assert_eq!(ByteSize(4096).to_string(), "4096");
+        assert_eq!(BlockCount(8).to_string(), "8");
+        assert_eq!(ByteSize(4096).human_readable(1), "4.0K");
+        assert_eq!(ByteSize(1).words(), "1 byte");
+        assert_eq!(ByteSize(1000).words(), "1000 bytes");
+        assert_eq!(ByteSize(4300).words(), "4.2 kilobytes");
+        assert_eq!(ByteSize(3 << 30).words(), "3.0 gigabytes");
+    }
+
+    #[test]
+    fn totals_add_up() {
+        let mut bytes = ByteSize(100);
+        bytes += ByteSize(28);
+        let mut blocks = BlockCount(8);
+        blocks += BlockCount(16);
+        assert!(bytes == ByteSize(128));
+        assert!(blocks == BlockCount(24));
+    }
+}
@@ -0,0 +1,31 @@
+use std::collections::HashMap;
+
+use crate::size::ByteSize;
+
+pub struct Group {
+    pub key: String,
+    pub count: usize,
+    pub total_size: ByteSize,
+}
+
+// Counts and adds up the sizes per key, biggest total first, ties by key
+pub fn group(items: impl Iterator<Item = (String, ByteSize)>) -> Vec<Group> {
+    let mut by_key: HashMap<String, Group> = HashMap::new();
+    for (key, size) in items {
+        let group = by_key.entry(key.clone()).or_insert(Group {
+            key,
+            count: 0,
+            total_size: ByteSize(0),
+        });
+        group.count += 1;
+        group.total_size += size;
+    }
+
+    let mut groups: Vec<Group> = by_key.into_values().collect();
+    groups.sort_by(|a, b| {
+        b.total_size
+            .cmp(&a.total_size)
+            .then_with(|| a.key.cmp(&b.key))
+    });
+    groups
+}
@@ -0,0 +1,276 @@
+// Finder tags, stored by macOS as a binary plist array of "name\ncolor" strings
+#[cfg(target_os = "macos")]
+const TAGS_XATTR_NAME: &str = "com.apple.metadata:_kMDItemUserTags";
+
+const BPLIST_MAGIC: &[u8] = b"bplist00";
+const BPLIST_TRAILER_LENGTH: usize = 32;
+
+// Object markers, the high nibble of an object's first byte
+const MARKER_INT: u8 = 0x1;
+const MARKER_ASCII_STRING: u8 = 0x5;
+const MARKER_UTF16_STRING: u8 = 0x6;
+const MARKER_ARRAY: u8 = 0xA;
+// A length nibble of 0xF means the length follows as an int object
+const LENGTH_FOLLOWS: u8 = 0xF;
+
+pub struct Tag {
+    pub name: String,
+    color: u8,
+}
+
+impl Tag {
+    // Finder's label colors by index, as color names --color-override knows
+    pub fn color_spec(&self) -> Option<&'static str> {
+        match self.color {
+            1 => Some("dim"),
+            2 => Some("green"),
+            3 => Some("magenta"),
+            4 => Some("blue"),
+            5 => Some("yellow"),
+            6 => Some("red"),
+            7 => Some("#ff8700"),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod sys {
+    use std::os::raw::{c_char, c_int, c_void};
+
+    extern "C" {
+        pub fn getxattr(
+            path: *const c_char,
+            name: *const c_char,
+            value: *mut c_void,
+            size: usize,
+            position: u32,
+            options: c_int,
+        ) -> isize;
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn read_tags_xattr(path: &std::path::Path) -> Option<Vec<u8>> {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt, ptr};
+
+    let c_path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let c_name = CString::new(TAGS_XATTR_NAME).ok()?;
+    // SAFETY: both strings are NUL terminated, a null buffer asks for the size
+    let size = unsafe { sys::getxattr(c_path.as_ptr(), c_name.as_ptr(), ptr::null_mut(), 0, 0, 0) };
+    if size <= 0 {
+        return None;
+    }
+    let mut value = vec![0u8; size as usize];
+    // SAFETY: value has room for the size getxattr just reported
+    let read = unsafe {
+        sys::getxattr(
+            c_path.as_ptr(),
+            c_name.as_ptr(),
+            value.as_mut_ptr().cast(),
+            value.len(),
+            0,
+            0,
+        )
+    };
+    if read < 0 {
+        return None;
+    }
+    value.truncate(read as usize);
+    Some(value)
+}
+
+// Only macOS has Finder tags
+#[cfg(not(target_os = "macos"))]
+fn read_tags_xattr(_path: &std::path::Path) -> Option<Vec<u8>> {
+    None
+}
+
+// A big endian unsigned int of 1 to 8 bytes
+fn read_uint(bytes: &[u8], offset: usize, size: usize) -> Option<u64> {
+    let slice = bytes.get(offset..offset.checked_add(size)?)?;
+    if size == 0 || size > 8 {
+        return None;
+    }
+    Some(
+        slice
+            .iter()
+            .fold(0, |value, byte| (value << 8) | *byte as u64),
+    )
+}
+
+// The length in an object's low nibble, or in the int object after it.
+// Returns the length and where the object's data starts.
+fn read_length(bytes: &[u8], offset: usize) -> Option<(usize, usize)> {
+    let length = bytes.get(offset)? & 0x0F;
+    if length != LENGTH_FOLLOWS {
+        return Some((length as usize, offset + 1));
+    }
+    let int_marker = *bytes.get(offset + 1)?;
+    if int_marker >> 4 != MARKER_INT {
+        return None;
+    }
+    let int_size = 1 << (int_marker & 0x0F);
+    let length = read_uint(bytes, offset + 2, int_size)?;
+    Some((usize::try_from(length).ok()?, offset + 2 + int_size))
+}
+
+// Decodes a binary plist holding an array of strings. Anything else is None.
+fn decode_string_array(bytes: &[u8]) -> Option<Vec<String>> {
+    if !bytes.starts_with(BPLIST_MAGIC) || bytes.len() < BPLIST_MAGIC.len() + BPLIST_TRAILER_LENGTH
+    {
+        return None;
+    }
+    let trailer = bytes.len() - BPLIST_TRAILER_LENGTH;
+    let offset_size = bytes[trailer + 6] as usize;
+    let ref_size = bytes[trailer + 7] as usize;
+    let object_count = read_uint(bytes, trailer + 8, 8)?;
+    let top_object = read_uint(bytes, trailer + 16, 8)?;
+    let offset_table = usize::try_from(read_uint(bytes, trailer + 24, 8)?).ok()?;
+
+    let object_offset = |index: u64| -> Option<usize> {
+        if index >= object_count {
+            return None;
+        }
+        let entry = offset_table.checked_add(usize::try_from(index).ok()? * offset_size)?;
+        usize::try_from(read_uint(bytes, entry, offset_size)?).ok()
+    };
+
+    let array_offset = object_offset(top_object)?;
+    if bytes.get(array_offset)? >> 4 != MARKER_ARRAY {
+        return None;
+    }
+    let (count, refs_start) = read_length(bytes, array_offset)?;
+
+    let mut strings = vec![];
+    for index in 0..count {
+        let object_ref = read_uint(bytes, refs_start + index * ref_size, ref_size)?;
+        let offset = object_offset(object_ref)?;
+        let (length, start) = read_length(bytes, offset)?;
+        let string = match bytes.get(offset)? >> 4 {
+            MARKER_ASCII_STRING => {
+                String::from_utf8(bytes.get(start..start + length)?.to_vec()).ok()?
+            }
+            MARKER_UTF16_STRING => {
+                let units: Vec<u16> = bytes
+                    .get(start..start + length * 2)?
+                    .chunks(2)
+                    .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                    .collect();
+                String::from_utf16(&units).ok()?
+            }
+            _ => return None,
+        };
+        strings.push(string);
+    }
+    Some(strings)
+}
+
+// Tags from the xattr's value, each "name" or "name\ncolor", None when it
+// isn't an array of strings
+fn decode(bytes: &[u8]) -> Option<Vec<Tag>> {
+    let tags = decode_string_array(bytes)?
+        .into_iter()
+        .map(|string| match string.split_once('\n') {
+            Some((name, color)) => Tag {
+                name: name.to_string(),
+                color: color.parse().unwrap_or(0),
+            },
+            None => Tag {
+                name: string,
+                color: 0,
+            },
+        })
+        .collect();
+    Some(tags)
+}
+
+// The entry's tags, an empty list when it has none and None when they
+// couldn't be decoded
+pub fn read(path: &std::path::Path) -> Option<Vec<Tag>> {
+    match read_tags_xattr(path) {
+        Some(bytes) => decode(&bytes),
+        None => Some(vec![]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Captured from plistlib's binary writer, which matches what Finder stores
+    const EMPTY: &[u8] = b"bplist00\xa0\x08\x00\x00\x00\x00\x00\x00\x01\x01\x00\x00\x00\x00\x00\x00\x00\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x09";
+    const ONE_TAG: &[u8] = b"bplist00\xa1\x01TWork\x08\x0a\x00\x00\x00\x00\x00\x00\x01\x01\x00\x00\x00\x00\x00\x00\x00\x02\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x0f";
+    // "Red\n6" and a UTF-16 "Caf\u{e9}\n4"
+    const COLORED_TAGS: &[u8] = b"bplist00\xa2\x01\x02URed\x0a6f\x00C\x00a\x00f\x00\xe9\x00\x0a\x004\x08\x0b\x11\x00\x00\x00\x00\x00\x00\x01\x01\x00\x00\x00\x00\x00\x00\x00\x03\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x1e";
+    // Sixteen tags, so the array's length follows its marker
+    const MANY_TAGS: &[u8] = b"bplist00\xaf\x10\x10\x01\x02\x03\x04\x05\x06\x07\x08\x09\x0a\x0b\x0c\x0d\x0e\x0f\x10Rt0Rt1Rt2Rt3Rt4Rt5Rt6Rt7Rt8Rt9St10St11St12St13St14St15\x08\x1b\x1e!$'*-0369=AEIM\x00\x00\x00\x00\x00\x00\x01\x01\x00\x00\x00\x00\x00\x00\x00\x11\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00Q";
+    // {"a": "b"}
+    const DICT: &[u8] = b"bplist00\xd1\x01\x02QaQb\x08\x0b\x0d\x00\x00\x00\x00\x00\x00\x01\x01\x00\x00\x00\x00\x00\x00\x00\x03\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x0f";
+
+    fn names_and_colors(bytes: &[u8]) -> Option<Vec<(String, Option<&'static str>)>> {
+        let tags = decode(bytes)?;
+        Some(
+            tags.iter()
+                .map(|tag| (tag.name.clone(), tag.color_spec()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn empty_array_has_no_tags() {
+        assert_eq!(names_and_colors(EMPTY), Some(vec![]));
+    }
+
+    #[test]
+    fn tag_without_a_color() {
+        assert_eq!(
+            names_and_colors(ONE_TAG),
+            Some(vec![("Work".to_string(), None)])
+        );
+    }
+
+    #[test]
+    fn color_suffix_picks_the_color() {
+        assert_eq!(
+            names_and_colors(COLORED_TAGS),
+            Some(vec![
+                ("Red".to_string(), Some("red")),
+                ("Caf\u{e9}".to_string(), Some("blue")),
+            ])
+        );
+    }
+
+    #[test]
+    fn long_arrays_read_their_length() {
+        let names: Vec<String> = decode(MANY_TAGS)
+            .unwrap()
+            .into_iter()
+            .map(|tag| tag.name)
+            .collect();
+        assert_eq!(
+            names,
+            (0..16).map(|i| format!("t{}", i)).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn malformed_plists_are_none() {
+        assert!(decode(DICT).is_none());
+        assert!(decode(b"").is_none());
+        assert!(decode(b"bplist00").is_none());
+        assert!(decode(&ONE_TAG[1..]).is_none());
+        // Cut short, so the trailer points past the end
+        for len in 0..ONE_TAG.len() {
+            assert!(decode(&ONE_TAG[..len]).is_none(), "{}", len);
+        }
+        // An object reference past the object count
+        let mut bad_ref = ONE_TAG.to_vec();
+        bad_ref[9] = 0x07;
+        assert!(decode(&bad_ref).is_none());
+        // A string whose length should follow it as an int, but doesn't
+        let mut bad_length = ONE_TAG.to_vec();
+        bad_length[10] = 0x5f;
+        assert!(decode(&bad_length).is_none());
+    }
+}
@@ -0,0 +1,47 @@
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+mod sys {
+    use std::os::raw::{c_int, c_ulong, c_ushort};
+
+    #[cfg(target_os = "linux")]
+    pub const TIOCGWINSZ: c_ulong = 0x5413;
+    #[cfg(target_os = "macos")]
+    pub const TIOCGWINSZ: c_ulong = 0x40087468;
+
+    pub const STDOUT_FILENO: c_int = 1;
+
+    #[repr(C)]
+    pub struct WinSize {
+        pub ws_row: c_ushort,
+        pub ws_col: c_ushort,
+        pub ws_xpixel: c_ushort,
+        pub ws_ypixel: c_ushort,
+    }
+
+    extern "C" {
+        pub fn ioctl(fd: c_int, request: c_ulong, ...) -> c_int;
+    }
+}
+
+// Columns of the terminal stdout is connected to, if it is one
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+pub fn width() -> Option<usize> {
+    let mut size = sys::WinSize {
+        ws_row: 0,
+        ws_col: 0,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    // SAFETY: TIOCGWINSZ only writes a winsize through the valid pointer it is given
+    if unsafe { sys::ioctl(sys::STDOUT_FILENO, sys::TIOCGWINSZ, &mut size) } != 0 {
+        return None;
+    }
+    match size.ws_col {
+        0 => None,
+        columns => Some(columns as usize),
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn width() -> Option<usize> {
+    None
+}
@@ -1,38 +1,219 @@
+use std::os::raw::{c_char, c_int, c_long};
+
+// Time
+const SECS_PER_DAY: i64 = 86400;
+const SECS_PER_HOUR: i64 = 3600;
+const SECS_PER_MINUTE: i64 = 60;
+// Half of an average Gregorian year, GNU ls' cutoff for recent timestamps
+const SECS_PER_SIX_MONTHS: i64 = 31556952 / 2;
+
+// Layout shared by glibc, musl and macOS
+#[repr(C)]
+struct Tm {
+    tm_sec: c_int,
+    tm_min: c_int,
+    tm_hour: c_int,
+    tm_mday: c_int,
+    tm_mon: c_int,
+    tm_year: c_int,
+    tm_wday: c_int,
+    tm_yday: c_int,
+    tm_isdst: c_int,
+    tm_gmtoff: c_long,
+    tm_zone: *const c_char,
+}
+
+extern "C" {
+    fn localtime_r(time: *const i64, result: *mut Tm) -> *mut Tm;
+}
+
+// Offset of the local timezone from UTC in seconds at the given time
+fn local_offset(secs: i64) -> i64 {
+    let mut tm = Tm {
+        tm_sec: 0,
+        tm_min: 0,
+        tm_hour: 0,
+        tm_mday: 0,
+        tm_mon: 0,
+        tm_year: 0,
+        tm_wday: 0,
+        tm_yday: 0,
+        tm_isdst: 0,
+        tm_gmtoff: 0,
+        tm_zone: std::ptr::null(),
+    };
+    // SAFETY: both pointers are valid for the duration of the call
+    let result = unsafe { localtime_r(&secs, &mut tm) };
+    if result.is_null() {
+        return 0;
+    }
+    tm.tm_gmtoff as i64
+}
+
+// Formats a timestamp the way GNU ls does in the C locale: the time of day
+// for the last six months, the year for anything older or in the future
+pub fn gnu_timestamp(secs: i64, now: i64) -> String {
+    let date = SimpleDate::from_secs(secs.saturating_add(local_offset(secs)));
+    let month = date.month_display(DateFormat::ShortMonth);
+    if secs <= now && now - secs < SECS_PER_SIX_MONTHS {
+        format!("{} {:>2} {}", month, date.day(), date.hour_minute())
+    } else {
+        format!("{} {:>2}  {}", month, date.day(), date.year())
+    }
+}
+
+// e.g. "2024-03-01 10:00:00.123456789 +0100" in the local timezone, the way
+// stat(1) prints timestamps
+pub fn full_timestamp(secs: i64, nanos: u32) -> String {
+    let offset = local_offset(secs);
+    let local_secs = secs.saturating_add(offset);
+    let date = SimpleDate::from_days(local_secs.div_euclid(SECS_PER_DAY));
+    let secs_of_day = local_secs.rem_euclid(SECS_PER_DAY);
+    let sign = if offset < 0 { '-' } else { '+' };
+    let offset_minutes = offset.abs() / SECS_PER_MINUTE;
+    format!(
+        "{}-{:02}-{:02} {:02}:{:02}:{:02}.{:09} {}{:02}{:02}",
+        date.year,
+        date.month,
+        date.day,
+        secs_of_day / SECS_PER_HOUR,
+        secs_of_day % SECS_PER_HOUR / SECS_PER_MINUTE,
+        secs_of_day % SECS_PER_MINUTE,
+        nanos,
+        sign,
+        offset_minutes / 60,
+        offset_minutes % 60
+    )
+}
+
+// e.g. "2024-03-01T10:00:00+01:00" in the local timezone, ISO 8601 for
+// structured output
+pub fn iso8601_timestamp(secs: i64) -> String {
+    let offset = local_offset(secs);
+    let local_secs = secs.saturating_add(offset);
+    let date = SimpleDate::from_days(local_secs.div_euclid(SECS_PER_DAY));
+    let secs_of_day = local_secs.rem_euclid(SECS_PER_DAY);
+    let sign = if offset < 0 { '-' } else { '+' };
+    let offset_minutes = offset.abs() / SECS_PER_MINUTE;
+    format!(
+        "{}-{:02}-{:02}T{:02}:{:02}:{:02}{}{:02}:{:02}",
+        date.year,
+        date.month,
+        date.day,
+        secs_of_day / SECS_PER_HOUR,
+        secs_of_day % SECS_PER_HOUR / SECS_PER_MINUTE,
+        secs_of_day % SECS_PER_MINUTE,
+        sign,
+        offset_minutes / 60,
+        offset_minutes % 60
+    )
+}
+
+// e.g. "2024-03-01 10:00" in the local timezone, GNU ls' long-iso style
+pub fn long_iso_timestamp(secs: i64) -> String {
+    let date = SimpleDate::from_secs(secs.saturating_add(local_offset(secs)));
+    format!(
+        "{}-{:02}-{:02} {}",
+        date.year,
+        date.month,
+        date.day,
+        date.hour_minute()
+    )
+}
+
+// GNU ls' iso style in the local timezone: "03-01 10:00" for the last six
+// months, "2024-03-01" for anything older or in the future
+pub fn iso_timestamp(secs: i64, now: i64) -> String {
+    let date = SimpleDate::from_secs(secs.saturating_add(local_offset(secs)));
+    if secs <= now && now - secs < SECS_PER_SIX_MONTHS {
+        format!("{:02}-{:02} {}", date.month, date.day, date.hour_minute())
+    } else {
+        format!("{}-{:02}-{:02}", date.year, date.month, date.day)
+    }
+}
+
+// Days since 1970-01-01 in the local timezone, so the day changes at local midnight
+pub fn local_days(secs: i64) -> i64 {
+    secs.saturating_add(local_offset(secs))
+        .div_euclid(SECS_PER_DAY)
+}
+
+// How long listings show timestamps, named as --time-style and TIME_STYLE
+// take them
+#[derive(Clone, Copy, PartialEq)]
+pub enum TimeStyle {
+    // rs' month and day, or GNU's with --compat=gnu
+    Default,
+    FullIso,
+    LongIso,
+    Iso,
+}
+
+impl TimeStyle {
+    // GNU's "posix-" prefix only matters outside the POSIX locale, which rs
+    // doesn't look at, so it's dropped
+    pub fn parse(name: &str) -> Option<TimeStyle> {
+        match name.strip_prefix("posix-").unwrap_or(name) {
+            "full-iso" => Some(TimeStyle::FullIso),
+            "long-iso" => Some(TimeStyle::LongIso),
+            "iso" => Some(TimeStyle::Iso),
+            "locale" | "default" => Some(TimeStyle::Default),
+            _ => None,
+        }
+    }
+}
+
 pub enum DateFormat {
     // Numeric,
-    // FullMonth,
+    FullMonth,
     ShortMonth,
 }
 
 pub struct SimpleDate {
-    // year: u64,
-    month: u64,
-    day: u64,
+    year: i64,
+    month: i64,
+    day: i64,
+    hour: i64,
+    minute: i64,
 }
 
 impl SimpleDate {
     // Stolen with great respect from Howard Hinnant :]
     // https://stackoverflow.com/a/32158604
-    pub fn from_days(mut days: u64) -> SimpleDate {
-        days += 719468;
-        let era = (if days > 0 { days } else { days - 146096 } / 146097);
-        let doe = days - era * 146097;
+    // Takes days since 1970-01-01, negative for dates before it. Eras are
+    // 400 years, counted from 0000-03-01 and rounded down for earlier dates,
+    // so the day of the era is never negative.
+    pub fn from_days(days: i64) -> SimpleDate {
+        let days = days + 719468;
+        let era = days.div_euclid(146097);
+        let doe = days.rem_euclid(146097);
         let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
-        // let y = yoe + era * 400;
+        let y = yoe + era * 400;
         let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
         let mp = (5 * doy + 2) / 153;
         let d = doy - (153 * mp + 2) / 5 + 1;
-        let m = mp + (if mp < 10 { 3 } else { 9 });
+        let m = if mp < 10 { mp + 3 } else { mp - 9 };
         SimpleDate {
-            // year: y,
+            year: if m <= 2 { y + 1 } else { y },
             month: m,
             day: d,
+            hour: 0,
+            minute: 0,
         }
     }
 
-    // pub fn year(&self) -> String {
-    //     return self.year.to_string();
-    // }
+    pub fn from_secs(secs: i64) -> SimpleDate {
+        let secs_of_day = secs.rem_euclid(SECS_PER_DAY);
+        SimpleDate {
+            hour: secs_of_day / SECS_PER_HOUR,
+            minute: secs_of_day % SECS_PER_HOUR / SECS_PER_MINUTE,
+            ..SimpleDate::from_days(secs.div_euclid(SECS_PER_DAY))
+        }
+    }
+
+    pub fn year(&self) -> String {
+        self.year.to_string()
+    }
 
     // pub fn month(&self) -> String {
     //     return self.month.to_string();
@@ -41,7 +222,7 @@ impl SimpleDate {
     pub fn month_display(&self, format: DateFormat) -> String {
         match format {
             // DateFormat::Numeric => self.month.to_string(),
-            // DateFormat::FullMonth => self.month_from_numeric(self.month).unwrap(),
+            DateFormat::FullMonth => self.month_from_numeric(self.month).unwrap(),
             DateFormat::ShortMonth => {
                 let mut month_string = self.month_from_numeric(self.month).unwrap();
                 month_string.truncate(3);
@@ -54,7 +235,23 @@ impl SimpleDate {
         self.day.to_string()
     }
 
-    fn month_from_numeric(&self, month_numeric: u64) -> Result<String, String> {
+    // 1st, 2nd, 3rd, 4th, ... 11th, 12th, 13th, ... 21st
+    pub fn day_ordinal(&self) -> String {
+        let suffix = match (self.day % 10, self.day % 100) {
+            (_, 11..=13) => "th",
+            (1, _) => "st",
+            (2, _) => "nd",
+            (3, _) => "rd",
+            _ => "th",
+        };
+        format!("{}{}", self.day, suffix)
+    }
+
+    pub fn hour_minute(&self) -> String {
+        format!("{:02}:{:02}", self.hour, self.minute)
+    }
+
+    fn month_from_numeric(&self, month_numeric: i64) -> Result<String, String> {
         let selected_month = match month_numeric {
             1 => "January",
             2 => "February",
@@ -78,3 +275,118 @@ impl SimpleDate {
         Ok(selected_month.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ymd(date: &SimpleDate) -> (i64, i64, i64) {
+        (date.year, date.month, date.day)
+    }
+
+    fn is_leap_year(year: i64) -> bool {
+        (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+    }
+
+    fn days_in_month(year: i64, month: i64) -> i64 {
+        match month {
+            2 if is_leap_year(year) => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            _ => 31,
+        }
+    }
+
+    // Counts days from 1970-01-01 a year and month at a time, slow but plain
+    fn reference_days(year: i64, month: i64, day: i64) -> i64 {
+        let mut days = 0;
+        for y in year.min(1970)..year.max(1970) {
+            let year_days = if is_leap_year(y) { 366 } else { 365 };
+            days += if year < 1970 { -year_days } else { year_days };
+        }
+        for m in 1..month {
+            days += days_in_month(year, m);
+        }
+        days + day - 1
+    }
+
+    #[test]
+    fn known_days() {
+        for (days, date) in [
+            (0, (1970, 1, 1)),
+            (58, (1970, 2, 28)),
+            (59, (1970, 3, 1)),
+            (60, (1970, 3, 2)),
+            (789, (1972, 2, 29)),
+            (11016, (2000, 2, 29)),
+            (11017, (2000, 3, 1)),
+            (47540, (2100, 2, 28)),
+            (47541, (2100, 3, 1)),
+            (-1, (1969, 12, 31)),
+            (-719468, (0, 3, 1)),
+        ] {
+            assert_eq!(ymd(&SimpleDate::from_days(days)), date, "day {}", days);
+        }
+    }
+
+    #[test]
+    fn days_round_trip_against_the_reference() {
+        // Every 7th day from 1600 to 2400, crossing the eras either side of 1970
+        for days in (-135_000..157_000).step_by(7) {
+            let date = SimpleDate::from_days(days);
+            assert!((1..=12).contains(&date.month), "day {}", days);
+            assert!(
+                (1..=days_in_month(date.year, date.month)).contains(&date.day),
+                "day {}",
+                days
+            );
+            assert_eq!(
+                reference_days(date.year, date.month, date.day),
+                days,
+                "day {}",
+                days
+            );
+        }
+    }
+
+    #[test]
+    fn secs_before_the_epoch_count_back_from_midnight() {
+        let date = SimpleDate::from_secs(-60);
+        assert_eq!(ymd(&date), (1969, 12, 31));
+        assert_eq!(date.hour_minute(), "23:59");
+
+        let date = SimpleDate::from_secs(SECS_PER_DAY + 3 * SECS_PER_HOUR + 4 * SECS_PER_MINUTE);
+        assert_eq!(ymd(&date), (1970, 1, 2));
+        assert_eq!(date.hour_minute(), "03:04");
+    }
+
+    #[test]
+    fn ordinal_suffixes() {
+        for (day, ordinal) in [
+            (1, "1st"),
+            (2, "2nd"),
+            (3, "3rd"),
+            (4, "4th"),
+            (11, "11th"),
+            (12, "12th"),
+            (13, "13th"),
+            (21, "21st"),
+            (22, "22nd"),
+            (31, "31st"),
+        ] {
+            let date = SimpleDate {
+                day,
+                ..SimpleDate::from_days(0)
+            };
+            assert_eq!(date.day_ordinal(), ordinal);
+        }
+    }
+
+    #[test]
+    fn time_style_names() {
+        assert!(TimeStyle::parse("full-iso") == Some(TimeStyle::FullIso));
+        assert!(TimeStyle::parse("posix-long-iso") == Some(TimeStyle::LongIso));
+        assert!(TimeStyle::parse("locale") == Some(TimeStyle::Default));
+        assert!(TimeStyle::parse("+%Y").is_none());
+    }
+}
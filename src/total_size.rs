@@ -0,0 +1,259 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+#[cfg(target_os = "linux")]
+use std::os::linux::fs::MetadataExt;
+
+#[cfg(target_os = "macos")]
+use std::os::macos::fs::MetadataExt;
+
+#[cfg(all(unix, not(any(target_os = "linux", target_os = "macos"))))]
+use std::os::unix::fs::MetadataExt;
+
+use crate::size::ByteSize;
+
+const CACHE_HOME_ENV_NAME: &str = "XDG_CACHE_HOME";
+const HOME_ENV_NAME: &str = "HOME";
+const CACHE_DIR_NAME: &str = "rs";
+const CACHE_FILE_NAME: &str = "sizes.db";
+// First line of the cache file, so an older layout is read as empty
+const CACHE_HEADER: &str = "rs-sizes 1";
+
+// How far a cached size is believed. A directory's mtime only changes when
+// entries are added, removed or renamed in it, not when a file deep below it
+// is written to, so both can be stale after files are modified in place.
+#[derive(Clone, Copy)]
+pub enum CacheTrust {
+    // An unchanged directory keeps its whole cached size without reading it
+    Mtime,
+    // An unchanged directory keeps the size of the files directly in it, its
+    // subdirectories are still read and checked the same way
+    Shallow,
+}
+
+// What was worked out for a directory, keyed by its (dev, ino)
+struct CachedDir {
+    mtime: (i64, i64),
+    files_size: u64,
+    total_size: u64,
+}
+
+struct SizeCache {
+    trust: CacheTrust,
+    dirs: HashMap<(u64, u64), CachedDir>,
+    is_dirty: bool,
+}
+
+thread_local! {
+    // Loaded on first use and written back by save_cache
+    static SIZE_CACHE: RefCell<Option<SizeCache>> = const { RefCell::new(None) };
+}
+
+// $XDG_CACHE_HOME/rs/sizes.db, or under ~/.cache without it
+fn cache_path() -> Option<PathBuf> {
+    let cache_home = match env::var_os(CACHE_HOME_ENV_NAME) {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os(HOME_ENV_NAME)?).join(".cache"),
+    };
+    Some(cache_home.join(CACHE_DIR_NAME).join(CACHE_FILE_NAME))
+}
+
+fn mtime(meta: &fs::Metadata) -> (i64, i64) {
+    (meta.st_mtime(), meta.st_mtime_nsec())
+}
+
+// Lines of "dev ino mtime mtime_nsec files_size total_size". Anything that
+// doesn't parse is dropped, the cache can always be rebuilt.
+fn parse_cache(contents: &str) -> HashMap<(u64, u64), CachedDir> {
+    let mut lines = contents.lines();
+    if lines.next() != Some(CACHE_HEADER) {
+        return HashMap::new();
+    }
+
+    let mut dirs = HashMap::new();
+    for line in lines {
+        let fields: Vec<&str> = line.split(' ').collect();
+        let [dev, ino, secs, nsecs, files_size, total_size] = fields[..] else {
+            continue;
+        };
+        let parsed = (
+            dev.parse(),
+            ino.parse(),
+            secs.parse(),
+            nsecs.parse(),
+            files_size.parse(),
+            total_size.parse(),
+        );
+        if let (Ok(dev), Ok(ino), Ok(secs), Ok(nsecs), Ok(files_size), Ok(total_size)) = parsed {
+            dirs.insert(
+                (dev, ino),
+                CachedDir {
+                    mtime: (secs, nsecs),
+                    files_size,
+                    total_size,
+                },
+            );
+        }
+    }
+    dirs
+}
+
+impl SizeCache {
+    fn load(trust: CacheTrust) -> SizeCache {
+        let dirs = cache_path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|contents| parse_cache(&contents))
+            .unwrap_or_default();
+        SizeCache {
+            trust,
+            dirs,
+            is_dirty: false,
+        }
+    }
+
+    fn save(&self) -> Result<(), String> {
+        if !self.is_dirty {
+            return Ok(());
+        }
+        let path = cache_path().ok_or("rs: no cache directory, set XDG_CACHE_HOME or HOME")?;
+        let mut contents = format!("{}\n", CACHE_HEADER);
+        for ((dev, ino), dir) in &self.dirs {
+            contents.push_str(&format!(
+                "{} {} {} {} {} {}\n",
+                dev, ino, dir.mtime.0, dir.mtime.1, dir.files_size, dir.total_size
+            ));
+        }
+
+        // Written aside and renamed over, so a reader never sees half a file
+        let write = || -> std::io::Result<()> {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let partial = path.with_extension("db.partial");
+            fs::write(&partial, contents)?;
+            fs::rename(&partial, &path)
+        };
+        write().map_err(|err| format!("rs: cannot write cache '{}': {}", path.display(), err))
+    }
+}
+
+// Sums the apparent size of everything below the directory, without
+// following symlinks. Unreadable entries count as empty.
+fn walk(dir: &Path, meta: &fs::Metadata, cache: &mut Option<SizeCache>) -> u64 {
+    let key = (meta.st_dev(), meta.st_ino());
+    let cached = cache
+        .as_ref()
+        .and_then(|cache| cache.dirs.get(&key).map(|dir| (cache.trust, dir)))
+        .filter(|(_, cached)| cached.mtime == mtime(meta))
+        .map(|(trust, cached)| (trust, cached.files_size, cached.total_size));
+
+    let (files_size, subdirs_size) = match cached {
+        Some((CacheTrust::Mtime, _, total_size)) => return total_size,
+        Some((CacheTrust::Shallow, files_size, _)) => {
+            let subdirs_size = fs::read_dir(dir)
+                .map(|read_dir| {
+                    read_dir
+                        .filter_map(Result::ok)
+                        .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_dir()))
+                        .filter_map(|entry| {
+                            let meta = entry.metadata().ok()?;
+                            Some(walk(&entry.path(), &meta, cache))
+                        })
+                        .sum()
+                })
+                .unwrap_or(0);
+            (files_size, subdirs_size)
+        }
+        None => {
+            let (mut files_size, mut subdirs_size) = (0, 0);
+            for entry in fs::read_dir(dir)
+                .into_iter()
+                .flatten()
+                .filter_map(Result::ok)
+            {
+                let Ok(entry_meta) = fs::symlink_metadata(entry.path()) else {
+                    continue;
+                };
+                match entry_meta.is_dir() {
+                    true => subdirs_size += walk(&entry.path(), &entry_meta, cache),
+                    false => files_size += entry_meta.len(),
+                }
+            }
+            (files_size, subdirs_size)
+        }
+    };
+
+    let total_size = files_size + subdirs_size;
+    if let Some(cache) = cache {
+        cache.dirs.insert(
+            key,
+            CachedDir {
+                mtime: mtime(meta),
+                files_size,
+                total_size,
+            },
+        );
+        cache.is_dirty = true;
+    }
+    total_size
+}
+
+// The size of a directory's contents, all the way down, going through the
+// cache when it is given a trust level
+pub fn total_size(dir: &Path, meta: &fs::Metadata, trust: Option<CacheTrust>) -> ByteSize {
+    let Some(trust) = trust else {
+        return ByteSize(walk(dir, meta, &mut None));
+    };
+    SIZE_CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if cache.is_none() {
+            *cache = Some(SizeCache::load(trust));
+        }
+        ByteSize(walk(dir, meta, &mut cache))
+    })
+}
+
+// Writes back whatever the run added to the cache
+pub fn save_cache() -> Result<(), String> {
+    SIZE_CACHE.with(|cache| match &*cache.borrow() {
+        Some(cache) => cache.save(),
+        None => Ok(()),
+    })
+}
+
+// Removes the cache file, which isn't an error when there is none
+pub fn clear_cache() -> Result<(), String> {
+    let Some(path) = cache_path() else {
+        return Ok(());
+    };
+    match fs::remove_file(&path) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(format!(
+            "rs: cannot remove cache '{}': {}",
+            path.display(),
+            err
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unreadable_cache_lines_are_dropped() {
+        let dirs =
+            parse_cache("rs-sizes 1\n1 2 3 4 5 6\n1 3 x 4 5 6\n1 4 3 4 5\n\n7 8 -9 0 10 11\n");
+        assert_eq!(dirs.len(), 2);
+        let dir = &dirs[&(1, 2)];
+        assert_eq!((dir.mtime, dir.files_size, dir.total_size), ((3, 4), 5, 6));
+        assert_eq!(dirs[&(7, 8)].mtime, (-9, 0));
+        assert!(parse_cache("rs-sizes 0\n1 2 3 4 5 6\n").is_empty());
+        assert!(parse_cache("").is_empty());
+    }
+}
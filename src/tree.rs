@@ -0,0 +1,143 @@
+const BRANCH: &str = "├── ";
+const LAST_BRANCH: &str = "└── ";
+const CONTINUATION: &str = "│   ";
+const LAST_CONTINUATION: &str = "    ";
+
+pub struct Node<T> {
+    pub item: T,
+    pub is_dir: bool,
+    pub is_match: bool,
+    // Whether children were left out, by pruning or the depth limit
+    pub is_truncated: bool,
+    // Why a directory's children couldn't be read
+    pub error: Option<String>,
+    pub children: Vec<Node<T>>,
+}
+
+// Keeps the nodes that match and the directories needed to reach them, like
+// `tree -P`. A matched directory with nothing kept below it stays unless
+// is_prune_empty is set. Returns whether anything is left.
+pub fn prune<T>(nodes: &mut Vec<Node<T>>, is_prune_empty: bool) -> bool {
+    nodes.retain_mut(|node| {
+        let child_count = node.children.len();
+        let has_matches = node.is_dir && prune(&mut node.children, is_prune_empty);
+        node.is_truncated |= node.children.len() < child_count;
+        has_matches || (node.is_match && !(node.is_dir && is_prune_empty))
+    });
+    !nodes.is_empty()
+}
+
+// Draws the nodes one per line below whatever printed the root, each
+// labelled by the function
+pub fn render<T>(nodes: &[Node<T>], label: &impl Fn(&Node<T>) -> String) -> Vec<String> {
+    let mut lines = vec![];
+    render_level(nodes, "", label, &mut lines);
+    lines
+}
+
+fn render_level<T>(
+    nodes: &[Node<T>],
+    indent: &str,
+    label: &impl Fn(&Node<T>) -> String,
+    lines: &mut Vec<String>,
+) {
+    for (index, node) in nodes.iter().enumerate() {
+        let is_last = index == nodes.len() - 1;
+        let (branch, continuation) = match is_last {
+            true => (LAST_BRANCH, LAST_CONTINUATION),
+            false => (BRANCH, CONTINUATION),
+        };
+        lines.push(format!("{}{}{}", indent, branch, label(node)));
+        render_level(
+            &node.children,
+            &format!("{}{}", indent, continuation),
+            label,
+            lines,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(name: &'static str, is_match: bool) -> Node<&'static str> {
+        Node {
+            item: name,
+            is_dir: false,
+            is_match,
+            is_truncated: false,
+            error: None,
+            children: vec![],
+        }
+    }
+
+    fn dir(
+        name: &'static str,
+        is_match: bool,
+        children: Vec<Node<&'static str>>,
+    ) -> Node<&'static str> {
+        Node {
+            is_dir: true,
+            children,
+            ..file(name, is_match)
+        }
+    }
+
+    fn lines(nodes: &[Node<&'static str>]) -> Vec<String> {
+        render(nodes, &|node| node.item.to_string())
+    }
+
+    fn nested() -> Vec<Node<&'static str>> {
+        vec![
+            dir(
+                "a",
+                false,
+                vec![
+                    file("hit", true),
+                    dir("b", false, vec![file("miss", false)]),
+                ],
+            ),
+            dir("c", true, vec![]),
+            file("top", false),
+        ]
+    }
+
+    #[test]
+    fn prune_keeps_matches_and_their_ancestors() {
+        let mut nodes = nested();
+        assert!(prune(&mut nodes, false));
+        assert_eq!(lines(&nodes), ["├── a", "│   └── hit", "└── c"]);
+        assert!(nodes[0].is_truncated);
+        assert!(!nodes[1].is_truncated);
+    }
+
+    #[test]
+    fn prune_empty_drops_matched_directories_without_matches() {
+        let mut nodes = nested();
+        assert!(prune(&mut nodes, true));
+        assert_eq!(lines(&nodes), ["└── a", "    └── hit"]);
+    }
+
+    #[test]
+    fn prune_without_matches_leaves_nothing() {
+        let mut nodes = vec![dir("a", false, vec![file("miss", false)])];
+        assert!(!prune(&mut nodes, false));
+        assert!(nodes.is_empty());
+    }
+
+    #[test]
+    fn render_continues_open_branches() {
+        assert_eq!(
+            lines(&nested()),
+            [
+                "├── a",
+                "│   ├── hit",
+                "│   └── b",
+                "│       └── miss",
+                "├── c",
+                "└── top",
+            ]
+        );
+    }
+}
@@ -3,19 +3,19 @@ use std::fs;
 const USER_DATABASE_PATH: &str = "/etc/passwd";
 const USER_GROUP_PATH: &str = "/etc/group";
 
+// The name on the line whose id, the third field, is the given one. Both
+// /etc/passwd and /etc/group start "name:password:id:".
 fn get_name_from_db(id: u32, db_string: String) -> String {
-    let mut name = String::new();
+    let id = id.to_string();
     for line in db_string.lines() {
-        if line.contains(format!(":{}:", id).as_str()) {
-            for ch in line.chars() {
-                if ch == ':' {
-                    return name;
-                }
-                name.push(ch);
+        let mut fields = line.split(':');
+        if let (Some(name), Some(line_id)) = (fields.next(), fields.nth(1)) {
+            if line_id == id {
+                return name.to_string();
             }
         }
     }
-    name
+    String::new()
 }
 
 pub fn get_by_uid(uid: u32) -> Result<String, String> {
@@ -0,0 +1,88 @@
+use std::cmp::Ordering;
+
+// Splits off the leading run of digits, or of anything but digits
+fn split_run(bytes: &[u8], is_digits: bool) -> (&[u8], &[u8]) {
+    let end = bytes
+        .iter()
+        .position(|byte| byte.is_ascii_digit() != is_digits)
+        .unwrap_or(bytes.len());
+    bytes.split_at(end)
+}
+
+// Compares runs of digits by their value, of any length. Leading zeros are
+// left out, after which the longer number is the bigger one.
+fn cmp_number(a: &[u8], b: &[u8]) -> Ordering {
+    let a = &a[a.iter().position(|digit| *digit != b'0').unwrap_or(a.len())..];
+    let b = &b[b.iter().position(|digit| *digit != b'0').unwrap_or(b.len())..];
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+}
+
+// Orders names like `ls -v`: runs of text compare byte by byte and runs of
+// digits by value, so "file2" comes before "file10" and "v1.9.0" before
+// "v1.10.0". Names that only differ in leading zeros fall back to comparing
+// their bytes.
+pub fn compare(a: &str, b: &str) -> Ordering {
+    let (mut a_rest, mut b_rest) = (a.as_bytes(), b.as_bytes());
+    while !a_rest.is_empty() || !b_rest.is_empty() {
+        let (a_text, a_after) = split_run(a_rest, false);
+        let (b_text, b_after) = split_run(b_rest, false);
+        let (a_digits, a_after) = split_run(a_after, true);
+        let (b_digits, b_after) = split_run(b_after, true);
+        let ordering = a_text
+            .cmp(b_text)
+            .then_with(|| cmp_number(a_digits, b_digits));
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+        (a_rest, b_rest) = (a_after, b_after);
+    }
+    a.cmp(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Each name sorts before the next
+    fn assert_ascending(names: &[&str]) {
+        for pair in names.windows(2) {
+            assert_eq!(compare(pair[0], pair[1]), Ordering::Less, "{:?}", pair);
+            assert_eq!(compare(pair[1], pair[0]), Ordering::Greater, "{:?}", pair);
+        }
+    }
+
+    #[test]
+    fn digit_runs_compare_by_value() {
+        assert_ascending(&["file1", "file2", "file10", "file11", "file100"]);
+        assert_ascending(&["a", "a1", "a1b", "a2", "b"]);
+    }
+
+    #[test]
+    fn embedded_versions_compare_part_by_part() {
+        assert_ascending(&["v1.9.0", "v1.10.0", "v1.10.1", "v2.0.0"]);
+        assert_ascending(&["lib-2.9.so", "lib-2.10.so", "lib-10.0.so"]);
+    }
+
+    #[test]
+    fn pure_digits_compare_as_numbers() {
+        assert_ascending(&["0", "1", "2", "9", "10", "99", "100"]);
+        // Longer than any integer type
+        assert_ascending(&["99999999999999999999999", "100000000000000000000000"]);
+    }
+
+    #[test]
+    fn leading_zeros_fall_back_to_the_bytes() {
+        assert_ascending(&["file01", "file2", "file010"]);
+        // Equal values, so only the byte comparison tells them apart
+        assert_ascending(&["file001", "file01", "file1"]);
+        assert_ascending(&["0", "00"]);
+        assert_eq!(compare("file007", "file007"), Ordering::Equal);
+    }
+
+    #[test]
+    fn text_runs_compare_byte_by_byte() {
+        assert_ascending(&["B1", "a1", "a1.txt", "a1b"]);
+        assert_eq!(compare("", ""), Ordering::Equal);
+        assert_ascending(&["", "0", "a"]);
+    }
+}
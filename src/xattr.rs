@@ -0,0 +1,113 @@
+use std::{ffi::CString, os::unix::ffi::OsStrExt, path::Path};
+
+#[cfg(target_os = "linux")]
+const ACL_XATTR_NAME: &str = "system.posix_acl_access";
+#[cfg(all(feature = "selinux", target_os = "linux"))]
+const SELINUX_XATTR_NAME: &str = "security.selinux";
+
+#[cfg(target_os = "macos")]
+const XATTR_NOFOLLOW: std::os::raw::c_int = 0x0001;
+
+#[cfg(target_os = "linux")]
+mod sys {
+    use std::os::raw::{c_char, c_void};
+
+    extern "C" {
+        pub fn lgetxattr(
+            path: *const c_char,
+            name: *const c_char,
+            value: *mut c_void,
+            size: usize,
+        ) -> isize;
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod sys {
+    use std::os::raw::{c_char, c_int};
+
+    extern "C" {
+        pub fn listxattr(
+            path: *const c_char,
+            names: *mut c_char,
+            size: usize,
+            options: c_int,
+        ) -> isize;
+    }
+}
+
+// What ls puts after the mode string: "+" for a file with an access ACL on
+// Linux, "@" for one with extended attributes on macOS. One syscall, not
+// following symlinks. Anything that fails, like a filesystem without
+// xattrs, means no mark.
+#[cfg(target_os = "linux")]
+pub fn mode_mark(path: &Path) -> &'static str {
+    let (Ok(c_path), Ok(c_name)) = (
+        CString::new(path.as_os_str().as_bytes()),
+        CString::new(ACL_XATTR_NAME),
+    ) else {
+        return "";
+    };
+    // SAFETY: both strings are NUL terminated, a null buffer asks for the size
+    let size = unsafe { sys::lgetxattr(c_path.as_ptr(), c_name.as_ptr(), std::ptr::null_mut(), 0) };
+    match size >= 0 {
+        true => "+",
+        false => "",
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub fn mode_mark(path: &Path) -> &'static str {
+    let Ok(c_path) = CString::new(path.as_os_str().as_bytes()) else {
+        return "";
+    };
+    // SAFETY: the path is NUL terminated, a null buffer asks for the size
+    let size = unsafe { sys::listxattr(c_path.as_ptr(), std::ptr::null_mut(), 0, XATTR_NOFOLLOW) };
+    match size > 0 {
+        true => "@",
+        false => "",
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn mode_mark(_path: &Path) -> &'static str {
+    ""
+}
+
+// The SELinux context of the entry, e.g. "unconfined_u:object_r:user_home_t:s0",
+// or None where there is none, SELinux is off or the kernel has no xattrs
+#[cfg(all(feature = "selinux", target_os = "linux"))]
+pub fn security_context(path: &Path) -> Option<String> {
+    let c_path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let c_name = CString::new(SELINUX_XATTR_NAME).ok()?;
+    // SAFETY: both strings are NUL terminated, a null buffer asks for the size
+    let size = unsafe { sys::lgetxattr(c_path.as_ptr(), c_name.as_ptr(), std::ptr::null_mut(), 0) };
+    if size <= 0 {
+        return None;
+    }
+    let mut value = vec![0u8; size as usize];
+    // SAFETY: value has room for the size lgetxattr just reported
+    let read = unsafe {
+        sys::lgetxattr(
+            c_path.as_ptr(),
+            c_name.as_ptr(),
+            value.as_mut_ptr().cast(),
+            value.len(),
+        )
+    };
+    if read <= 0 {
+        return None;
+    }
+    value.truncate(read as usize);
+    // Stored with the C string's NUL
+    if value.last() == Some(&0) {
+        value.pop();
+    }
+    String::from_utf8(value).ok()
+}
+
+// Only Linux has SELinux
+#[cfg(all(feature = "selinux", not(target_os = "linux")))]
+pub fn security_context(_path: &Path) -> Option<String> {
+    None
+}
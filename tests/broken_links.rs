@@ -0,0 +1,76 @@
+mod common;
+
+use common::Fixture;
+
+fn links() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        .file("file", b"")
+        .symlink("file", "working")
+        .symlink("missing", "relative")
+        .dir("sub/deeper")
+        // Resolved from the link's directory, not the working directory
+        .symlink("../file", "sub/up")
+        .symlink("../missing", "sub/dangling")
+        .symlink("/nonexistent/rs-test/target", "sub/deeper/absolute")
+        .symlink("loop", "sub/loop");
+    fixture
+}
+
+#[test]
+fn only_dangling_links_are_listed() {
+    let fixture = links();
+    assert_eq!(
+        fixture.lines(["-1", "--broken-links"]),
+        ["relative -> missing"]
+    );
+    assert_eq!(
+        fixture.lines(["-1", "--broken-links", "sub"]),
+        ["dangling -> ../missing", "loop -> loop"]
+    );
+}
+
+#[test]
+fn absolute_targets() {
+    let fixture = links();
+    fixture.symlink(fixture.path("file").to_str().unwrap(), "sub/deeper/present");
+    assert_eq!(
+        fixture.lines(["-1", "--broken-links", "sub/deeper"]),
+        ["absolute -> /nonexistent/rs-test/target"]
+    );
+}
+
+#[test]
+fn a_link_to_itself_is_broken() {
+    let fixture = Fixture::new();
+    fixture.symlink("self", "self");
+    assert_eq!(fixture.lines(["-1", "--broken-links"]), ["self -> self"]);
+}
+
+#[test]
+fn recursion_enters_the_directories_left_out() {
+    let fixture = links();
+    assert_eq!(
+        fixture.lines(["-1R", "--broken-links"]),
+        [
+            ".:",
+            "relative -> missing",
+            "",
+            "./sub:",
+            "dangling -> ../missing",
+            "loop -> loop",
+            "",
+            "./sub/deeper:",
+            "absolute -> /nonexistent/rs-test/target",
+        ]
+    );
+}
+
+#[test]
+fn finding_none_still_succeeds() {
+    let fixture = Fixture::new();
+    fixture.file("file", b"").symlink("file", "working");
+    let output = fixture.run(["-1", "--broken-links"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "");
+}
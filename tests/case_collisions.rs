@@ -0,0 +1,54 @@
+mod common;
+
+use common::Fixture;
+
+fn names() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        .file("README.md", b"")
+        .file("readme.md", b"")
+        .file("caf\u{e9}", b"")
+        .file("cafe\u{301}", b"")
+        .file("other", b"");
+    fixture
+}
+
+#[test]
+fn each_group_is_warned_about_and_fails_the_run() {
+    let fixture = names();
+    let output = fixture.run(["-1", "--check-case-collisions"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "rs: names differ only in case: ./README.md, ./readme.md\n\
+         rs: names differ only in case: ./cafe\u{301}, ./caf\u{e9}\n\
+         rs: 2 group(s) of names differ only in case\n"
+    );
+    // The listing itself is complete
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "README.md\ncafe\u{301}\ncaf\u{e9}\nother\nreadme.md\n"
+    );
+}
+
+#[test]
+fn colliding_names_are_marked() {
+    let fixture = names();
+    let output = fixture.run(["-1", "--check-case-collisions", "--color=always"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let marked: Vec<&str> = stdout
+        .lines()
+        .filter(|line| line.starts_with('\x1b'))
+        .collect();
+    assert_eq!(marked.len(), 4);
+    assert!(stdout.contains("\nother\n"));
+}
+
+#[test]
+fn no_collisions_pass() {
+    let fixture = Fixture::new();
+    fixture.file("README.md", b"").file("readme.txt", b"");
+    let output = fixture.run(["--check-case-collisions"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stderr.is_empty());
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use common::Fixture;
+
+fn listing() -> Fixture {
+    let fixture = Fixture::new();
+    fixture.dir("dir").file("file", b"");
+    fixture
+}
+
+#[test]
+fn override_replaces_the_class_escape() {
+    let fixture = listing();
+    assert_eq!(
+        fixture.lines(["-1", "--color=always"]),
+        ["\x1b[34;1mdir\x1b[0m", "file"]
+    );
+    assert_eq!(
+        fixture.lines(["-1", "--color=always", "--color-override", "dir=bold,green"]),
+        ["\x1b[1;32mdir\x1b[0m", "file"]
+    );
+}
+
+#[test]
+fn override_wins_over_ls_colors() {
+    let fixture = listing();
+    let output = fixture
+        .command(["-1", "--color=always", "--color-override=dir=#102030"])
+        .env("LS_COLORS", "di=01;35")
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "\x1b[38;2;16;32;48mdir\x1b[0m\nfile\n"
+    );
+}
+
+#[test]
+fn the_last_override_of_a_class_wins() {
+    let fixture = listing();
+    assert_eq!(
+        fixture.lines([
+            "-1",
+            "--color=always",
+            "--color-override=dir=red",
+            "--color-override=dir=cyan",
+            "--color-override=file=yellow",
+        ]),
+        ["\x1b[36mdir\x1b[0m", "\x1b[33mfile\x1b[0m"]
+    );
+}
+
+#[test]
+fn bad_overrides_name_the_token() {
+    let fixture = listing();
+    for (value, error) in [
+        (
+            "dir",
+            "invalid argument 'dir' for '--color-override', expected CLASS=SPEC",
+        ),
+        (
+            "folder=red",
+            "unknown style class 'folder' for '--color-override'",
+        ),
+        (
+            "dir=bold,purple",
+            "invalid color 'purple' for '--color-override'",
+        ),
+    ] {
+        let output = fixture.run(["--color-override", value]);
+        assert_eq!(output.status.code(), Some(2));
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert_eq!(
+            stderr.lines().next(),
+            Some(format!("rs: {}", error).as_str())
+        );
+    }
+}
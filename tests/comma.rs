@@ -0,0 +1,58 @@
+mod common;
+
+use common::Fixture;
+
+fn names() -> Fixture {
+    let fixture = Fixture::new();
+    for name in ["alpha", "beta", "delta", "epsilon", "eta", "gamma", "iota"] {
+        fixture.file(name, b"");
+    }
+    fixture
+}
+
+#[test]
+fn wraps_at_the_width() {
+    let fixture = names();
+    assert_eq!(
+        fixture.stdout(["-m", "-w", "26"]),
+        "alpha, beta, delta,\nepsilon, eta, gamma, iota\n"
+    );
+    assert_eq!(
+        fixture.stdout(["-m", "-w", "80"]),
+        "alpha, beta, delta, epsilon, eta, gamma, iota\n"
+    );
+}
+
+#[test]
+fn long_names_get_their_own_line() {
+    let fixture = names();
+    fixture.file("a-name-longer-than-the-width", b"");
+    assert_eq!(
+        fixture.stdout(["-m", "-w", "20", "-I", "[bdeg]*"]),
+        "a-name-longer-than-the-width,\nalpha, iota\n"
+    );
+}
+
+#[test]
+fn the_width_comes_from_columns_like_the_grid() {
+    let fixture = names();
+    let output = fixture
+        .command(["-m"])
+        .env("COLUMNS", "26")
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        fixture.stdout(["-m", "-w", "26"])
+    );
+    // -w wins over COLUMNS
+    let output = fixture
+        .command(["-m", "-w", "80"])
+        .env("COLUMNS", "26")
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "alpha, beta, delta, epsilon, eta, gamma, iota\n"
+    );
+}
@@ -0,0 +1,177 @@
+// Just enough of a JSON parser to read back what rs prints: no floats, and
+// only the escapes rs writes
+
+#[derive(Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(u64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    // The member with the key, panicking when there's none
+    pub fn get(&self, key: &str) -> &Value {
+        match self {
+            Value::Object(members) => members
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value)
+                .unwrap_or_else(|| panic!("no member '{}'", key)),
+            _ => panic!("not an object"),
+        }
+    }
+
+    pub fn keys(&self) -> Vec<&str> {
+        match self {
+            Value::Object(members) => members.iter().map(|(key, _)| key.as_str()).collect(),
+            _ => panic!("not an object"),
+        }
+    }
+
+    pub fn items(&self) -> &[Value] {
+        match self {
+            Value::Array(items) => items,
+            _ => panic!("not an array"),
+        }
+    }
+
+    pub fn str(&self) -> &str {
+        match self {
+            Value::String(text) => text,
+            _ => panic!("not a string"),
+        }
+    }
+}
+
+pub fn parse(text: &str) -> Value {
+    let mut parser = Parser {
+        chars: text.chars().collect(),
+        index: 0,
+    };
+    let value = parser.value();
+    parser.skip_whitespace();
+    assert_eq!(parser.index, parser.chars.len(), "trailing characters");
+    value
+}
+
+struct Parser {
+    chars: Vec<char>,
+    index: usize,
+}
+
+impl Parser {
+    fn skip_whitespace(&mut self) {
+        while self
+            .chars
+            .get(self.index)
+            .is_some_and(|ch| ch.is_whitespace())
+        {
+            self.index += 1;
+        }
+    }
+
+    fn next(&mut self) -> char {
+        let ch = self.chars[self.index];
+        self.index += 1;
+        ch
+    }
+
+    fn expect(&mut self, expected: &str) {
+        for ch in expected.chars() {
+            assert_eq!(self.next(), ch, "at {}", self.index);
+        }
+    }
+
+    fn value(&mut self) -> Value {
+        self.skip_whitespace();
+        match self.chars[self.index] {
+            'n' => {
+                self.expect("null");
+                Value::Null
+            }
+            't' => {
+                self.expect("true");
+                Value::Bool(true)
+            }
+            'f' => {
+                self.expect("false");
+                Value::Bool(false)
+            }
+            '"' => Value::String(self.string()),
+            '[' => {
+                self.next();
+                let mut items = vec![];
+                while !self.is_closed(']') {
+                    items.push(self.value());
+                }
+                Value::Array(items)
+            }
+            '{' => {
+                self.next();
+                let mut members = vec![];
+                while !self.is_closed('}') {
+                    self.skip_whitespace();
+                    let key = self.string();
+                    self.skip_whitespace();
+                    self.expect(":");
+                    members.push((key, self.value()));
+                }
+                Value::Object(members)
+            }
+            _ => {
+                let start = self.index;
+                while self
+                    .chars
+                    .get(self.index)
+                    .is_some_and(|ch| ch.is_ascii_digit())
+                {
+                    self.index += 1;
+                }
+                let digits: String = self.chars[start..self.index].iter().collect();
+                Value::Number(digits.parse().unwrap())
+            }
+        }
+    }
+
+    // Steps over a separating comma, or the closing bracket at the end
+    fn is_closed(&mut self, close: char) -> bool {
+        self.skip_whitespace();
+        match self.chars[self.index] {
+            ch if ch == close => {
+                self.next();
+                true
+            }
+            ',' => {
+                self.next();
+                false
+            }
+            _ => false,
+        }
+    }
+
+    fn string(&mut self) -> String {
+        self.expect("\"");
+        let mut text = String::new();
+        loop {
+            match self.next() {
+                '"' => return text,
+                '\\' => match self.next() {
+                    'n' => text.push('\n'),
+                    'r' => text.push('\r'),
+                    't' => text.push('\t'),
+                    'b' => text.push('\u{8}'),
+                    'f' => text.push('\u{c}'),
+                    'u' => {
+                        let hex: String = (0..4).map(|_| self.next()).collect();
+                        text.push(char::from_u32(u32::from_str_radix(&hex, 16).unwrap()).unwrap());
+                    }
+                    ch => text.push(ch),
+                },
+                ch => text.push(ch),
+            }
+        }
+    }
+}
@@ -0,0 +1,149 @@
+// Helpers shared by the tests that run the rs binary. Each test binary uses
+// a different part of them.
+#![allow(dead_code)]
+
+pub mod json;
+
+use std::{
+    env,
+    ffi::OsStr,
+    fs::{self, File, FileTimes},
+    os::unix::fs::{symlink, PermissionsExt},
+    path::PathBuf,
+    process::{self, Command, Output},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, SystemTime},
+};
+
+static NEXT_FIXTURE: AtomicUsize = AtomicUsize::new(0);
+
+// A scratch directory of files to list, removed when dropped. rs runs with
+// it as the working directory, in UTC and without any colors or widths from
+// the environment.
+pub struct Fixture {
+    pub root: PathBuf,
+}
+
+impl Fixture {
+    pub fn new() -> Fixture {
+        let root = env::temp_dir().join(format!(
+            "rs-test-{}-{}",
+            process::id(),
+            NEXT_FIXTURE.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        Fixture { root }
+    }
+
+    pub fn path(&self, relative: &str) -> PathBuf {
+        self.root.join(relative)
+    }
+
+    pub fn dir(&self, relative: &str) -> &Fixture {
+        fs::create_dir_all(self.path(relative)).unwrap();
+        self
+    }
+
+    // A file with the given contents, creating the directories above it
+    pub fn file(&self, relative: &str, contents: &[u8]) -> &Fixture {
+        let path = self.path(relative);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+        self
+    }
+
+    // A file of len bytes
+    pub fn sized(&self, relative: &str, len: usize) -> &Fixture {
+        self.file(relative, &vec![b'x'; len])
+    }
+
+    pub fn symlink(&self, target: &str, relative: &str) -> &Fixture {
+        symlink(target, self.path(relative)).unwrap();
+        self
+    }
+
+    pub fn hard_link(&self, original: &str, relative: &str) -> &Fixture {
+        fs::hard_link(self.path(original), self.path(relative)).unwrap();
+        self
+    }
+
+    // Sets the permission bits, like chmod
+    pub fn mode(&self, relative: &str, mode: u32) -> &Fixture {
+        fs::set_permissions(self.path(relative), fs::Permissions::from_mode(mode)).unwrap();
+        self
+    }
+
+    // Sets the modification time to secs after the epoch
+    pub fn mtime(&self, relative: &str, secs: u64) -> &Fixture {
+        let time = SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+        File::open(self.path(relative))
+            .unwrap()
+            .set_times(FileTimes::new().set_modified(time).set_accessed(time))
+            .unwrap();
+        self
+    }
+
+    pub fn command<I, S>(&self, args: I) -> Command
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_rs"));
+        command
+            .args(args)
+            .current_dir(&self.root)
+            .env("TZ", "UTC")
+            .env("LC_ALL", "C")
+            .env("XDG_CACHE_HOME", self.root.with_extension("cache"));
+        for name in [
+            "LS_COLORS",
+            "COLUMNS",
+            "COLORFGBG",
+            "TIME_STYLE",
+            "QUOTING_STYLE",
+        ] {
+            command.env_remove(name);
+        }
+        command
+    }
+
+    pub fn run<I, S>(&self, args: I) -> Output
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.command(args).output().unwrap()
+    }
+
+    // What rs prints, failing the test if it doesn't succeed
+    pub fn stdout<I, S>(&self, args: I) -> String
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let output = self.run(args);
+        assert!(
+            output.status.success(),
+            "rs failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    // The printed lines
+    pub fn lines<I, S>(&self, args: I) -> Vec<String>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.stdout(args).lines().map(String::from).collect()
+    }
+}
+
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+        let _ = fs::remove_dir_all(self.root.with_extension("cache"));
+    }
+}
@@ -0,0 +1,70 @@
+mod common;
+
+use std::{
+    process::Command,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use common::Fixture;
+
+// GNU ls run the same way as rs, or None when it isn't installed
+fn gnu_ls(fixture: &Fixture, args: &[&str]) -> Option<String> {
+    let output = Command::new("ls")
+        .args(args)
+        .current_dir(&fixture.root)
+        .env("TZ", "UTC")
+        .env("LC_ALL", "C")
+        .env_remove("LS_COLORS")
+        .env_remove("TIME_STYLE")
+        .env_remove("QUOTING_STYLE")
+        .output()
+        .ok()?;
+    let is_gnu = Command::new("ls")
+        .arg("--version")
+        .output()
+        .is_ok_and(|version| String::from_utf8_lossy(&version.stdout).contains("GNU"));
+    (output.status.success() && is_gnu).then(|| String::from_utf8(output.stdout).unwrap())
+}
+
+#[test]
+fn long_output_matches_gnu_ls() {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let fixture = Fixture::new();
+    fixture
+        .sized("small", 3)
+        .sized("larger file", 12_345)
+        .sized("recent", 70_000)
+        .file("before-1970", b"")
+        .dir("dir")
+        .symlink("small", "link")
+        .symlink("missing", "dangling")
+        .mtime("small", 1_000_000_000)
+        .mtime("larger file", 1_600_000_000)
+        .mtime("recent", now - 86_400)
+        .mtime("dir", 1_500_000_000);
+    // Before the epoch, which can't be given as unsigned seconds
+    Command::new("touch")
+        .args(["-d", "1960-03-01", "before-1970"])
+        .current_dir(&fixture.root)
+        .status()
+        .unwrap();
+
+    for args in [
+        &["-l"][..],
+        &["-la"],
+        &["-ls"],
+        &["-ln"],
+        &["-lS"],
+        &["-lt"],
+    ] {
+        let Some(expected) = gnu_ls(&fixture, args) else {
+            eprintln!("GNU ls isn't installed, skipping the comparison");
+            return;
+        };
+        let rs_args: Vec<&str> = args.iter().copied().chain(["--compat=gnu"]).collect();
+        assert_eq!(fixture.stdout(&rs_args), expected, "ls {}", args.join(" "));
+    }
+}
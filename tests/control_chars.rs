@@ -0,0 +1,52 @@
+mod common;
+
+use common::Fixture;
+
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        .file("cr\rx", b"")
+        .file("new\nline", b"")
+        .file("tab\there", b"")
+        .file("z", b"");
+    fixture
+}
+
+#[test]
+fn control_characters_keep_long_rows_aligned() {
+    let lines = fixture().lines(["-l"]);
+    assert_eq!(lines.len(), 5);
+    let names: Vec<_> = lines[1..]
+        .iter()
+        .map(|line| line.split(' ').next_back().unwrap())
+        .collect();
+    assert_eq!(names, ["cr?x", "new?line", "tab?here", "z"]);
+    let name_starts: Vec<_> = lines[1..]
+        .iter()
+        .map(|line| line.len() - line.split(' ').next_back().unwrap().len())
+        .collect();
+    assert!(name_starts.windows(2).all(|pair| pair[0] == pair[1]));
+}
+
+#[test]
+fn control_characters_take_one_column_in_the_grid() {
+    let fixture = fixture();
+    assert_eq!(
+        fixture.stdout(["-w", "18"]),
+        "cr?x      tab?here\nnew?line  z\n"
+    );
+    assert_eq!(
+        fixture.stdout(["-w", "17"]),
+        "cr?x\nnew?line\ntab?here\nz\n"
+    );
+}
+
+#[test]
+fn control_characters_take_one_column_in_comma_lists() {
+    let fixture = fixture();
+    assert_eq!(fixture.stdout(["-m"]), "cr?x, new?line, tab?here, z\n");
+    assert_eq!(
+        fixture.stdout(["-m", "-w", "20"]),
+        "cr?x, new?line,\ntab?here, z\n"
+    );
+}
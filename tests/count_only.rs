@@ -0,0 +1,81 @@
+mod common;
+
+use common::Fixture;
+
+fn tree() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        .file("a.rs", b"")
+        .file("b.txt", b"")
+        .file(".hidden", b"")
+        .file("backup~", b"")
+        .file("src/lib.rs", b"")
+        .file("src/main.rs", b"")
+        .file("src/nested/deep.rs", b"")
+        .file("docs/guide.md", b"");
+    fixture
+}
+
+// How many entries the listing shows, leaving out -R's headers and blank lines
+fn listed(fixture: &Fixture, args: &[&str]) -> usize {
+    let mut command = vec!["-1"];
+    command.extend(args);
+    fixture
+        .lines(command)
+        .iter()
+        .filter(|line| !line.is_empty() && !line.ends_with(':'))
+        .count()
+}
+
+fn counted(fixture: &Fixture, args: &[&str]) -> usize {
+    let mut command = vec!["--count-only"];
+    command.extend(args);
+    fixture.stdout(command).trim().parse().unwrap()
+}
+
+#[test]
+fn counts_what_the_listing_shows() {
+    let fixture = tree();
+    for args in [
+        &[][..],
+        &["-a"],
+        &["-A"],
+        &["-B"],
+        &["-I", "*.rs"],
+        &["--regex", "\\.rs$"],
+        &["--only-files"],
+        &["-R"],
+        &["-Ra"],
+        &["-R", "--regex", "\\.rs$"],
+        &["-R", "--only-files"],
+        &["-R", "-I", "nested"],
+        &["src", "-R"],
+    ] {
+        assert_eq!(
+            counted(&fixture, args),
+            listed(&fixture, args),
+            "{:?}",
+            args
+        );
+    }
+}
+
+#[test]
+fn recursion_counts_below_directories_the_filters_hide() {
+    let fixture = tree();
+    assert_eq!(counted(&fixture, &["-R", "--regex", "\\.rs$"]), 4);
+    assert_eq!(counted(&fixture, &["-R", "--only-files"]), 7);
+}
+
+#[test]
+fn a_count_per_operand_and_a_total() {
+    let fixture = tree();
+    assert_eq!(
+        fixture.lines(["--count-only", "src", "docs", "a.rs"]),
+        ["3 src", "1 docs", "1 a.rs", "5 total"]
+    );
+    assert_eq!(
+        fixture.lines(["--count-only", "-R", "src", "docs"]),
+        ["4 src", "1 docs", "5 total"]
+    );
+}
@@ -0,0 +1,103 @@
+mod common;
+
+use std::fs;
+
+use common::Fixture;
+
+fn links() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        .sized("big", 3000)
+        .sized("small", 10)
+        .dir("dir")
+        .mtime("big", 1_000_000)
+        .mtime("small", 2_000_000)
+        .symlink("big", "to-big")
+        .symlink("small", "to-small")
+        .symlink("dir", "to-dir")
+        .symlink("nowhere", "dangling")
+        .symlink("loop-b", "loop-a")
+        .symlink("loop-a", "loop-b");
+    fixture
+}
+
+// The type character and size of each listed name
+fn kinds_and_sizes(lines: &[String]) -> Vec<(char, String, String)> {
+    lines
+        .iter()
+        .filter(|line| !line.starts_with("total"))
+        .map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            (
+                line.chars().next().unwrap(),
+                fields[4].to_string(),
+                fields[7].to_string(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn links_describe_their_targets() {
+    let fixture = links();
+    let lines = fixture.lines(["-lLd", "to-big", "to-small", "to-dir"]);
+    let dir_size = fs::metadata(fixture.path("dir")).unwrap().len();
+    assert_eq!(
+        kinds_and_sizes(&lines),
+        [
+            ('-', "3000".to_string(), "to-big".to_string()),
+            ('d', dir_size.to_string(), "to-dir".to_string()),
+            ('-', "10".to_string(), "to-small".to_string()),
+        ]
+    );
+    // Without -L the links describe themselves
+    let lines = fixture.lines(["-l", "-I", "[!t]*", "-I", "to-dir"]);
+    assert_eq!(
+        kinds_and_sizes(&lines),
+        [
+            ('l', "3".to_string(), "to-big".to_string()),
+            ('l', "5".to_string(), "to-small".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn dangling_links_and_loops_fall_back_to_the_link() {
+    let fixture = links();
+    let output = fixture.run(["-lL", "-I", "[bst]*", "-I", "dir"]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("rs: cannot access './dangling': No such file or directory"));
+    assert!(stderr.contains("rs: cannot access './loop-a': Too many levels of symbolic links"));
+    assert!(stderr.contains("rs: cannot access './loop-b': Too many levels of symbolic links"));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let names: Vec<(char, &str)> = stdout
+        .lines()
+        .skip(1)
+        .map(|line| {
+            (
+                line.chars().next().unwrap(),
+                line.rsplit(' ').next().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(names, [('l', "nowhere"), ('l', "loop-b"), ('l', "loop-a")]);
+}
+
+#[test]
+fn sorting_goes_by_the_targets() {
+    let fixture = links();
+    let args = ["-1", "-I", "[bsdl]*", "-I", "to-dir"];
+    assert_eq!(
+        fixture.lines([&args[..], &["-LS"]].concat()),
+        ["to-big", "to-small"]
+    );
+    assert_eq!(
+        fixture.lines([&args[..], &["-Lt"]].concat()),
+        ["to-small", "to-big"]
+    );
+    // The links themselves: to-small has the longer target, and was made last
+    assert_eq!(
+        fixture.lines([&args[..], &["-S"]].concat()),
+        ["to-small", "to-big"]
+    );
+}
@@ -0,0 +1,53 @@
+// The device column decodes dev_t as Linux does
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use common::Fixture;
+use std::process::Command;
+
+// What stat prints for the file with the format, like %d or %i
+fn stat(fixture: &Fixture, format: &str, relative: &str) -> String {
+    let output = Command::new("stat")
+        .args(["-c", format])
+        .arg(fixture.path(relative))
+        .output()
+        .unwrap();
+    String::from_utf8(output.stdout).unwrap().trim().to_string()
+}
+
+// major:minor of a decimal dev_t, as glibc splits it on Linux
+fn major_minor(dev: &str) -> String {
+    let dev: u64 = dev.parse().unwrap();
+    let major = ((dev >> 8) & 0xfff) | ((dev >> 32) & !0xfff);
+    let minor = (dev & 0xff) | ((dev >> 12) & !0xff);
+    format!("{}:{}", major, minor)
+}
+
+#[test]
+fn device_column_matches_stat() {
+    let fixture = Fixture::new();
+    fixture.file("file", b"").dir("dir");
+    let lines = fixture.lines(["-l", "--device"]);
+    for (line, name) in lines[1..].iter().zip(["dir", "file"]) {
+        let device = line.split_whitespace().next().unwrap();
+        assert_eq!(device, major_minor(&stat(&fixture, "%d", name)), "{}", line);
+    }
+}
+
+#[test]
+fn device_and_inode_identify_the_file() {
+    let fixture = Fixture::new();
+    fixture.file("file", b"").hard_link("file", "link");
+    let lines = fixture.lines(["-li", "--device"]);
+    let ids: Vec<(&str, &str)> = lines[1..]
+        .iter()
+        .map(|line| {
+            let mut cells = line.split_whitespace();
+            (cells.next().unwrap(), cells.next().unwrap())
+        })
+        .collect();
+    let inode = stat(&fixture, "%i", "file");
+    let device = major_minor(&stat(&fixture, "%d", "file"));
+    assert_eq!(ids, [(inode.as_str(), device.as_str()); 2]);
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use std::fs;
+
+use common::Fixture;
+
+const COLUMNS: [&str; 4] = ["-l", "--no-permissions", "--no-user", "--no-time"];
+
+fn listing() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        .sized("big", 5000)
+        .sized("small", 10)
+        .file("dir/a", b"")
+        .file("dir/b", b"")
+        .dir("dir/sub");
+    fixture
+}
+
+// The size and name of each entry, leaving out the total and link counts
+fn sizes(fixture: &Fixture, args: &[&str]) -> Vec<String> {
+    fixture
+        .lines(COLUMNS.iter().chain(args))
+        .iter()
+        .skip(1)
+        .map(|line| {
+            line.split_whitespace()
+                .skip(1)
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect()
+}
+
+#[test]
+fn bytes_is_the_default() {
+    let fixture = listing();
+    let dir_size = fs::metadata(fixture.path("dir")).unwrap().len();
+    let expected = [
+        "5000 big".to_string(),
+        format!("{} dir", dir_size),
+        "10 small".to_string(),
+    ];
+    assert_eq!(sizes(&fixture, &[]), expected);
+    assert_eq!(sizes(&fixture, &["--dir-size=bytes"]), expected);
+}
+
+#[test]
+fn dash() {
+    let fixture = listing();
+    assert_eq!(
+        sizes(&fixture, &["--dir-size=dash"]),
+        ["5000 big", "- dir", "10 small"]
+    );
+    assert_eq!(
+        sizes(&fixture, &["--dir-size=dash", "-H"]),
+        ["4.9K big", "- dir", "10 small"]
+    );
+}
+
+#[test]
+fn entries() {
+    let fixture = listing();
+    assert_eq!(
+        sizes(&fixture, &["--dir-size=entries"]),
+        ["5000 big", "3 dir", "10 small"]
+    );
+}
+
+#[test]
+fn size_sort_puts_directories_last_unless_bytes() {
+    let fixture = listing();
+    for mode in ["dash", "entries"] {
+        let lines = sizes(&fixture, &["-S", &format!("--dir-size={}", mode)]);
+        assert_eq!(lines.last().unwrap().split(' ').nth(1), Some("dir"));
+    }
+}
+
+#[test]
+fn unknown_mode_is_refused() {
+    let fixture = listing();
+    let output = fixture.run(["--dir-size=blocks"]);
+    assert_eq!(output.status.code(), Some(2));
+}
@@ -0,0 +1,38 @@
+mod common;
+
+use common::Fixture;
+use std::{fs, os::unix::fs::MetadataExt};
+
+fn operands() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        .file("dir/inside", b"")
+        .file("file", b"0123")
+        .mode("dir", 0o750)
+        .mode("file", 0o644);
+    fixture
+}
+
+#[test]
+fn directories_are_listed_themselves() {
+    let fixture = operands();
+    assert_eq!(fixture.lines(["-1d", "dir"]), ["dir"]);
+    assert_eq!(fixture.lines(["-1d", "file", "dir"]), ["dir", "file"]);
+}
+
+#[test]
+fn long_listing_with_inodes() {
+    let fixture = operands();
+    let lines = fixture.lines(["-ldi", "dir", "file"]);
+    // No total and no heading, just a row for each operand
+    assert_eq!(lines.len(), 2);
+    for (line, name, mode, links) in [
+        (&lines[0], "dir", "drwxr-x---", "2"),
+        (&lines[1], "file", "-rw-r--r--", "1"),
+    ] {
+        let inode = fs::symlink_metadata(fixture.path(name)).unwrap().ino();
+        let cells: Vec<&str> = line.split_whitespace().collect();
+        assert_eq!(cells[..3], [&inode.to_string(), mode, links], "{}", line);
+        assert_eq!(cells.last(), Some(&name), "{}", line);
+    }
+}
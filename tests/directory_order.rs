@@ -0,0 +1,53 @@
+mod common;
+
+use common::Fixture;
+
+fn listing() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        .dir("dir")
+        .sized("big", 300)
+        .sized("small", 3)
+        .file(".hidden", b"");
+    fixture
+}
+
+#[test]
+fn directory_order_is_never_colored() {
+    let fixture = listing();
+    for args in [
+        ["-1f", "--color=always"],
+        ["--color=always", "-1f"],
+        ["-1f", "--color"],
+    ] {
+        let stdout = fixture.stdout(args);
+        assert!(!stdout.contains('\x1b'), "{:?}: {:?}", args, stdout);
+        assert!(stdout.lines().any(|line| line == "dir"), "{:?}", args);
+    }
+    // The same listing is colored without -f
+    assert!(fixture
+        .stdout(["-1", "--color=always"])
+        .contains("\x1b[34;1mdir"));
+}
+
+#[test]
+fn directory_order_shows_everything_unsorted() {
+    let fixture = listing();
+    assert_eq!(fixture.lines(["-1f"]), fixture.lines(["-1aU"]));
+    let mut names = fixture.lines(["-1f"]);
+    names.sort();
+    assert_eq!(names, [".", "..", ".hidden", "big", "dir", "small"]);
+}
+
+#[test]
+fn sort_flag_after_directory_order_still_sorts() {
+    let fixture = listing();
+    assert_eq!(
+        fixture.lines(["-1f", "-S", "--only-files"]),
+        ["big", "small", ".hidden"]
+    );
+    assert_eq!(
+        fixture.lines(["-1", "-S", "-f", "--only-files"]),
+        fixture.lines(["-1aU", "--only-files"])
+    );
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use common::Fixture;
+
+fn project() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        .sized("README.md", 42)
+        .sized("src/main.rs", 1000)
+        .sized("src/util/fmt.rs", 2500)
+        .file("target/junk", b"")
+        .file("say \"hi\"", b"")
+        .file("back\\slash", b"");
+    fixture
+}
+
+// Checks the graph is shaped like DOT, returning its node and edge lines
+fn parse(output: &str) -> (Vec<&str>, Vec<&str>) {
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.first(), Some(&"digraph rs {"));
+    assert_eq!(lines.last(), Some(&"}"));
+    let body = &lines[1..lines.len() - 1];
+    let (nodes, edges): (Vec<&str>, Vec<&str>) =
+        body.iter().partition(|line| line.contains("[label="));
+    for node in &nodes {
+        // Unescaped quotes come in pairs around the label
+        let label = node.split_once("[label=").unwrap().1;
+        let quotes = label.replace("\\\\", "").replace("\\\"", "");
+        assert_eq!(quotes.matches('"').count(), 2, "{}", node);
+        assert!(node.ends_with("];"), "{}", node);
+    }
+    for edge in &edges {
+        assert!(edge.starts_with("  n") && edge.contains(" -> n") && edge.ends_with(';'));
+    }
+    (nodes, edges)
+}
+
+#[test]
+fn every_entry_is_a_node_with_an_edge_from_its_directory() {
+    let fixture = project();
+    let output = fixture.stdout(["--dot"]);
+    let (nodes, edges) = parse(&output);
+    // The root and 9 entries below it, each but the root with one edge in
+    assert_eq!(nodes.len(), 10);
+    assert_eq!(edges.len(), 9);
+    assert_eq!(
+        output,
+        r#"digraph rs {
+  n0 [label=".", shape=box];
+  n1 [label="README.md", shape=ellipse];
+  n2 [label="back\\slash", shape=ellipse];
+  n3 [label="say \"hi\"", shape=ellipse];
+  n4 [label="src", shape=box];
+  n5 [label="main.rs", shape=ellipse];
+  n6 [label="util", shape=box];
+  n7 [label="fmt.rs", shape=ellipse];
+  n8 [label="target", shape=box];
+  n9 [label="junk", shape=ellipse];
+  n0 -> n1;
+  n0 -> n2;
+  n0 -> n3;
+  n0 -> n4;
+  n4 -> n5;
+  n4 -> n6;
+  n6 -> n7;
+  n0 -> n8;
+  n8 -> n9;
+}
+"#
+    );
+}
+
+#[test]
+fn level_and_ignore_limit_the_graph() {
+    let fixture = project();
+    let output = fixture.stdout(["--dot", "--level=1", "-I", "target", "src"]);
+    parse(&output);
+    assert_eq!(
+        output,
+        "digraph rs {\n  n0 [label=\"src\", shape=box];\n  n1 [label=\"main.rs\", \
+         shape=ellipse];\n  n2 [label=\"util\", shape=box];\n  n0 -> n1;\n  n0 -> n2;\n}\n"
+    );
+    let output = fixture.stdout(["--dot", "-I", "target", "-I", "src"]);
+    let (nodes, edges) = parse(&output);
+    assert_eq!((nodes.len(), edges.len()), (4, 3));
+}
+
+#[test]
+fn human_readable_sizes_go_under_the_names() {
+    let fixture = project();
+    let output = fixture.stdout(["--dot", "-H", "src/util"]);
+    assert!(output.contains("  n1 [label=\"fmt.rs\\n2.5K\", shape=ellipse];\n"));
+}
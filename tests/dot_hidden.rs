@@ -0,0 +1,54 @@
+mod common;
+
+use common::Fixture;
+
+fn listing() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        .file("build", b"")
+        .file("notes.txt", b"")
+        .file("src", b"")
+        .file(".hidden", b"build\nmissing\n");
+    fixture
+}
+
+#[test]
+fn names_in_dot_hidden_are_hidden() {
+    let fixture = listing();
+    assert_eq!(fixture.lines(["-1"]), ["notes.txt", "src"]);
+}
+
+#[test]
+fn all_and_almost_all_show_them() {
+    let fixture = listing();
+    assert_eq!(
+        fixture.lines(["-1a"]),
+        [".", "..", ".hidden", "build", "notes.txt", "src"]
+    );
+    assert_eq!(
+        fixture.lines(["-1A"]),
+        [".hidden", "build", "notes.txt", "src"]
+    );
+}
+
+#[test]
+fn no_dot_hidden_ignores_the_file() {
+    let fixture = listing();
+    assert_eq!(
+        fixture.lines(["-1", "--no-dot-hidden"]),
+        ["build", "notes.txt", "src"]
+    );
+}
+
+#[test]
+fn each_directory_reads_its_own_file() {
+    let fixture = listing();
+    fixture
+        .file("sub/build", b"")
+        .file("sub/kept", b"")
+        .file("sub/.hidden", b"kept");
+    assert_eq!(fixture.lines(["-1", "sub"]), ["build"]);
+    // Only exact names, and a directory without one hides nothing
+    fixture.file("other/build", b"").file("other/builds", b"");
+    assert_eq!(fixture.lines(["-1", "other"]), ["build", "builds"]);
+}
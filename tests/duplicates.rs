@@ -0,0 +1,76 @@
+#![cfg(feature = "hash")]
+
+mod common;
+
+use common::Fixture;
+
+#[test]
+fn identical_files_are_grouped() {
+    let fixture = Fixture::new();
+    fixture
+        .file("a", b"same")
+        .file("b", b"other")
+        .file("c", b"same")
+        .file("d", b"unique content");
+    assert_eq!(fixture.lines(["-1", "--duplicates"]), ["[1] a", "[1] c"]);
+    assert_eq!(
+        fixture.lines(["-1", "--duplicates", "--show-all"]),
+        ["[1] a", "[1] c", "b", "d"]
+    );
+}
+
+#[test]
+fn hard_links_are_duplicates() {
+    let fixture = Fixture::new();
+    fixture
+        .file("original", b"linked")
+        .hard_link("original", "link")
+        .file("other", b"linker");
+    assert_eq!(
+        fixture.lines(["-1", "--duplicates"]),
+        ["[1] link", "[1] original"]
+    );
+}
+
+#[test]
+fn same_size_with_different_content_is_not_a_duplicate() {
+    let fixture = Fixture::new();
+    fixture.file("a", b"abc").file("b", b"abd").file("c", b"");
+    assert_eq!(fixture.stdout(["-1", "--duplicates"]).trim(), "");
+}
+
+#[test]
+fn groups_are_numbered_in_listing_order() {
+    let fixture = Fixture::new();
+    fixture
+        .file("a", b"one")
+        .file("b", b"two")
+        .file("c", b"one")
+        .file("d", b"two");
+    assert_eq!(
+        fixture.lines(["-1", "--duplicates"]),
+        ["[1] a", "[1] c", "[2] b", "[2] d"]
+    );
+    assert_eq!(
+        fixture.lines(["-1r", "--duplicates"]),
+        ["[1] d", "[1] b", "[2] c", "[2] a"]
+    );
+}
+
+#[test]
+fn recursion_compares_across_subdirectories() {
+    let fixture = Fixture::new();
+    fixture
+        .file("top", b"nested")
+        .file("one/file", b"nested")
+        .file("two/deeper/file", b"nested")
+        .file("two/unique", b"single");
+    assert_eq!(
+        fixture.lines(["-1R", "--duplicates"]),
+        ["[1] one/file", "[1] top", "[1] two/deeper/file"]
+    );
+    assert_eq!(
+        fixture.stdout(["-R", "--duplicates", "--count-only"]),
+        "3\n"
+    );
+}
@@ -0,0 +1,28 @@
+mod common;
+
+use common::Fixture;
+use std::{ffi::OsStr, fs, os::unix::ffi::OsStrExt};
+
+#[test]
+fn escape_writes_a_newline_as_backslash_n() {
+    let fixture = Fixture::new();
+    fixture.file("new\nline", b"");
+    assert_eq!(fixture.stdout(["-1b"]), "new\\nline\n");
+    assert_eq!(fixture.stdout(["-1", "--escape"]), "new\\nline\n");
+}
+
+#[test]
+fn escape_writes_a_stray_byte_in_octal() {
+    let fixture = Fixture::new();
+    fs::write(fixture.root.join(OsStr::from_bytes(b"bad\x80byte")), b"").unwrap();
+    assert_eq!(fixture.stdout(["-1b"]), "bad\\200byte\n");
+}
+
+#[test]
+fn escape_measures_the_escaped_name() {
+    let fixture = Fixture::new();
+    fixture.file("a\tb", b"").file("plain", b"");
+    // "a\tb" takes four columns, so both fit in 4 + 2 + 5
+    assert_eq!(fixture.stdout(["-b", "-w", "11"]), "a\\tb  plain\n");
+    assert_eq!(fixture.stdout(["-b", "-w", "10"]), "a\\tb\nplain\n");
+}
@@ -0,0 +1,51 @@
+// Flags of features left out of the build are still parsed, and fail with a
+// usage error naming the feature. These only run in such a build, e.g. with
+// `cargo test --no-default-features`.
+mod common;
+
+use common::Fixture;
+
+#[allow(dead_code)]
+fn assert_needs_feature(args: &[&str], flag: &str, feature: &str) {
+    let fixture = Fixture::new();
+    let output = fixture.run(args);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(output.stdout.is_empty());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        format!(
+            "rs: '--{}' is not available, rs was compiled without the '{}' feature\n\
+             Try 'rs --help' for more information.\n",
+            flag, feature
+        )
+    );
+}
+
+#[cfg(not(feature = "icons"))]
+#[test]
+fn icons_need_the_icons_feature() {
+    assert_needs_feature(&["--icons"], "icons", "icons");
+    assert_needs_feature(&["-1", "--emoji"], "emoji", "icons");
+}
+
+#[cfg(not(feature = "hash"))]
+#[test]
+fn duplicates_need_the_hash_feature() {
+    assert_needs_feature(&["-R", "--duplicates"], "duplicates", "hash");
+}
+
+#[cfg(not(feature = "git"))]
+#[test]
+fn git_flags_need_the_git_feature() {
+    assert_needs_feature(&["-l", "--git"], "git", "git");
+    assert_needs_feature(&["--git-ignore"], "git-ignore", "git");
+    assert_needs_feature(&["-l", "--git-time"], "git-time", "git");
+    assert_needs_feature(&["-l", "--git-author"], "git-author", "git");
+    assert_needs_feature(&["--sort=git-time"], "sort=git-time", "git");
+}
+
+#[cfg(not(feature = "selinux"))]
+#[test]
+fn context_needs_the_selinux_feature() {
+    assert_needs_feature(&["-Z"], "context", "selinux");
+}
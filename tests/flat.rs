@@ -0,0 +1,99 @@
+mod common;
+
+use common::Fixture;
+use std::{fs, path::Path};
+
+fn nested() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        .file("top", b"1")
+        .file("a/x", b"12")
+        .file("a/b/y", b"12345")
+        .file("a/b/c/z", b"123")
+        .file("d/w", b"1234")
+        .file(".hidden/q", b"")
+        .file("a/.dot", b"");
+    fixture
+}
+
+// Every path under dir relative to root, like `find`, skipping dotfiles
+// and what's in hidden directories unless asked for, in byte order
+fn walk(root: &Path, dir: &Path, is_all: bool, paths: &mut Vec<String>) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        let name = path.file_name().unwrap().to_str().unwrap();
+        if name.starts_with('.') && !is_all {
+            continue;
+        }
+        paths.push(
+            path.strip_prefix(root)
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .to_string(),
+        );
+        if path.is_dir() {
+            walk(root, &path, is_all, paths);
+        }
+    }
+}
+
+fn reference_walk(root: &Path, is_all: bool) -> Vec<String> {
+    let mut paths = vec![];
+    walk(root, root, is_all, &mut paths);
+    paths.sort();
+    paths
+}
+
+#[test]
+fn flat_matches_a_reference_walk() {
+    let fixture = nested();
+    assert_eq!(
+        fixture.lines(["--flat"]),
+        reference_walk(&fixture.root, false)
+    );
+    assert_eq!(
+        fixture.lines(["--flat", "-R"]),
+        reference_walk(&fixture.root, false)
+    );
+    assert_eq!(
+        fixture.lines(["--flat", "-A"]),
+        reference_walk(&fixture.root, true)
+    );
+    assert_eq!(
+        fixture.lines(["--flat", "a"]),
+        reference_walk(&fixture.path("a"), false)
+    );
+}
+
+#[test]
+fn flat_sorts_across_every_directory() {
+    let fixture = nested();
+    assert_eq!(
+        fixture.lines(["--flat", "-S", "--only-files"]),
+        ["a/b/y", "d/w", "a/b/c/z", "a/x", "top"]
+    );
+    assert_eq!(
+        fixture.lines(["--flat", "-r", "--only-files"]),
+        ["top", "d/w", "a/x", "a/b/y", "a/b/c/z"]
+    );
+}
+
+#[test]
+fn flat_paths_end_with_nul_with_zero() {
+    let fixture = nested();
+    let output = fixture.run(["--flat", "--zero", "--only-files"]);
+    assert_eq!(output.stdout, b"a/b/c/z\0a/b/y\0a/x\0d/w\0top\0");
+}
+
+#[test]
+fn flat_long_rows_end_with_the_path() {
+    let fixture = nested();
+    let lines = fixture.lines(["--flat", "-l", "--only-files"]);
+    assert_eq!(lines.len(), 6);
+    let names: Vec<&str> = lines[1..]
+        .iter()
+        .map(|line| line.split(' ').next_back().unwrap())
+        .collect();
+    assert_eq!(names, ["a/b/c/z", "a/b/y", "a/x", "d/w", "top"]);
+}
@@ -0,0 +1,27 @@
+mod common;
+
+use common::Fixture;
+
+fn filesystem_lines(output: &str) -> usize {
+    output
+        .lines()
+        .filter(|line| line.starts_with("filesystem: "))
+        .count()
+}
+
+#[test]
+fn one_line_per_filesystem_after_the_listing() {
+    let fixture = Fixture::new();
+    fixture.file("d/sub/deep/file", b"");
+
+    let recursive = fixture.stdout(["-R", "--fs-info", "d"]);
+    assert_eq!(filesystem_lines(&recursive), 1);
+    assert!(recursive
+        .lines()
+        .last()
+        .unwrap()
+        .starts_with("filesystem: "));
+
+    let operands = fixture.stdout(["--fs-info", "d", "d/sub", "d/sub/deep/file"]);
+    assert_eq!(filesystem_lines(&operands), 1);
+}
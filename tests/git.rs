@@ -0,0 +1,247 @@
+#![cfg(feature = "git")]
+
+mod common;
+
+use common::Fixture;
+use std::process::Command;
+
+// Runs git in the fixture as the given author, at the given date
+fn git(fixture: &Fixture, author: &str, date: &str, args: &[&str]) {
+    let status = Command::new("git")
+        .args(args)
+        .current_dir(&fixture.root)
+        .env("GIT_AUTHOR_NAME", author)
+        .env("GIT_AUTHOR_EMAIL", "author@example.com")
+        .env("GIT_AUTHOR_DATE", date)
+        .env("GIT_COMMITTER_NAME", author)
+        .env("GIT_COMMITTER_EMAIL", "author@example.com")
+        .env("GIT_COMMITTER_DATE", date)
+        .env_remove("GIT_DIR")
+        .env_remove("GIT_WORK_TREE")
+        .status()
+        .unwrap();
+    assert!(status.success(), "git {:?} failed", args);
+}
+
+// A repository with two commits by different authors, then a change to a
+// committed file, a staged new file and an untracked one
+fn repository() -> Fixture {
+    let fixture = Fixture::new();
+    git(&fixture, "Ann", "2001-02-03T04:05:06Z", &["init", "-q"]);
+    fixture.file("first.txt", b"1\n").file("second.txt", b"2\n");
+    git(&fixture, "Ann", "2001-02-03T04:05:06Z", &["add", "."]);
+    git(
+        &fixture,
+        "Ann",
+        "2001-02-03T04:05:06Z",
+        &["commit", "-q", "-m", "first"],
+    );
+    fixture.file("second.txt", b"2 again\n");
+    git(
+        &fixture,
+        "Bob",
+        "2002-03-04T05:06:07Z",
+        &["add", "second.txt"],
+    );
+    git(
+        &fixture,
+        "Bob",
+        "2002-03-04T05:06:07Z",
+        &["commit", "-q", "-m", "second"],
+    );
+    fixture
+        .file("first.txt", b"1 changed\n")
+        .file("staged.txt", b"")
+        .file("untracked.txt", b"");
+    git(
+        &fixture,
+        "Bob",
+        "2002-03-04T05:06:07Z",
+        &["add", "staged.txt"],
+    );
+    fixture
+}
+
+// The cells after the permissions, links, owner, group, size and date
+fn trailing_cells(line: &str) -> Vec<&str> {
+    line.split_whitespace().skip(7).collect()
+}
+
+#[test]
+fn git_time_and_author_come_from_the_last_commit() {
+    let fixture = repository();
+    let lines = fixture.lines(["-l", "--git-time", "--git-author", "--time-style=long-iso"]);
+    let rows: Vec<Vec<&str>> = lines[1..].iter().map(|line| trailing_cells(line)).collect();
+    assert_eq!(
+        rows,
+        [
+            vec!["2001-02-03", "04:05", "Ann", "first.txt"],
+            vec!["2002-03-04", "05:06", "Bob", "second.txt"],
+            vec!["-", "-", "staged.txt"],
+            vec!["-", "-", "untracked.txt"],
+        ]
+    );
+}
+
+#[test]
+fn git_time_follows_the_time_style() {
+    let fixture = repository();
+    let lines = fixture.lines(["-l", "--git-time", "--time-style=full-iso", "second.txt"]);
+    assert!(
+        lines[0].ends_with(" 2002-03-04 05:06:07.000000000 +0000 second.txt"),
+        "{}",
+        lines[0]
+    );
+}
+
+#[test]
+fn sort_by_git_time_puts_the_latest_commit_first() {
+    let fixture = repository();
+    assert_eq!(
+        fixture.lines(["-1", "--sort=git-time"]),
+        ["second.txt", "first.txt", "staged.txt", "untracked.txt"]
+    );
+}
+
+#[test]
+fn git_column_shows_each_status() {
+    let fixture = repository();
+    let lines = fixture.lines(["-l", "--git"]);
+    let rows: Vec<Vec<&str>> = lines[1..].iter().map(|line| trailing_cells(line)).collect();
+    assert_eq!(
+        rows,
+        [
+            vec!["-M", "first.txt"],
+            vec!["--", "second.txt"],
+            vec!["A-", "staged.txt"],
+            vec!["??", "untracked.txt"],
+        ]
+    );
+    // Operands are looked up in the current directory's repository
+    let lines = fixture.lines(["-l", "--git", "first.txt"]);
+    assert_eq!(trailing_cells(&lines[0]), ["-M", "first.txt"]);
+}
+
+// The repository with an ignored directory, file and dotfile, and an
+// untracked directory
+fn with_ignored() -> Fixture {
+    let fixture = repository();
+    fixture
+        .file(".gitignore", b"build/\n*.log\n.env\n")
+        .file(".env", b"")
+        .dir("build")
+        .file("build/out", b"")
+        .file("debug.log", b"")
+        .dir("new")
+        .file("new/file", b"");
+    fixture
+}
+
+#[test]
+fn git_column_marks_ignored_entries() {
+    let fixture = with_ignored();
+    // The rows of the file, then of each directory
+    let lines = fixture.lines(["-l", "--git", "build", "debug.log", "new"]);
+    let rows: Vec<Vec<&str>> = lines
+        .iter()
+        .map(|line| trailing_cells(line))
+        .filter(|cells| !cells.is_empty())
+        .collect();
+    assert_eq!(
+        rows,
+        [vec!["I", "debug.log"], vec!["I", "out"], vec!["??", "file"]]
+    );
+    let lines = fixture.lines(["-ld", "--git", "build", "new"]);
+    assert_eq!(trailing_cells(&lines[0]), ["I", "build"]);
+    assert_eq!(trailing_cells(&lines[1]), ["??", "new"]);
+}
+
+#[test]
+fn git_column_disappears_outside_a_repository() {
+    let fixture = Fixture::new();
+    fixture.file("file", b"");
+    assert_eq!(fixture.stdout(["-l", "--git"]), fixture.stdout(["-l"]));
+}
+
+#[test]
+fn git_ignore_hides_ignored_entries() {
+    let fixture = with_ignored();
+    assert_eq!(
+        fixture.lines(["-1", "--git-ignore"]),
+        [
+            "first.txt",
+            "new",
+            "second.txt",
+            "staged.txt",
+            "untracked.txt"
+        ]
+    );
+    // Ignored dotfiles stay hidden with -A
+    assert_eq!(
+        fixture.lines(["-1A", "--git-ignore", "-I", "*.txt"]),
+        [".git", ".gitignore", "new"]
+    );
+}
+
+#[test]
+fn git_ignore_prunes_recursion() {
+    let fixture = with_ignored();
+    assert_eq!(
+        fixture.lines(["-1R", "--git-ignore", "-I", "*.txt"]),
+        [".:", "new", "", "./new:", "file"]
+    );
+    assert_eq!(
+        fixture.lines(["--tree", "--git-ignore", "-I", "*.txt"]),
+        [".", "└── new", "    └── file"]
+    );
+}
+
+#[test]
+fn git_ignore_does_nothing_outside_a_repository() {
+    let fixture = Fixture::new();
+    fixture.file(".gitignore", b"*\n").file("file", b"");
+    assert_eq!(
+        fixture.lines(["-1A", "--git-ignore"]),
+        [".gitignore", "file"]
+    );
+}
+
+// git is run for the git fields, and only for them
+#[test]
+fn git_is_only_run_for_git_fields() {
+    let fixture = Fixture::new();
+    // A git that notes each run in the fixture
+    let runs = fixture.path("bin/runs");
+    let script = format!("#!/bin/sh\necho \"$@\" >> '{}'\nexit 1\n", runs.display());
+    fixture
+        .file("bin/git", script.as_bytes())
+        .mode("bin/git", 0o755)
+        .file("listed/file", b"");
+    let run = |args: &[&str]| {
+        let output = fixture
+            .command(args)
+            .env("PATH", fixture.path("bin"))
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", args);
+    };
+    for args in [
+        &["--json", "listed"][..],
+        &["--json-tree", "listed"],
+        &["--csv", "listed"],
+        &["--json", "--fields=name,mtime", "listed"],
+        &["-l", "--git-time", "--fields=name,size", "listed"],
+    ] {
+        run(args);
+        assert!(!runs.exists(), "git ran for {:?}", args);
+    }
+    for args in [
+        &["--json", "--fields=name,git-time", "listed"][..],
+        &["--csv", "--fields=git-author", "listed"],
+        &["-l", "--fields=name,git-time", "listed"],
+    ] {
+        run(args);
+        assert!(runs.exists(), "git didn't run for {:?}", args);
+        std::fs::remove_file(&runs).unwrap();
+    }
+}
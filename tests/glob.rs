@@ -0,0 +1,65 @@
+mod common;
+
+use common::Fixture;
+
+fn tree() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        .file("src/main.rs", b"")
+        .file("src/.hidden.rs", b"")
+        .file("src/a/x.rs", b"")
+        .file("src/a/b/y.rs", b"")
+        .file("src/a/b/z.txt", b"")
+        // A directory whose name the file pattern matches too
+        .dir("lib.rs");
+    fixture
+}
+
+#[test]
+fn recursive_segment_matches_any_depth() {
+    let fixture = tree();
+    assert_eq!(
+        fixture.lines(["--glob", "-1d", "src/**/*.rs"]),
+        ["src/a/b/y.rs", "src/a/x.rs", "src/main.rs"]
+    );
+    assert_eq!(
+        fixture.lines(["--glob", "-1d", "src/**"]),
+        ["src", "src/a", "src/a/b"]
+    );
+}
+
+#[test]
+fn patterns_match_files_and_directories() {
+    let fixture = tree();
+    assert_eq!(
+        fixture.lines(["--glob", "-1d", "**/*.rs"]),
+        ["lib.rs", "src/a/b/y.rs", "src/a/x.rs", "src/main.rs"]
+    );
+    assert_eq!(
+        fixture.lines(["--glob", "-1d", "*/[a-z]*"]),
+        ["src/a", "src/main.rs"]
+    );
+}
+
+#[test]
+fn no_matches_is_an_error_unless_allowed() {
+    let fixture = tree();
+    let output = fixture.run(["--glob", "nothing*"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "rs: nothing*: no matches\n"
+    );
+
+    assert_eq!(
+        fixture.stdout(["--glob", "--glob-empty-ok", "nothing*"]),
+        ""
+    );
+}
+
+#[test]
+fn operands_are_literal_without_the_flag() {
+    let fixture = tree();
+    fixture.file("*.rs", b"");
+    assert_eq!(fixture.lines(["-1d", "*.rs"]), ["*.rs"]);
+}
@@ -0,0 +1,58 @@
+mod common;
+
+use common::Fixture;
+
+fn two_dirs() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        .sized("one/a", 1000)
+        .sized("one/b", 24)
+        .sized("two/c", 3000)
+        .sized("two/deeper/d", 2000);
+    fixture
+}
+
+// The numbers from each "total N" line
+fn section_totals(output: &str) -> Vec<u64> {
+    output
+        .lines()
+        .filter_map(|line| line.strip_prefix("total "))
+        .map(|total| total.parse().unwrap())
+        .collect()
+}
+
+#[test]
+fn apparent_sizes_add_up_over_the_operands() {
+    let fixture = two_dirs();
+    let output = fixture.stdout(["-1", "--grand-total", "--apparent-size", "one", "two"]);
+    let deeper = std::fs::metadata(fixture.path("two/deeper")).unwrap().len();
+    assert_eq!(
+        output.lines().last().unwrap(),
+        format!("grand total: 4 entries, {}", 1000 + 24 + 3000 + deeper)
+    );
+}
+
+#[test]
+fn the_sum_of_the_section_totals() {
+    let fixture = two_dirs();
+    for (args, entries) in [(&["-l", "one", "two"][..], 4), (&["-lR", "."], 7)] {
+        let output = fixture.stdout([args, &["--grand-total"]].concat());
+        let sum: u64 = section_totals(&output).iter().sum();
+        assert_eq!(
+            output.lines().last().unwrap(),
+            format!("grand total: {} entries, {}", entries, sum),
+            "{:?}",
+            args
+        );
+    }
+}
+
+#[test]
+fn human_readable_and_singular() {
+    let fixture = Fixture::new();
+    fixture.sized("only", 1536);
+    assert_eq!(
+        fixture.stdout(["--grand-total", "--apparent-size", "-H"]),
+        "only\ngrand total: 1 entry, 1.5K\n"
+    );
+}
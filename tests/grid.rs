@@ -0,0 +1,122 @@
+mod common;
+
+use common::Fixture;
+
+const NAMES: [&str; 10] = [
+    "alpha", "beta", "gamma", "delta", "epsilon", "zeta", "eta", "theta", "iota", "kappa",
+];
+
+fn names() -> Fixture {
+    let fixture = Fixture::new();
+    for name in NAMES {
+        fixture.file(name, b"");
+    }
+    fixture
+}
+
+#[test]
+fn across_fills_rows_first() {
+    let fixture = names();
+    assert_eq!(
+        fixture.stdout(["-x", "-w", "30"]),
+        "\
+alpha  beta   delta  epsilon
+eta    gamma  iota   kappa
+theta  zeta
+"
+    );
+    assert_eq!(
+        fixture.stdout(["-x", "-w", "20"]),
+        "\
+alpha  beta
+delta  epsilon
+eta    gamma
+iota   kappa
+theta  zeta
+"
+    );
+}
+
+#[test]
+fn down_fills_columns_first() {
+    let fixture = names();
+    assert_eq!(
+        fixture.stdout(["-C", "-w", "30"]),
+        "\
+alpha  epsilon  iota   zeta
+beta   eta      kappa
+delta  gamma    theta
+"
+    );
+    assert_eq!(
+        fixture.stdout(["-C", "-w", "40"]),
+        "\
+alpha  delta    eta    iota   theta
+beta   epsilon  gamma  kappa  zeta
+"
+    );
+}
+
+#[test]
+fn a_row_may_fill_the_width_exactly() {
+    let fixture = names();
+    // 5 + 5 + 5 + 7 + 3 + 5 wide, with five separators of two
+    assert_eq!(
+        fixture.stdout(["-x", "-w", "40"]),
+        "\
+alpha  beta   delta  epsilon  eta  gamma
+iota   kappa  theta  zeta
+"
+    );
+    assert_eq!(
+        fixture.stdout(["-x", "-w", "39"]),
+        "\
+alpha  beta  delta  epsilon  eta
+gamma  iota  kappa  theta    zeta
+"
+    );
+}
+
+#[test]
+fn the_last_of_across_and_down_wins() {
+    let fixture = names();
+    assert_eq!(
+        fixture.stdout(["-Cx", "-w", "30"]),
+        fixture.stdout(["-x", "-w", "30"])
+    );
+    assert_eq!(
+        fixture.stdout(["-xC", "-w", "30"]),
+        fixture.stdout(["-C", "-w", "30"])
+    );
+    assert_eq!(
+        fixture.stdout(["-x", "-w", "30", "-C"]),
+        fixture.stdout(["-C", "-w", "30"])
+    );
+}
+
+#[test]
+fn sorted_order_reads_across_the_rows() {
+    let fixture = names();
+    fixture
+        .sized("kappa", 50)
+        .sized("beta", 40)
+        .sized("zeta", 30)
+        .sized("eta", 20);
+    assert_eq!(
+        fixture.stdout(["-xS", "-w", "30"]),
+        "\
+kappa  beta   zeta     eta
+alpha  delta  epsilon  gamma
+iota   theta
+"
+    );
+}
+
+#[test]
+fn too_narrow_is_one_per_line() {
+    let fixture = names();
+    let mut sorted = NAMES;
+    sorted.sort();
+    assert_eq!(fixture.lines(["-x", "-w", "4"]), sorted);
+    assert_eq!(fixture.lines(["-C", "-w", "4"]), sorted);
+}
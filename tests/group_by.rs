@@ -0,0 +1,71 @@
+mod common;
+
+use common::Fixture;
+
+fn listing() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        .dir("src")
+        .file("a.rs", b"")
+        .file("b.md", b"")
+        .file("Makefile", b"")
+        .file("c.rs", b"")
+        .symlink("a.rs", "link");
+    fixture
+}
+
+#[test]
+fn type_sections() {
+    let fixture = listing();
+    assert_eq!(
+        fixture.stdout(["-1", "--group-by=type"]),
+        "\
+Directories:
+src
+
+Files:
+Makefile
+a.rs
+b.md
+c.rs
+
+Symlinks:
+link
+"
+    );
+}
+
+#[test]
+fn extension_sections_in_the_grid() {
+    let fixture = listing();
+    assert_eq!(
+        fixture.stdout(["-C", "--group-by=extension"]),
+        "\
+Directories:
+src
+
+*.md:
+b.md
+
+*.rs:
+a.rs  c.rs
+
+No extension:
+Makefile  link
+"
+    );
+}
+
+#[test]
+fn sections_follow_the_sort() {
+    let fixture = listing();
+    let lines = fixture.lines(["-1r", "--group-by=type"]);
+    assert_eq!(lines[3..8], ["Files:", "c.rs", "b.md", "a.rs", "Makefile"]);
+}
+
+#[test]
+fn headers_are_dimmed() {
+    let fixture = listing();
+    let lines = fixture.lines(["-1", "--group-by=type", "--color=always"]);
+    assert_eq!(lines[0], "\x1b[2mDirectories:\x1b[0m");
+}
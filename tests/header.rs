@@ -0,0 +1,100 @@
+mod common;
+
+use common::Fixture;
+use std::{fs, os::unix::fs::MetadataExt};
+
+fn listing() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        .file("big", b"0123456789")
+        .file("small", b"0")
+        .mode("big", 0o644)
+        .mode("small", 0o600)
+        .hard_link("small", "twin");
+    fixture
+}
+
+#[test]
+fn header_names_the_inode_column() {
+    let fixture = listing();
+    let lines = fixture.lines(["-li", "--header"]);
+    let header: Vec<&str> = lines[1].split_whitespace().collect();
+    assert_eq!(
+        header,
+        [
+            "Inode",
+            "Permissions",
+            "Links",
+            "Owner",
+            "Group",
+            "Size",
+            "Date",
+            "Name"
+        ]
+    );
+    // The inode is right-aligned under its name
+    let inode = |name| fs::metadata(fixture.path(name)).unwrap().ino().to_string();
+    let width = ["big", "small"]
+        .map(|name| inode(name).len())
+        .into_iter()
+        .fold("Inode".len(), usize::max);
+    assert!(
+        lines[1].starts_with(&format!("{:>width$} ", "Inode")),
+        "{}",
+        lines[1]
+    );
+    assert!(
+        lines[2].starts_with(&format!("{:>width$} -rw-r--r-- ", inode("big"))),
+        "{}",
+        lines[2]
+    );
+    assert_eq!(lines.len(), 5);
+}
+
+#[test]
+fn an_empty_directory_has_no_header() {
+    let fixture = Fixture::new();
+    fixture.dir("empty");
+    assert!(!fixture
+        .stdout(["-li", "--header", "empty"])
+        .contains("Inode"));
+}
+
+#[test]
+fn fields_pick_and_order_the_columns() {
+    let fixture = listing();
+    let lines = fixture.lines(["-l", "--header", "--fields=size,nlink,permissions"]);
+    assert_eq!(
+        lines[1..],
+        [
+            "Size Links Permissions Name",
+            "  10     1 -rw-r--r--  big",
+            "   1     2 -rw-------  small",
+            "   1     2 -rw-------  twin",
+        ]
+    );
+}
+
+#[test]
+fn the_name_stays_last() {
+    let fixture = listing();
+    // Fields without a column of -l, like path, are left out
+    assert_eq!(
+        fixture.lines(["-l", "--header", "--fields=name,path,size", "big"]),
+        ["Size Name", "  10 big"]
+    );
+    // Without --header the rows are the same
+    assert_eq!(
+        fixture.lines(["-l", "--fields=name,nlink,mode", "big"]),
+        ["1 -rw-r--r-- big"]
+    );
+}
+
+#[test]
+fn fields_win_over_the_column_flags() {
+    let fixture = listing();
+    assert_eq!(
+        fixture.lines(["-l", "--no-filesize", "-i", "--fields=size", "big"]),
+        ["10 big"]
+    );
+}
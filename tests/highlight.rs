@@ -0,0 +1,86 @@
+mod common;
+
+use common::Fixture;
+
+const ON: &str = "\x1b[1;7m";
+const OFF: &str = "\x1b[0m";
+
+fn listing() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        .file("conf.d.conf", b"")
+        .file("app.conf", b"")
+        .file("other", b"")
+        .file("x.zip", b"");
+    fixture
+}
+
+#[test]
+fn every_match_is_wrapped_on_its_own() {
+    let fixture = listing();
+    assert_eq!(
+        fixture.lines(["-1", "--color=always", "--highlight", "conf"]),
+        [
+            format!("app.{ON}conf{OFF}"),
+            format!("{ON}conf{OFF}.d.{ON}conf{OFF}"),
+            "other".to_string(),
+            format!("\x1b[31;1mx.zip{OFF}"),
+        ]
+    );
+}
+
+#[test]
+fn the_name_style_resumes_around_a_match() {
+    let fixture = listing();
+    let red = "\x1b[31;1m";
+    assert_eq!(
+        fixture.lines(["-1", "--color=always", "--highlight=zi", "x.zip"]),
+        [format!("{red}x.{OFF}{red}{ON}zi{OFF}{red}p{OFF}")]
+    );
+}
+
+#[test]
+fn case_is_ignored_unless_asked() {
+    let fixture = listing();
+    assert_eq!(
+        fixture.stdout(["-1", "--color=always", "--highlight=CONF", "app.conf"]),
+        format!("app.{ON}conf{OFF}\n")
+    );
+    assert_eq!(
+        fixture.stdout([
+            "-1",
+            "--color=always",
+            "--highlight=CONF",
+            "--highlight-case",
+            "app.conf"
+        ]),
+        "app.conf\n"
+    );
+}
+
+#[test]
+fn highlights_accumulate() {
+    let fixture = listing();
+    assert_eq!(
+        fixture.stdout([
+            "-1",
+            "--color=always",
+            "--highlight=app",
+            "--highlight=conf",
+            "app.conf"
+        ]),
+        format!("{ON}app{OFF}.{ON}conf{OFF}\n")
+    );
+}
+
+#[test]
+fn grid_columns_ignore_the_escapes() {
+    let fixture = listing();
+    assert_eq!(
+        fixture.stdout(["-w", "24", "--color=always", "--highlight", "conf"]),
+        format!(
+            "app.{ON}conf{OFF}     other\n\
+             {ON}conf{OFF}.d.{ON}conf{OFF}  \x1b[31;1mx.zip{OFF}\n"
+        )
+    );
+}
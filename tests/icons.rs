@@ -0,0 +1,52 @@
+#![cfg(feature = "icons")]
+
+mod common;
+
+use common::Fixture;
+
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        .file("a.rs", b"")
+        .file("notes.txt", b"")
+        .file("pic.png", b"")
+        .file("x.tar.gz", b"")
+        .dir("src")
+        .symlink("a.rs", "link");
+    fixture
+}
+
+// Emoji take two columns, and 🖼️ is a one-column symbol widened by a
+// variation selector, so the columns only line up if both are counted
+#[test]
+fn grid_aligns_with_emoji() {
+    let fixture = fixture();
+    fixture.file("run.sh", b"");
+    assert_eq!(
+        fixture.stdout(["-C", "-w", "40", "--emoji"]),
+        "📄 a.rs       🖼️ pic.png  📦 x.tar.gz\n\
+         🔗 link       📄 run.sh\n\
+         📝 notes.txt  📁 src\n"
+    );
+    assert_eq!(
+        fixture.stdout(["-x", "-w", "40", "--emoji"]),
+        "📄 a.rs      🔗 link    📝 notes.txt\n\
+         🖼️ pic.png   📄 run.sh  📁 src\n\
+         📦 x.tar.gz\n"
+    );
+}
+
+#[test]
+fn last_of_emoji_and_icons_wins() {
+    let fixture = fixture();
+    assert_eq!(
+        fixture.lines(["-1", "--icons=always", "--emoji"])[0],
+        "📄 a.rs"
+    );
+    assert_ne!(
+        fixture.lines(["-1", "--emoji", "--icons=always"])[0],
+        "📄 a.rs"
+    );
+    // --icons=never still overrides an earlier --emoji, leaving no icons
+    assert_eq!(fixture.lines(["-1", "--emoji", "--icons=never"])[0], "a.rs");
+}
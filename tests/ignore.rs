@@ -0,0 +1,145 @@
+mod common;
+
+use common::Fixture;
+
+fn build() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        .file("main.c", b"")
+        .file("main.o", b"")
+        .file("util.o", b"")
+        .file("cache.pyc", b"")
+        .file(".env.o", b"")
+        .dir("build");
+    fixture
+}
+
+#[test]
+fn ignore_drops_matching_names() {
+    let fixture = build();
+    assert_eq!(
+        fixture.lines(["-1", "-I", "*.o"]),
+        ["build", "cache.pyc", "main.c"]
+    );
+    // Patterns accumulate, and one that matches nothing is fine
+    assert_eq!(
+        fixture.lines(["-1", "-I", "*.o", "--ignore=*.pyc", "-I", "*.rs"]),
+        ["build", "main.c"]
+    );
+    // Matched against the whole name, with classes and literal dots
+    assert_eq!(
+        fixture.lines(["-1", "-I", "[mu]*.?"]),
+        ["build", "cache.pyc"]
+    );
+    assert_eq!(fixture.lines(["-1", "-I", "main"]), fixture.lines(["-1"]));
+}
+
+#[test]
+fn ignore_applies_even_with_all() {
+    let fixture = build();
+    // A leading dot is only matched by a dot, like the shell
+    assert_eq!(
+        fixture.lines(["-1a", "-I", "*.o", "-I", "."]),
+        ["..", ".env.o", "build", "cache.pyc", "main.c"]
+    );
+    assert_eq!(
+        fixture.lines(["-1A", "-I", ".*"]),
+        ["build", "cache.pyc", "main.c", "main.o", "util.o"]
+    );
+}
+
+#[test]
+fn ignore_matches_the_normalized_name() {
+    let fixture = Fixture::new();
+    fixture.file("cafe\u{301}.txt", b"").file("other", b"");
+    assert_eq!(
+        fixture.lines(["-1", "--normalize=nfc", "-I", "caf\u{e9}*"]),
+        ["other"]
+    );
+    // Either form of the pattern is normalized the same way
+    assert_eq!(
+        fixture.lines(["-1", "--normalize=nfc", "-I", "cafe\u{301}*"]),
+        ["other"]
+    );
+    // Without --normalize the name is matched as stored
+    assert_eq!(
+        fixture.lines(["-1", "-I", "caf\u{e9}*"]),
+        ["cafe\u{301}.txt", "other"]
+    );
+}
+
+#[test]
+fn hide_drops_matching_names_without_all() {
+    let fixture = build();
+    assert_eq!(
+        fixture.lines(["-1", "--hide=*.o"]),
+        ["build", "cache.pyc", "main.c"]
+    );
+    assert_eq!(
+        fixture.lines(["-1", "--hide=*.o", "--hide=*.pyc"]),
+        ["build", "main.c"]
+    );
+}
+
+#[test]
+fn all_and_almost_all_override_hide() {
+    let fixture = build();
+    assert_eq!(
+        fixture.lines(["-1a", "--hide=*.o"]),
+        [
+            ".",
+            "..",
+            ".env.o",
+            "build",
+            "cache.pyc",
+            "main.c",
+            "main.o",
+            "util.o"
+        ]
+    );
+    assert_eq!(
+        fixture.lines(["-1A", "--hide=*.o"]),
+        [".env.o", "build", "cache.pyc", "main.c", "main.o", "util.o"]
+    );
+    // In either order
+    assert_eq!(
+        fixture.lines(["-1", "--hide=*.o", "-A"]),
+        fixture.lines(["-1", "-A", "--hide=*.o"])
+    );
+}
+
+#[test]
+fn ignore_wins_over_all_where_hide_gives_way() {
+    let fixture = build();
+    assert_eq!(
+        fixture.lines(["-1", "-I", "*.pyc", "--hide=*.o"]),
+        ["build", "main.c"]
+    );
+    assert_eq!(
+        fixture.lines(["-1A", "-I", "*.pyc", "--hide=*.o"]),
+        [".env.o", "build", "main.c", "main.o", "util.o"]
+    );
+    // Both naming the same files, -I still drops them with -a
+    assert_eq!(
+        fixture.lines(["-1A", "-I", "main.*", "--hide=main.*"]),
+        [".env.o", "build", "cache.pyc", "util.o"]
+    );
+}
+
+#[test]
+fn hide_matches_the_normalized_name() {
+    let fixture = Fixture::new();
+    fixture.file("cafe\u{301}.txt", b"").file("other", b"");
+    assert_eq!(
+        fixture.lines(["-1", "--normalize=nfc", "--hide=caf\u{e9}*"]),
+        ["other"]
+    );
+    assert_eq!(
+        fixture.lines(["-1", "--normalize=nfc", "--hide=cafe\u{301}*"]),
+        ["other"]
+    );
+    assert_eq!(
+        fixture.lines(["-1A", "--normalize=nfc", "--hide=caf\u{e9}*"]),
+        ["caf\u{e9}.txt", "other"]
+    );
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use common::{json, Fixture};
+use std::{ffi::OsStr, fs, os::unix::ffi::OsStrExt};
+
+#[test]
+fn every_field_is_listed_by_default() {
+    let fixture = Fixture::new();
+    fixture.file("file", b"hello").symlink("file", "link");
+    let entries = json::parse(&fixture.stdout(["--json"]));
+    let entries = entries.items();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(
+        entries[0].keys(),
+        [
+            "name",
+            "path",
+            "type",
+            "size",
+            "blocks",
+            "inode",
+            "nlink",
+            "uid",
+            "gid",
+            "user",
+            "group",
+            "mode",
+            "permissions",
+            "mtime",
+            "atime",
+            "ctime",
+            "target"
+        ]
+    );
+    assert_eq!(entries[0].get("size"), &json::Value::Number(5));
+    assert_eq!(entries[0].get("target"), &json::Value::Null);
+    assert_eq!(entries[1].get("type").str(), "symlink");
+    assert_eq!(entries[1].get("target").str(), "file");
+}
+
+#[test]
+fn fields_pick_and_order_the_members() {
+    let fixture = Fixture::new();
+    fixture.file("file", b"hello").symlink("file", "link");
+    for (fields, keys) in [
+        ("name", &["name"][..]),
+        ("size,name", &["size", "name"]),
+        (
+            "target,nlink,permissions",
+            &["target", "nlink", "permissions"],
+        ),
+    ] {
+        let entries = json::parse(&fixture.stdout(["--json", "--fields", fields]));
+        for entry in entries.items() {
+            assert_eq!(entry.keys(), keys, "{}", fields);
+        }
+    }
+    let entries = json::parse(&fixture.stdout(["--json", "--fields=name,target"]));
+    assert_eq!(
+        entries.items()[1],
+        json::Value::Object(vec![
+            ("name".to_string(), json::Value::String("link".to_string())),
+            (
+                "target".to_string(),
+                json::Value::String("file".to_string())
+            ),
+        ])
+    );
+}
+
+#[test]
+fn names_are_escaped() {
+    let fixture = Fixture::new();
+    fixture
+        .file("say \"hi\"", b"")
+        .file("back\\slash", b"")
+        .file("new\nline", b"")
+        .file("tab\there", b"")
+        .file("esc\x1b", b"");
+    fs::write(fixture.root.join(OsStr::from_bytes(b"caf\xe9")), b"").unwrap();
+    let stdout = fixture.stdout(["--json", "--fields=name"]);
+    for escaped in [
+        r#""say \"hi\"""#,
+        r#""back\\slash""#,
+        r#""new\nline""#,
+        r#""tab\there""#,
+        r#""esc\u001b""#,
+        "\"caf\u{fffd}\"",
+    ] {
+        assert!(stdout.contains(escaped), "{} in {}", escaped, stdout);
+    }
+    let names: Vec<_> = json::parse(&stdout)
+        .items()
+        .iter()
+        .map(|entry| entry.get("name").str().to_string())
+        .collect();
+    assert_eq!(
+        names,
+        [
+            "back\\slash",
+            "caf\u{fffd}",
+            "esc\x1b",
+            "new\nline",
+            "say \"hi\"",
+            "tab\there"
+        ]
+    );
+}
@@ -0,0 +1,95 @@
+mod common;
+
+use common::{json, Fixture};
+
+fn project() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        .file("README.md", b"")
+        .file("src/main.rs", b"")
+        .file("src/util/fmt.rs", b"")
+        .file("src/util/parse.rs", b"")
+        .dir("src/empty")
+        .file("docs/guide.md", b"");
+    fixture
+}
+
+fn names(nodes: &json::Value) -> Vec<&str> {
+    nodes
+        .items()
+        .iter()
+        .map(|node| node.get("name").str())
+        .collect()
+}
+
+// How many directories deep the nodes nest
+fn depth(nodes: &json::Value) -> usize {
+    nodes
+        .items()
+        .iter()
+        .filter(|node| node.keys().contains(&"children"))
+        .map(|node| 1 + depth(node.get("children")))
+        .max()
+        .unwrap_or(0)
+}
+
+#[test]
+fn directories_nest_their_children() {
+    let fixture = project();
+    let roots = json::parse(&fixture.stdout(["--json-tree"]));
+    assert_eq!(roots.items().len(), 1);
+    let root = &roots.items()[0];
+    assert_eq!(root.get("path").str(), ".");
+    assert_eq!(depth(&roots), 3);
+
+    let children = root.get("children");
+    assert_eq!(names(children), ["README.md", "docs", "src"]);
+    let src = &children.items()[2];
+    assert_eq!(names(src.get("children")), ["empty", "main.rs", "util"]);
+    let util = &src.get("children").items()[2];
+    assert_eq!(names(util.get("children")), ["fmt.rs", "parse.rs"]);
+
+    // Directories that were read have children, even none, and files never do
+    let empty = &src.get("children").items()[0];
+    assert_eq!(empty.get("children").items().len(), 0);
+    assert!(!children.items()[0].keys().contains(&"children"));
+}
+
+#[test]
+fn level_limits_the_nesting() {
+    let fixture = project();
+    let roots = json::parse(&fixture.stdout(["--json-tree", "--level", "2"]));
+    assert_eq!(depth(&roots), 2);
+    let src = &roots.items()[0].get("children").items()[2];
+    let util = &src.get("children").items()[2];
+    // Too deep to be read, so it's left without children
+    assert!(!util.keys().contains(&"children"));
+}
+
+#[test]
+fn each_operand_is_a_root() {
+    let fixture = project();
+    let roots = json::parse(&fixture.stdout(["--json-tree", "src/util", "README.md"]));
+    assert_eq!(names(&roots), ["src/util", "README.md"]);
+    assert_eq!(roots.items()[0].get("children").items().len(), 2);
+    assert!(!roots.items()[1].keys().contains(&"children"));
+}
+
+#[test]
+fn unreadable_directories_have_an_error() {
+    let fixture = project();
+    fixture.mode("src/util", 0o000);
+    // Permissions don't keep root out, so there's no error to see
+    if std::fs::read_dir(fixture.path("src/util")).is_ok() {
+        fixture.mode("src/util", 0o755);
+        return;
+    }
+    let output = fixture.stdout(["--json-tree"]);
+    fixture.mode("src/util", 0o755);
+
+    let roots = json::parse(&output);
+    let src = &roots.items()[0].get("children").items()[2];
+    let util = &src.get("children").items()[2];
+    assert!(util.get("error").str().contains("Permission denied"));
+    assert_eq!(util.get("children").items().len(), 0);
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use common::Fixture;
+
+fn sizes() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        .sized("tiny", 1)
+        .sized("huge", 900)
+        .sized("medium-b", 300)
+        .sized("medium-a", 300)
+        .sized("big", 600);
+    fixture
+}
+
+#[test]
+fn keeps_the_biggest_in_descending_order() {
+    let fixture = sizes();
+    assert_eq!(
+        fixture.lines(["-1", "--largest", "3"]),
+        ["huge", "big", "medium-a"]
+    );
+    assert_eq!(
+        fixture.lines(["-1", "--largest", "10"]),
+        ["huge", "big", "medium-a", "medium-b", "tiny"]
+    );
+}
+
+#[test]
+fn ties_at_the_cutoff_go_by_name() {
+    let fixture = sizes();
+    assert_eq!(
+        fixture.lines(["-1", "--largest", "4"]),
+        ["huge", "big", "medium-a", "medium-b"]
+    );
+    assert_eq!(
+        fixture.lines(["-1r", "--largest", "3"]),
+        ["medium-a", "big", "huge"]
+    );
+}
+
+#[test]
+fn recursion_picks_across_the_whole_tree() {
+    let fixture = Fixture::new();
+    fixture
+        .sized("top", 300)
+        .sized("small/a", 10)
+        .sized("small/deep/down/biggest", 1000)
+        .sized("other/mid", 500)
+        .sized("other/less", 200);
+    assert_eq!(
+        fixture.lines(["-1R", "--only-files", "--largest", "3"]),
+        ["small/deep/down/biggest", "other/mid", "top"]
+    );
+    assert_eq!(
+        fixture.stdout(["-R", "--only-files", "--largest", "3", "--count-only"]),
+        "3\n"
+    );
+}
+
+#[test]
+fn total_size_ranks_directories_by_their_contents() {
+    let fixture = Fixture::new();
+    fixture
+        .sized("file", 5000)
+        .sized("dir/one", 4000)
+        .sized("dir/two", 4000);
+    assert_eq!(
+        fixture.lines(["-1", "--total-size", "--largest", "1"]),
+        ["dir"]
+    );
+}
@@ -0,0 +1,40 @@
+mod common;
+
+use common::Fixture;
+
+#[test]
+fn literal_writes_a_tab_as_it_is() {
+    let fixture = Fixture::new();
+    fixture.file("tab\there", b"");
+    assert_eq!(fixture.stdout(["-1N"]), "tab\there\n");
+    assert_eq!(fixture.stdout(["-1", "--literal"]), "tab\there\n");
+    assert_eq!(fixture.stdout(["-1b"]), "tab\\there\n");
+}
+
+#[test]
+fn literal_overrides_other_quoting_flags_in_any_order() {
+    let fixture = Fixture::new();
+    fixture.file("tab\there", b"");
+    for args in [
+        ["-1", "-N", "-Q"],
+        ["-1", "-Q", "-N"],
+        ["-1", "-N", "-b"],
+        ["-1", "-b", "-N"],
+        ["-1", "-N", "-q"],
+        ["-1", "-q", "-N"],
+        ["-1", "-N", "--quoting-style=shell-escape"],
+        ["-1", "--quoting-style=c", "-N"],
+    ] {
+        assert_eq!(fixture.stdout(args), "tab\there\n", "{:?}", args);
+    }
+}
+
+#[test]
+fn literal_colors_the_name_as_stored() {
+    let fixture = Fixture::new();
+    fixture.dir("my\tdir");
+    assert_eq!(
+        fixture.stdout(["-1dN", "--color=always", "my\tdir"]),
+        "\x1b[34;1mmy\tdir\x1b[0m\n"
+    );
+}
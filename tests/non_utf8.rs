@@ -0,0 +1,52 @@
+mod common;
+
+use common::Fixture;
+use std::{ffi::OsStr, fs, os::unix::ffi::OsStrExt};
+
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    fs::write(fixture.root.join(OsStr::from_bytes(b"caf\xe9")), b"").unwrap();
+    fixture.file("plain", b"");
+    fixture
+}
+
+#[test]
+fn non_utf8_names_are_listed() {
+    let fixture = fixture();
+    assert_eq!(fixture.lines(["-1"]), ["caf\u{fffd}", "plain"]);
+    assert_eq!(fixture.lines(["-1q"]), ["caf?", "plain"]);
+    assert_eq!(fixture.lines(["-1b"]), ["caf\\351", "plain"]);
+    let long = fixture.lines(["-l"]);
+    assert_eq!(long.len(), 3);
+    assert!(long[1].ends_with(" caf\u{fffd}"));
+}
+
+#[test]
+fn zero_writes_non_utf8_names_untouched() {
+    let fixture = fixture();
+    let output = fixture.run(["--zero"]);
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"caf\xe9\0plain\0");
+}
+
+#[test]
+fn literal_writes_non_utf8_names_untouched() {
+    let fixture = Fixture::new();
+    fs::write(fixture.root.join(OsStr::from_bytes(b"x\xffy")), b"").unwrap();
+    fixture.file("plain", b"").file("tab\there", b"");
+    for (args, stdout) in [
+        (&["-1N"][..], &b"plain\ntab\there\nx\xffy\n"[..]),
+        (&["-mN"], b"plain, tab\there, x\xffy\n"),
+        (&["-CN", "--width=80"], b"plain  tab\there  x\xffy\n"),
+        (
+            &["--tree", "-N", "-I", "[pt]*"],
+            b".\n\xe2\x94\x94\xe2\x94\x80\xe2\x94\x80 x\xffy\n",
+        ),
+    ] {
+        let output = fixture.run(args);
+        assert!(output.status.success());
+        assert_eq!(output.stdout, stdout, "{:?}", args);
+    }
+    let output = fixture.run(["-lN", "-I", "[pt]*"]);
+    assert!(output.stdout.ends_with(b" x\xffy\n"));
+}
@@ -0,0 +1,59 @@
+mod common;
+
+use common::Fixture;
+
+const NFC: &str = "caf\u{e9}.txt";
+const NFD: &str = "cafe\u{301}.txt";
+
+fn decomposed() -> Fixture {
+    let fixture = Fixture::new();
+    fixture.file(NFD, b"").file("other", b"");
+    fixture
+}
+
+#[test]
+fn names_are_shown_as_asked() {
+    let fixture = decomposed();
+    assert_eq!(fixture.lines(["-1"]), [NFD, "other"]);
+    assert_eq!(fixture.lines(["-1", "--normalize=none"]), [NFD, "other"]);
+    assert_eq!(fixture.lines(["-1", "--normalize=nfc"]), [NFC, "other"]);
+    let composed = Fixture::new();
+    composed.file(NFC, b"");
+    assert_eq!(composed.lines(["-1", "--normalize=nfd"]), [NFD]);
+}
+
+#[test]
+fn regex_matches_the_normalized_name() {
+    let fixture = decomposed();
+    assert_eq!(fixture.stdout(["-1", "--regex", "^caf\u{e9}"]).trim(), "");
+    assert_eq!(
+        fixture.lines(["-1", "--regex", "^caf\u{e9}", "--normalize=nfc"]),
+        [NFC]
+    );
+}
+
+#[test]
+fn paths_keep_the_names_as_read() {
+    let fixture = decomposed();
+    // Reading the file's metadata goes through its name on disk
+    let lines = fixture.lines(["-l", "--normalize=nfc", "--regex", "txt"]);
+    let line = lines.last().unwrap();
+    assert!(line.starts_with("-rw"), "{}", line);
+    assert!(line.ends_with(&format!(" {}", NFC)));
+}
+
+#[test]
+fn widths_count_the_normalized_name() {
+    let fixture = decomposed();
+    fixture.file("z", b"");
+    // Eight columns either way, the combining mark takes none, so the
+    // three names just fit in a row
+    assert_eq!(
+        fixture.stdout(["-x", "-w", "18", "--normalize=nfc"]),
+        format!("{}  other  z\n", NFC)
+    );
+    assert_eq!(
+        fixture.stdout(["-x", "-w", "18"]),
+        format!("{}  other  z\n", NFD)
+    );
+}
@@ -0,0 +1,70 @@
+mod common;
+
+use common::Fixture;
+
+fn mixed() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        .dir("dir")
+        .dir(".hidden-dir")
+        .sized("file", 40_000)
+        .file("backup~", b"")
+        .file(".hidden-file", b"")
+        .symlink("dir", "dir-link")
+        .symlink("file", "file-link")
+        .symlink("nowhere", "dangling");
+    fixture
+}
+
+// The number on the total line of -l
+fn total(fixture: &Fixture, args: &[&str]) -> u64 {
+    let lines = fixture.lines(args);
+    lines[0].strip_prefix("total ").unwrap().parse().unwrap()
+}
+
+#[test]
+fn only_dirs_keeps_directories() {
+    let fixture = mixed();
+    assert_eq!(fixture.lines(["-1D"]), ["dir"]);
+    assert_eq!(fixture.lines(["-1", "--only-dirs"]), ["dir"]);
+    assert_eq!(fixture.lines(["-1AD"]), [".hidden-dir", "dir"]);
+    // With -L a symlink is the type of its target
+    assert_eq!(fixture.lines(["-1DL"]), ["dir", "dir-link"]);
+}
+
+#[test]
+fn only_files_keeps_regular_files() {
+    let fixture = mixed();
+    assert_eq!(fixture.lines(["-1", "--only-files"]), ["backup~", "file"]);
+    assert_eq!(
+        fixture.lines(["-1A", "--only-files"]),
+        [".hidden-file", "backup~", "file"]
+    );
+    // Applied after the other filters
+    assert_eq!(fixture.lines(["-1B", "--only-files"]), ["file"]);
+    assert_eq!(
+        fixture.lines(["-1L", "--only-files"]),
+        ["backup~", "file", "file-link"]
+    );
+}
+
+#[test]
+fn total_and_grid_count_only_what_is_shown() {
+    let fixture = Fixture::new();
+    fixture.dir("dir").dir("other").sized("file", 40_000);
+    assert_eq!(
+        total(&fixture, &["-lD"]) + total(&fixture, &["-l", "--only-files"]),
+        total(&fixture, &["-l"])
+    );
+    assert_eq!(fixture.lines(["-lD"]).len(), 3);
+    // Ten columns fit "dir  other" on one line, but not with file beside them
+    assert_eq!(fixture.stdout(["-D", "-w", "10"]), "dir  other\n");
+}
+
+#[test]
+fn only_dirs_and_only_files_cannot_be_used_together() {
+    let output = Fixture::new().run(["-D", "--only-files"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("'--only-dirs' and '--only-files' can't be used together"));
+}
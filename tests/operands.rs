@@ -0,0 +1,49 @@
+mod common;
+
+use common::Fixture;
+
+fn dirs() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        .file("dir1/file", b"")
+        .file("dir1/.hidden", b"")
+        .file("dir2/other", b"")
+        .file("dir2/.dotfile", b"");
+    fixture
+}
+
+#[test]
+fn flags_may_follow_the_operand() {
+    let fixture = dirs();
+    assert_eq!(
+        fixture.stdout(["dir1", "-l"]),
+        fixture.stdout(["-l", "dir1"])
+    );
+    assert_eq!(fixture.lines(["dir1", "-1"]), ["file"]);
+}
+
+#[test]
+fn flags_between_operands_apply_to_all_of_them() {
+    let fixture = dirs();
+    assert_eq!(
+        fixture.lines(["-1", "dir1", "-a", "dir2"]),
+        ["dir1:", ".", "..", ".hidden", "file", "", "dir2:", ".", "..", ".dotfile", "other"]
+    );
+}
+
+#[test]
+fn repeated_operands_are_listed_once() {
+    let fixture = dirs();
+    assert_eq!(fixture.lines(["-1", "dir1", "dir1"]), ["file"]);
+    assert_eq!(
+        fixture.lines(["-1", "dir2", "dir1", "dir2"]),
+        ["dir2:", "other", "", "dir1:", "file"]
+    );
+}
+
+#[test]
+fn double_dash_ends_the_flags() {
+    let fixture = dirs();
+    fixture.file("-a", b"");
+    assert_eq!(fixture.lines(["-1", "--", "-a"]), ["-a"]);
+}
@@ -0,0 +1,53 @@
+mod common;
+
+use common::Fixture;
+
+fn listing() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        .file("a", b"")
+        .file("b", b"")
+        .file("dir/x", b"")
+        .mtime("a", 0)
+        .mtime("b", 0)
+        .mtime("dir/x", 0);
+    fixture
+}
+
+const COMMAS: &str = "a, b, dir\n";
+const ONE_PER_LINE: &str = "a\nb\ndir\n";
+
+// Whether the output is -l's: a total line, then one row per entry
+fn is_long(stdout: &str) -> bool {
+    let lines: Vec<&str> = stdout.lines().collect();
+    lines[0].starts_with("total ")
+        && lines[1..].len() == 3
+        && lines[1].starts_with("-rw")
+        && lines[1].ends_with(" a")
+        && lines[3].starts_with('d')
+        && lines[3].ends_with(" dir")
+}
+
+#[test]
+fn last_format_flag_wins() {
+    let fixture = listing();
+    assert_eq!(fixture.stdout(["-lm"]), COMMAS);
+    assert_eq!(fixture.stdout(["-l", "-m"]), COMMAS);
+    assert!(is_long(&fixture.stdout(["-ml"])));
+    assert!(is_long(&fixture.stdout(["-m", "-l"])));
+    assert_eq!(fixture.stdout(["-1m"]), COMMAS);
+    assert_eq!(fixture.stdout(["-m1"]), ONE_PER_LINE);
+    assert_eq!(fixture.stdout(["-lm1"]), ONE_PER_LINE);
+    assert!(is_long(&fixture.stdout(["-1ml"])));
+}
+
+#[test]
+fn format_flags_count_wherever_they_are_among_operands() {
+    let fixture = listing();
+    assert_eq!(fixture.stdout(["-l", "dir", "-1"]), "x\n");
+    let long = fixture.lines(["-1", "dir", "-l"]);
+    assert_eq!(long, fixture.lines(["-l", "dir"]));
+    assert_eq!(long[0], "total 0");
+    assert!(long[1].starts_with("-rw-r--r-- 1 "));
+    assert!(long[1].ends_with(" 0 Jan 1 x"));
+}
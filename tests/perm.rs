@@ -0,0 +1,87 @@
+mod common;
+
+use common::Fixture;
+
+fn modes() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        .file("plain", b"")
+        .file("script", b"")
+        .file("setuid", b"")
+        .file("shared", b"")
+        .file("private/open", b"")
+        .mode("plain", 0o644)
+        .mode("script", 0o755)
+        .mode("setuid", 0o4755)
+        .mode("shared", 0o666)
+        .mode("private/open", 0o777)
+        .mode("private", 0o700);
+    fixture
+}
+
+#[test]
+fn exact_modes() {
+    let fixture = modes();
+    assert_eq!(fixture.lines(["-1", "--perm", "644"]), ["plain"]);
+    assert_eq!(fixture.lines(["-1", "--perm", "755"]), ["script"]);
+    assert_eq!(fixture.lines(["-1", "--perm", "4755"]), ["setuid"]);
+}
+
+#[test]
+fn all_of_the_bits() {
+    let fixture = modes();
+    assert_eq!(fixture.lines(["-1", "--perm", "-4000"]), ["setuid"]);
+    assert_eq!(
+        fixture.lines(["-1", "--perm", "-0755"]),
+        ["script", "setuid"]
+    );
+}
+
+#[test]
+fn any_of_the_bits() {
+    let fixture = modes();
+    assert_eq!(
+        fixture.lines(["-1", "--perm", "/0111"]),
+        ["private", "script", "setuid"]
+    );
+    assert_eq!(fixture.lines(["-1", "--perm", "/022"]), ["shared"]);
+}
+
+#[test]
+fn world_writable_files_across_the_tree() {
+    let fixture = modes();
+    // Directories are walked whether they're shown or not
+    assert_eq!(
+        fixture.stdout(["-1R", "--perm", "-0002", "--only-files"]),
+        ".:\nshared\n\n./private:\nopen\n"
+    );
+}
+
+#[test]
+fn invalid_modes_are_refused() {
+    let fixture = modes();
+    for spec in ["9", "-", "/u+w", "77777"] {
+        let output = fixture.run(["--perm", spec]);
+        assert_eq!(output.status.code(), Some(2));
+        assert!(String::from_utf8(output.stderr)
+            .unwrap()
+            .starts_with(&format!("rs: invalid argument '{}' for '--perm'\n", spec)));
+    }
+}
+
+#[test]
+fn symlinks_have_their_own_mode() {
+    let fixture = Fixture::new();
+    fixture
+        .file("file", b"")
+        .file("open", b"")
+        .mode("file", 0o600)
+        .mode("open", 0o777)
+        .symlink("file", "link");
+    // A symlink's mode is 777 on Linux, whatever its target's is
+    assert_eq!(fixture.lines(["-1", "--perm", "777"]), ["link", "open"]);
+    assert_eq!(fixture.lines(["-1", "--perm", "600"]), ["file"]);
+    // -L tests the target instead
+    assert_eq!(fixture.lines(["-1L", "--perm", "777"]), ["open"]);
+    assert_eq!(fixture.lines(["-1L", "--perm", "600"]), ["file", "link"]);
+}
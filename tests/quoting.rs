@@ -0,0 +1,85 @@
+mod common;
+
+use common::Fixture;
+
+fn names() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        .dir("my dir")
+        .file("plain", b"")
+        .file("say \"hi\"", b"")
+        .file("tab\there", b"");
+    fixture
+}
+
+#[test]
+fn quote_name_in_every_layout() {
+    let fixture = names();
+    assert_eq!(
+        fixture.lines(["-1Q"]),
+        [
+            r#""my dir""#,
+            r#""plain""#,
+            r#""say \"hi\"""#,
+            r#""tab\there""#
+        ]
+    );
+    assert_eq!(
+        fixture.stdout(["-Q", "-w", "30"]),
+        "\"my dir\"  \"say \\\"hi\\\"\"\n\"plain\"   \"tab\\there\"\n"
+    );
+    assert_eq!(
+        fixture.stdout(["-Qm"]),
+        "\"my dir\", \"plain\", \"say \\\"hi\\\"\", \"tab\\there\"\n"
+    );
+}
+
+#[test]
+fn quote_name_goes_outside_the_color() {
+    let fixture = names();
+    assert_eq!(
+        fixture.lines(["-1Qd", "--color=always", "my dir", "plain"]),
+        ["\"\x1b[34;1mmy dir\x1b[0m\"", "\"plain\""]
+    );
+}
+
+#[test]
+fn quote_name_keeps_long_columns_aligned() {
+    let fixture = names();
+    let lines = fixture.lines(["-lQ"]);
+    let name_starts: Vec<_> = lines[1..]
+        .iter()
+        .map(|line| line.find('"').unwrap())
+        .collect();
+    assert!(name_starts.windows(2).all(|pair| pair[0] == pair[1]));
+    assert!(lines[4].ends_with(r#""tab\there""#));
+}
+
+#[test]
+fn quoting_style_env_sets_the_default() {
+    let fixture = names();
+    let output = fixture
+        .command(["-1d", "plain", "my dir"])
+        .env("QUOTING_STYLE", "shell-always")
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "'my dir'\n'plain'\n"
+    );
+
+    // A flag wins over it, and a style it doesn't know is ignored
+    let output = fixture
+        .command(["-1d", "-b", "my dir"])
+        .env("QUOTING_STYLE", "c")
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "my\\ dir\n");
+    let output = fixture
+        .command(["-1d", "my dir"])
+        .env("QUOTING_STYLE", "fancy")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "my dir\n");
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use common::Fixture;
+
+// f1 is the oldest and f6 the newest, with f4 and g/tie modified together
+fn staggered() -> Fixture {
+    let fixture = Fixture::new();
+    for n in 1..=6 {
+        let name = format!("f{}", n);
+        fixture.file(&name, b"").mtime(&name, 1_000_000 + n * 1000);
+    }
+    fixture
+        .file("g/old", b"")
+        .file("g/tie", b"")
+        .file("g/h/new", b"")
+        .mtime("g/old", 500_000)
+        .mtime("g/tie", 1_004_000)
+        .mtime("g/h/new", 2_000_000)
+        .mtime("g/h", 100_000)
+        .mtime("g", 100_000);
+    fixture
+}
+
+#[test]
+fn keeps_the_newest_first() {
+    let fixture = staggered();
+    assert_eq!(fixture.lines(["-1", "--recent", "2"]), ["f6", "f5"]);
+    assert_eq!(
+        fixture.lines(["-1", "--recent", "4"]),
+        ["f6", "f5", "f4", "f3"]
+    );
+    // Other sort flags don't change what is picked or its order
+    assert_eq!(fixture.lines(["-1S", "--recent", "2"]), ["f6", "f5"]);
+    assert_eq!(fixture.lines(["-1r", "--recent", "2"]), ["f5", "f6"]);
+}
+
+#[test]
+fn more_than_there_are_keeps_everything() {
+    let fixture = staggered();
+    assert_eq!(
+        fixture.lines(["-1", "--recent", "100"]),
+        ["f6", "f5", "f4", "f3", "f2", "f1", "g"]
+    );
+    assert_eq!(fixture.stdout(["-1", "--recent", "0"]).trim(), "");
+}
+
+#[test]
+fn recursion_picks_across_the_tree() {
+    let fixture = staggered();
+    assert_eq!(
+        fixture.lines(["-1R", "--recent", "3"]),
+        ["g/h/new", "f6", "f5"]
+    );
+    // Ties go by name
+    assert_eq!(
+        fixture.lines(["-1R", "--recent", "5"]),
+        ["g/h/new", "f6", "f5", "f4", "g/tie"]
+    );
+    assert_eq!(
+        fixture.lines(["-1R", "--recent", "100"]),
+        ["g/h/new", "f6", "f5", "f4", "g/tie", "f3", "f2", "f1", "g/old", "g", "g/h"]
+    );
+}
+
+#[test]
+fn recursion_keeps_the_newest_from_every_directory() {
+    // Enough in each directory that the walk has to drop some as it goes
+    let fixture = Fixture::new();
+    for dir in ["a", "b", "c"] {
+        for n in 0..10 {
+            let name = format!("{}/{}", dir, n);
+            let secs = match dir {
+                "b" => 3_000_000 + n,
+                _ => 1_000_000 + n,
+            };
+            fixture.file(&name, b"").mtime(&name, secs);
+        }
+        fixture.mtime(dir, 100_000);
+    }
+    assert_eq!(
+        fixture.lines(["-1R", "--recent", "3"]),
+        ["b/9", "b/8", "b/7"]
+    );
+    assert_eq!(
+        fixture.lines(["-1R", "--recent", "12"])[10..],
+        ["a/9", "c/9"]
+    );
+}
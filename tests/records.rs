@@ -0,0 +1,65 @@
+mod common;
+
+use common::Fixture;
+
+#[test]
+fn header_and_records_follow_the_fields() {
+    let fixture = Fixture::new();
+    fixture
+        .sized("big", 300)
+        .sized("small", 3)
+        .symlink("big", "link");
+    assert_eq!(
+        fixture.lines(["--csv", "--fields=size,name,type"]),
+        [
+            "size,name,type",
+            "300,big,file",
+            "3,link,symlink",
+            "3,small,file"
+        ]
+    );
+    assert_eq!(
+        fixture.lines(["--tsv", "--fields=name,target"]),
+        ["name\ttarget", "big\t", "link\tbig", "small\t"]
+    );
+}
+
+#[test]
+fn default_fields_are_the_record_fields() {
+    let fixture = Fixture::new();
+    fixture
+        .sized("file", 5)
+        .mode("file", 0o640)
+        .mtime("file", 0);
+    let lines = fixture.lines(["--csv"]);
+    assert_eq!(lines[0], "name,type,size,mode,nlink,user,group,mtime");
+    assert!(lines[1].starts_with("file,file,5,0640,1,"));
+    assert!(lines[1].ends_with(",1970-01-01T00:00:00+00:00"));
+}
+
+#[test]
+fn tsv_escapes_names_that_would_break_the_record() {
+    let fixture = Fixture::new();
+    fixture
+        .file("tab\there", b"")
+        .file("new\nline", b"")
+        .file("cr\r", b"")
+        .file("back\\slash", b"");
+    assert_eq!(
+        fixture.stdout(["--tsv", "--fields=name,type"]),
+        "name\ttype\nback\\\\slash\tfile\ncr\\r\tfile\nnew\\nline\tfile\ntab\\there\tfile\n"
+    );
+}
+
+#[test]
+fn csv_quotes_names_as_rfc_4180_has_it() {
+    let fixture = Fixture::new();
+    fixture
+        .file("a,b", b"")
+        .file("say \"hi\"", b"")
+        .file("new\nline", b"");
+    assert_eq!(
+        fixture.stdout(["--csv", "--fields=name"]),
+        "name\n\"a,b\"\n\"new\nline\"\n\"say \"\"hi\"\"\"\n"
+    );
+}
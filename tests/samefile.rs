@@ -0,0 +1,65 @@
+mod common;
+
+use common::Fixture;
+
+// A file with a hard link in another directory, a copy of it and a symlink
+// to it
+fn links() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        .file("a/orig", b"same\n")
+        .dir("b")
+        .hard_link("a/orig", "b/link")
+        .file("copy", b"same\n")
+        .symlink("a/orig", "sym");
+    fixture
+}
+
+#[test]
+fn hard_links_are_found_across_the_tree() {
+    let fixture = links();
+    assert_eq!(
+        fixture.lines(["-1", "--flat", "--samefile", "a/orig"]),
+        ["a/orig", "b/link"]
+    );
+    let names: Vec<String> = fixture
+        .lines(["-1R", "--samefile", "b/link"])
+        .into_iter()
+        .filter(|line| !line.is_empty() && !line.ends_with(':'))
+        .collect();
+    assert_eq!(names, ["orig", "link"]);
+}
+
+#[test]
+fn a_copy_is_not_the_same_file() {
+    let fixture = links();
+    // The same content in another inode
+    assert_eq!(
+        fixture.lines(["-1", "--flat", "--samefile", "copy"]),
+        ["copy"]
+    );
+}
+
+#[test]
+fn a_symlink_reference_is_followed() {
+    let fixture = links();
+    assert_eq!(
+        fixture.lines(["-1", "--flat", "--samefile", "sym"]),
+        ["a/orig", "b/link"]
+    );
+    assert_eq!(
+        fixture.lines(["-1", "--flat", "--samefile", "sym", "--no-dereference-ref"]),
+        ["sym"]
+    );
+}
+
+#[test]
+fn a_missing_reference_is_an_error() {
+    let fixture = links();
+    let output = fixture.run(["--samefile", "missing"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .starts_with("rs: cannot access 'missing'"));
+}
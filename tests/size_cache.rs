@@ -0,0 +1,120 @@
+mod common;
+
+use std::{fs, os::unix::fs::MetadataExt, path::PathBuf};
+
+use common::Fixture;
+
+// d holds 5 bytes itself and 7 more in d/sub
+fn project() -> Fixture {
+    let fixture = Fixture::new();
+    fixture.sized("d/a", 5).sized("d/sub/b", 7);
+    fixture
+}
+
+fn cache_file(fixture: &Fixture) -> PathBuf {
+    fixture.root.with_extension("cache").join("rs/sizes.db")
+}
+
+// A cache line for the directory as it is now, holding the sizes given
+fn cache_line(fixture: &Fixture, relative: &str, files_size: u64, total_size: u64) -> String {
+    let meta = fs::metadata(fixture.path(relative)).unwrap();
+    format!(
+        "{} {} {} {} {} {}\n",
+        meta.dev(),
+        meta.ino(),
+        meta.mtime(),
+        meta.mtime_nsec(),
+        files_size,
+        total_size
+    )
+}
+
+fn write_cache(fixture: &Fixture, lines: &[String]) {
+    let path = cache_file(fixture);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, format!("rs-sizes 1\n{}", lines.concat())).unwrap();
+}
+
+// The size column for d
+fn size_of_d(fixture: &Fixture, args: &[&str]) -> String {
+    let line = fixture.stdout([&["-ld", "--total-size", "d"], args].concat());
+    line.split_whitespace().nth(4).unwrap().to_string()
+}
+
+#[test]
+fn a_miss_walks_and_fills_the_cache() {
+    let fixture = project();
+    assert_eq!(size_of_d(&fixture, &["--cache"]), "12");
+    let contents = fs::read_to_string(cache_file(&fixture)).unwrap();
+    assert!(contents.starts_with("rs-sizes 1\n"));
+    assert!(contents.contains(&cache_line(&fixture, "d", 5, 12)));
+    assert!(contents.contains(&cache_line(&fixture, "d/sub", 7, 7)));
+}
+
+#[test]
+fn a_hit_reuses_the_cached_size() {
+    let fixture = project();
+    write_cache(&fixture, &[cache_line(&fixture, "d", 5, 999)]);
+    assert_eq!(
+        size_of_d(&fixture, &["--cache", "--cache-trust=mtime"]),
+        "999"
+    );
+    // Only read when asked for
+    assert_eq!(size_of_d(&fixture, &[]), "12");
+    assert_eq!(size_of_d(&fixture, &["--cache", "--no-cache"]), "12");
+}
+
+#[test]
+fn a_changed_mtime_invalidates_the_entry() {
+    let fixture = project();
+    write_cache(&fixture, &[cache_line(&fixture, "d", 5, 999)]);
+    fixture.sized("d/c", 3);
+    assert_eq!(
+        size_of_d(&fixture, &["--cache", "--cache-trust=mtime"]),
+        "15"
+    );
+    let contents = fs::read_to_string(cache_file(&fixture)).unwrap();
+    assert!(contents.contains(&cache_line(&fixture, "d", 8, 15)));
+}
+
+#[test]
+fn shallow_trust_still_checks_subdirectories() {
+    let fixture = project();
+    // d itself is unchanged, but d/sub has gained a file since
+    write_cache(
+        &fixture,
+        &[
+            cache_line(&fixture, "d", 5, 12),
+            cache_line(&fixture, "d/sub", 7, 7),
+        ],
+    );
+    fixture.sized("d/sub/c", 3);
+    assert_eq!(
+        size_of_d(&fixture, &["--cache", "--cache-trust=mtime"]),
+        "12"
+    );
+    assert_eq!(
+        size_of_d(&fixture, &["--cache", "--cache-trust=shallow"]),
+        "15"
+    );
+}
+
+#[test]
+fn clear_cache_removes_the_file() {
+    let fixture = project();
+    size_of_d(&fixture, &["--cache"]);
+    assert!(cache_file(&fixture).exists());
+    fixture.stdout(["--clear-cache"]);
+    assert!(!cache_file(&fixture).exists());
+    // Clearing again is fine
+    fixture.stdout(["--clear-cache"]);
+}
+
+#[test]
+fn a_cache_of_another_layout_is_ignored() {
+    let fixture = project();
+    let path = cache_file(&fixture);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(&path, cache_line(&fixture, "d", 5, 999)).unwrap();
+    assert_eq!(size_of_d(&fixture, &["--cache"]), "12");
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use common::Fixture;
+
+// Sizes, times and extensions that each give a different order:
+// by size b.txt, c.md, a.rs; by time c.md, a.rs, b.txt; by extension
+// c.md, a.rs, b.txt
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        .sized("a.rs", 10)
+        .sized("b.txt", 300)
+        .sized("c.md", 20)
+        .mtime("a.rs", 2_000_000)
+        .mtime("b.txt", 1_000_000)
+        .mtime("c.md", 3_000_000);
+    fixture
+}
+
+#[test]
+fn last_sort_flag_wins() {
+    let fixture = fixture();
+    for (args, order) in [
+        (&["-1", "-t", "-S"][..], ["b.txt", "c.md", "a.rs"]),
+        (&["-1", "-S", "-t"], ["c.md", "a.rs", "b.txt"]),
+        (&["-1", "-S", "--sort=time"], ["c.md", "a.rs", "b.txt"]),
+        (&["-1", "--sort=time", "-S"], ["b.txt", "c.md", "a.rs"]),
+        (&["-1", "-t", "-X"], ["c.md", "a.rs", "b.txt"]),
+        (&["-1", "-t", "--sort=name"], ["a.rs", "b.txt", "c.md"]),
+        (&["-1tS"], ["b.txt", "c.md", "a.rs"]),
+    ] {
+        assert_eq!(fixture.lines(args), order, "rs {}", args.join(" "));
+    }
+}
+
+#[test]
+fn reverse_applies_to_whichever_key_wins() {
+    let fixture = fixture();
+    assert_eq!(
+        fixture.lines(["-1", "-r", "-t", "-S"]),
+        ["a.rs", "c.md", "b.txt"]
+    );
+    assert_eq!(
+        fixture.lines(["-1", "-S", "-t", "-r"]),
+        ["b.txt", "a.rs", "c.md"]
+    );
+}
+
+#[test]
+fn directories_first_keeps_the_sort_in_each_group() {
+    let fixture = fixture();
+    fixture
+        .dir("big")
+        .dir("small")
+        .mtime("big", 500_000)
+        .mtime("small", 4_000_000);
+    assert_eq!(
+        fixture.lines(["-1", "-S", "-t", "--group-directories-first"]),
+        ["small", "big", "c.md", "a.rs", "b.txt"]
+    );
+}
+
+#[test]
+fn version_sort_orders_numbers_by_value() {
+    let fixture = Fixture::new();
+    for name in [
+        "file10", "file2", "file1", "v1.10.0", "v1.9.0", "007", "10", "9",
+    ] {
+        fixture.file(name, b"");
+    }
+    let order = [
+        "007", "9", "10", "file1", "file2", "file10", "v1.9.0", "v1.10.0",
+    ];
+    assert_eq!(fixture.lines(["-1v"]), order);
+    assert_eq!(fixture.lines(["-1", "--sort=version"]), order);
+    assert_eq!(
+        fixture.lines(["-1", "-v", "-r"]),
+        order.iter().rev().copied().collect::<Vec<_>>()
+    );
+}
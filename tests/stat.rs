@@ -0,0 +1,99 @@
+mod common;
+
+use common::Fixture;
+
+// Values that depend on the machine or the moment, each replaced by a `#`
+// padded to the same width, so the columns are still checked
+const VOLATILE: [&str; 6] = [
+    "Blocks: ",
+    "IO Block: ",
+    "Device: ",
+    "Inode: ",
+    "Uid: ",
+    "Gid: ",
+];
+
+fn mask(output: &str) -> Vec<String> {
+    let mut masked = vec![];
+    for line in output.lines() {
+        let mut line = line.to_string();
+        for label in VOLATILE {
+            let Some(start) = line.find(label).map(|index| index + label.len()) else {
+                continue;
+            };
+            // Owners are bracketed and padded inside, the rest end at a space
+            let end = match line[start..].starts_with('(') {
+                true => start + line[start..].find(')').unwrap() + 1,
+                false => start + line[start..].find(' ').unwrap_or(line.len() - start),
+            };
+            line.replace_range(start..end, &format!("{:<1$}", "#", end - start));
+        }
+        // The change time is when the test ran, and not every filesystem
+        // keeps a birth time
+        for label in ["Change: ", " Birth: "] {
+            if line.starts_with(label) {
+                line = format!("{}#", label);
+            }
+        }
+        masked.push(line.trim_end().to_string());
+    }
+    masked
+}
+
+fn files() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        .file("hello", b"hello")
+        .mode("hello", 0o640)
+        .mtime("hello", 1_234_567_890)
+        .sized("big", 3000)
+        .mode("big", 0o4755)
+        .mtime("big", 1_000_000_000)
+        .symlink("hello", "link");
+    fixture
+}
+
+#[test]
+fn each_entry_gets_a_block() {
+    let fixture = files();
+    assert_eq!(
+        mask(&fixture.stdout(["--stat", "-H", "hello", "big"])),
+        [
+            "  File: big",
+            "  Size: 3000 (3.0K)     Blocks: #          IO Block: #      regular file",
+            "Device: #               Inode: #           Links: 1",
+            "Access: (4755/-rwsr-xr-x)  Uid: #                  Gid: #",
+            "Access: 2001-09-09 01:46:40.000000000 +0000",
+            "Modify: 2001-09-09 01:46:40.000000000 +0000",
+            "Change: #",
+            " Birth: #",
+            "",
+            "  File: hello",
+            "  Size: 5 (5)           Blocks: #          IO Block: #      regular file",
+            "Device: #               Inode: #           Links: 1",
+            "Access: (0640/-rw-r-----)  Uid: #                  Gid: #",
+            "Access: 2009-02-13 23:31:30.000000000 +0000",
+            "Modify: 2009-02-13 23:31:30.000000000 +0000",
+            "Change: #",
+            " Birth: #",
+        ]
+    );
+}
+
+#[test]
+fn layout_flags_are_ignored_and_filters_apply() {
+    let fixture = files();
+    let stat = fixture.stdout(["--stat", "--only-files"]);
+    assert_eq!(fixture.stdout(["--stat", "-l", "--only-files"]), stat);
+    assert_eq!(fixture.stdout(["--stat", "-C", "--only-files"]), stat);
+    let names: Vec<&str> = stat
+        .lines()
+        .filter_map(|line| line.strip_prefix("  File: "))
+        .collect();
+    assert_eq!(names, ["big", "hello"]);
+    // The link itself is listed in a directory, like -l does
+    let link = fixture.stdout(["--stat", "-I", "big", "-I", "hello"]);
+    assert!(link.contains("  File: link\n"));
+    assert!(link.contains("symbolic link\n"));
+    assert!(link.contains("Access: (0777/lrwxrwxrwx)"));
+}
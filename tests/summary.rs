@@ -0,0 +1,183 @@
+mod common;
+
+use std::{
+    fs,
+    os::unix::fs::{chown, MetadataExt},
+};
+
+use common::Fixture;
+
+fn files() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        .sized("a.rs", 100)
+        .sized("b.rs", 250)
+        .sized("notes.md", 2000)
+        .sized("Makefile", 50)
+        .sized("sub/c.rs", 1000)
+        .sized("sub/deeper/d.md", 48)
+        .sized("sub/deeper/e.txt", 5);
+    fixture
+}
+
+#[test]
+fn counts_and_totals_per_extension() {
+    let fixture = files();
+    let lines = fixture.lines(["--summary-by=extension", "--only-files"]);
+    assert_eq!(
+        lines,
+        [
+            "Count Size Extension",
+            "    1 2000 md",
+            "    2  350 rs",
+            "    1   50 <none>"
+        ]
+    );
+}
+
+#[test]
+fn directories_are_grouped_together() {
+    let fixture = files();
+    let lines = fixture.lines(["--summary-by=extension"]);
+    let dirs = lines.iter().find(|line| line.ends_with(" <dir>")).unwrap();
+    assert!(dirs.starts_with("    1 "));
+}
+
+#[test]
+fn nothing_listed_prints_nothing() {
+    let fixture = Fixture::new();
+    let output = fixture.run(["--summary-by=extension"]);
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"");
+    fixture.dir("empty");
+    assert_eq!(fixture.stdout(["--summary-by=extension", "empty"]), "");
+    assert_eq!(fixture.stdout(["--summary-by=owner", "--only-files"]), "");
+}
+
+#[test]
+fn header_names_the_grouping() {
+    let fixture = files();
+    for (by, title) in [
+        ("extension", "Extension"),
+        ("owner", "Owner"),
+        ("group", "Group"),
+    ] {
+        let lines = fixture.lines(["--summary-by", by]);
+        assert_eq!(lines[0], format!("Count Size {}", title));
+    }
+}
+
+#[test]
+fn human_readable_totals() {
+    let fixture = files();
+    assert_eq!(
+        fixture.lines(["--summary-by=extension", "--only-files", "-H"]),
+        [
+            "Count Size Extension",
+            "    1 2.0K md",
+            "    2  350 rs",
+            "    1   50 <none>"
+        ]
+    );
+}
+
+#[test]
+fn recursion_aggregates_one_table_over_the_tree() {
+    let fixture = files();
+    assert_eq!(
+        fixture.stdout(["-R", "--summary-by=extension", "--only-files"]),
+        "Count Size Extension\n    2 2048 md\n    3 1350 rs\n    1   50 <none>\n    1    5 txt\n"
+    );
+}
+
+#[test]
+fn operands_add_up_in_one_table() {
+    let fixture = files();
+    assert_eq!(
+        fixture.lines([
+            "--summary-by=extension",
+            "--only-files",
+            "sub",
+            "sub/deeper",
+            "a.rs"
+        ]),
+        [
+            "Count Size Extension",
+            "    2 1100 rs",
+            "    1   48 md",
+            "    1    5 txt"
+        ]
+    );
+}
+
+// Owners other than the one running the tests need root to set up
+fn is_root() -> bool {
+    fs::metadata("/proc/self").is_ok_and(|meta| meta.uid() == 0)
+}
+
+fn owned_files() -> Fixture {
+    let fixture = files();
+    chown(fixture.path("notes.md"), Some(65534), Some(65534)).unwrap();
+    chown(fixture.path("sub/c.rs"), Some(54321), Some(54321)).unwrap();
+    chown(fixture.path("sub/deeper/d.md"), Some(54321), Some(0)).unwrap();
+    fixture
+}
+
+#[test]
+fn counts_and_totals_per_owner() {
+    let fixture = files();
+    let user = fixture.stdout(["-1", "--long", "a.rs"]);
+    let user = user.split_whitespace().nth(2).unwrap();
+    assert_eq!(
+        fixture.lines(["--summary-by=owner", "--only-files"]),
+        [
+            "Count Size Owner".to_string(),
+            format!("    4 2400 {}", user)
+        ]
+    );
+    if !is_root() {
+        return;
+    }
+
+    let fixture = owned_files();
+    // An owner without a name goes by the uid
+    assert_eq!(
+        fixture.lines(["--summary-by=owner", "--only-files"]),
+        ["Count Size Owner", "    1 2000 nobody", "    3  400 root"]
+    );
+    assert_eq!(
+        fixture.lines(["-R", "--summary-by=owner", "--only-files", "-H"]),
+        [
+            "Count Size Owner",
+            "    1 2.0K nobody",
+            "    2 1.1K 54321",
+            "    4  405 root"
+        ]
+    );
+    assert_eq!(
+        fixture.lines(["-R", "--summary-by=owner", "--only-files", "-n"]),
+        [
+            "Count Size Owner",
+            "    1 2000 65534",
+            "    2 1048 54321",
+            "    4  405 0"
+        ]
+    );
+}
+
+#[test]
+fn counts_and_totals_per_group() {
+    if !is_root() {
+        return;
+    }
+    let fixture = owned_files();
+    assert_eq!(
+        fixture.lines(["-R", "--summary-by=group", "--only-files"]),
+        [
+            "Count Size Group",
+            "    1 2000 nogroup",
+            "    1 1000 54321",
+            "    5  453 root"
+        ]
+    );
+}
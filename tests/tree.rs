@@ -0,0 +1,68 @@
+mod common;
+
+use common::Fixture;
+
+fn project() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        .file("main.rs", b"")
+        .file("README.md", b"")
+        .file("src/lib.rs", b"")
+        .file("src/util/fmt.rs", b"")
+        .file("src/util/notes.txt", b"")
+        .file("src/nothing/x.txt", b"")
+        .file("docs/guide.md", b"")
+        // A directory that matches, with nothing inside
+        .dir("empty.rs");
+    fixture
+}
+
+#[test]
+fn regex_keeps_the_ancestors_of_matches() {
+    let fixture = project();
+    assert_eq!(
+        fixture.stdout(["--tree", "--regex", "\\.rs$"]),
+        "\
+.
+├── empty.rs
+├── main.rs
+└── src
+    ├── lib.rs
+    └── util
+        └── fmt.rs
+"
+    );
+}
+
+#[test]
+fn prune_drops_matched_directories_left_empty() {
+    let fixture = project();
+    assert_eq!(
+        fixture.stdout(["--tree", "--regex", "\\.rs$", "--prune"]),
+        "\
+.
+├── main.rs
+└── src
+    ├── lib.rs
+    └── util
+        └── fmt.rs
+"
+    );
+}
+
+#[test]
+fn without_a_pattern_everything_is_drawn() {
+    let fixture = project();
+    assert_eq!(
+        fixture.stdout(["--tree", "src"]),
+        "\
+src
+├── lib.rs
+├── nothing
+│   └── x.txt
+└── util
+    ├── fmt.rs
+    └── notes.txt
+"
+    );
+}
@@ -0,0 +1,71 @@
+mod common;
+
+use common::Fixture;
+
+fn project() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        .sized("README.md", 42)
+        .sized("docs/guide.md", 300)
+        .sized("src/main.rs", 1000)
+        .sized("src/util/fmt.rs", 2500)
+        .dir("empty");
+    fixture
+}
+
+#[test]
+fn directories_show_their_subtree_size() {
+    let fixture = project();
+    assert_eq!(
+        fixture.stdout(["--tree", "--total-size"]),
+        "\
+.
+├── [  42]  README.md
+├── [ 300]  docs
+│   └── [ 300]  guide.md
+├── [   0]  empty
+└── [3500]  src
+    ├── [1000]  main.rs
+    └── [2500]  util
+        └── [2500]  fmt.rs
+"
+    );
+}
+
+#[test]
+fn human_readable_sizes_share_the_column() {
+    let fixture = project();
+    assert_eq!(
+        fixture.stdout(["--tree", "--total-size", "-H", "src"]),
+        "\
+src
+├── [1000]  main.rs
+└── [2.5K]  util
+    └── [2.5K]  fmt.rs
+"
+    );
+}
+
+#[test]
+fn cut_directories_keep_their_size_and_a_marker() {
+    let fixture = project();
+    // An empty directory hides nothing, so it isn't marked
+    assert_eq!(
+        fixture.stdout(["--tree", "--total-size", "--level=1"]),
+        "\
+.
+├── [  42]  README.md
+├── [ 300]  docs …
+├── [   0]  empty
+└── [3500]  src …
+"
+    );
+    assert_eq!(
+        fixture.stdout(["--tree", "--total-size", "--regex", "main"]),
+        "\
+.
+└── [3500]  src …
+    └── [1000]  main.rs
+"
+    );
+}